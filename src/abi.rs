@@ -0,0 +1,23 @@
+//! Calling conventions of MSVC compiled code.
+
+/// Expands to an `unsafe extern` function item or pointer type with the
+/// calling convention of MSVC non-static member functions.
+///
+/// It is `thiscall` on 32-bit x86 and the C calling convention everywhere else.
+#[cfg(all(windows, target_arch = "x86"))]
+macro_rules! member_fn {
+    ($($tt:tt)*) => {
+        unsafe extern "thiscall" $($tt)*
+    };
+}
+
+/// Expands to an `unsafe extern` function item or pointer type with the
+/// calling convention of MSVC non-static member functions.
+///
+/// It is `thiscall` on 32-bit x86 and the C calling convention everywhere else.
+#[cfg(not(all(windows, target_arch = "x86")))]
+macro_rules! member_fn {
+    ($($tt:tt)*) => {
+        unsafe extern "C" $($tt)*
+    };
+}
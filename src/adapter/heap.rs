@@ -0,0 +1,302 @@
+//! Heap algorithms of MSVC, `std::push_heap` and friends.
+//!
+//! The algorithms move the elements exactly like MSVC does, so a heap
+//! modified from Rust has the same element order as one modified from C++.
+
+use core::{mem::ManuallyDrop, ptr};
+
+use crate::compare::Compare;
+
+/// Element taken out of a slice, leaving a hole which is filled when dropped.
+struct Hole<'a, T> {
+    data: &'a mut [T],
+    elt: ManuallyDrop<T>,
+    pos: usize,
+}
+
+impl<'a, T> Hole<'a, T> {
+    /// # Safety
+    ///
+    /// `pos` must be in bounds.
+    unsafe fn new(data: &'a mut [T], pos: usize) -> Self {
+        unsafe {
+            let elt = ptr::read(data.get_unchecked(pos));
+
+            Self {
+                data,
+                elt: ManuallyDrop::new(elt),
+                pos,
+            }
+        }
+    }
+
+    fn element(&self) -> &T {
+        &self.elt
+    }
+
+    /// # Safety
+    ///
+    /// `index` must be in bounds and not equal to the hole position.
+    unsafe fn get(&self, index: usize) -> &T {
+        unsafe { self.data.get_unchecked(index) }
+    }
+
+    /// Moves the element at `index` into the hole, leaving the hole at `index`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be in bounds and not equal to the hole position.
+    unsafe fn move_to(&mut self, index: usize) {
+        unsafe {
+            let ptr = self.data.as_mut_ptr();
+            ptr::copy_nonoverlapping(ptr.add(index), ptr.add(self.pos), 1);
+        }
+
+        self.pos = index;
+    }
+}
+
+impl<T> Drop for Hole<'_, T> {
+    fn drop(&mut self) {
+        unsafe {
+            let pos = self.pos;
+            ptr::copy_nonoverlapping(&*self.elt, self.data.get_unchecked_mut(pos), 1);
+        }
+    }
+}
+
+/// Moves the last element of `data` into the heap `data[..len - 1]`, like `std::push_heap`.
+pub fn push_heap<T, C: Compare<T>>(data: &mut [T], comp: &C) {
+    if data.len() >= 2 {
+        let last = data.len() - 1;
+
+        unsafe {
+            push_hole(Hole::new(data, last), 0, comp);
+        }
+    }
+}
+
+/// Moves the largest element of the heap `data` to its end,
+/// leaving a heap in `data[..len - 1]`, like `std::pop_heap`.
+pub fn pop_heap<T, C: Compare<T>>(data: &mut [T], comp: &C) {
+    if data.len() >= 2 {
+        let last = data.len() - 1;
+        data.swap(0, last);
+
+        unsafe {
+            pop_hole(Hole::new(&mut data[..last], 0), comp);
+        }
+    }
+}
+
+/// Arranges `data` into a heap, like `std::make_heap`.
+pub fn make_heap<T, C: Compare<T>>(data: &mut [T], comp: &C) {
+    for hole in (0..data.len() / 2).rev() {
+        unsafe {
+            pop_hole(Hole::new(data, hole), comp);
+        }
+    }
+}
+
+/// Sorts the heap `data` in ascending order, like `std::sort_heap`.
+pub fn sort_heap<T, C: Compare<T>>(data: &mut [T], comp: &C) {
+    for len in (2..=data.len()).rev() {
+        pop_heap(&mut data[..len], comp);
+    }
+}
+
+/// Whether `data` is a heap, like `std::is_heap`.
+pub fn is_heap<T, C: Compare<T>>(data: &[T], comp: &C) -> bool {
+    (1..data.len()).all(|index| !comp.less(&data[(index - 1) / 2], &data[index]))
+}
+
+/// Moves the hole up to `top` until its parent is not less than its element,
+/// `_Push_heap_by_index`.
+fn push_hole<T, C: Compare<T>>(mut hole: Hole<'_, T>, top: usize, comp: &C) {
+    while hole.pos > top {
+        let parent = (hole.pos - 1) / 2;
+
+        unsafe {
+            if !comp.less(hole.get(parent), hole.element()) {
+                break;
+            }
+
+            hole.move_to(parent);
+        }
+    }
+}
+
+/// Moves the hole down to a leaf along the larger children, then back up,
+/// `_Pop_heap_hole_by_index`.
+fn pop_hole<T, C: Compare<T>>(mut hole: Hole<'_, T>, comp: &C) {
+    let bottom = hole.data.len();
+    let top = hole.pos;
+    let max_non_leaf = (bottom - 1) / 2;
+    let mut index = hole.pos;
+
+    while index < max_non_leaf {
+        index = 2 * index + 2;
+
+        unsafe {
+            if comp.less(hole.get(index), hole.get(index - 1)) {
+                index -= 1;
+            }
+
+            hole.move_to(index);
+        }
+    }
+
+    if index == max_non_leaf && bottom.is_multiple_of(2) {
+        unsafe {
+            hole.move_to(bottom - 1);
+        }
+    }
+
+    push_hole(hole, top, comp);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::{Greater, Less};
+
+    /// Orders by the first element only, smallest first, so that the second
+    /// element shows where each of the equal keys moves.
+    struct KeyGreater;
+
+    impl Compare<(u8, u8)> for KeyGreater {
+        fn less(&self, a: &(u8, u8), b: &(u8, u8)) -> bool {
+            a.0 > b.0
+        }
+    }
+
+    // The expected orders were worked out from the heap algorithms in MSVC's <xutility>.
+
+    #[test]
+    fn make_and_sort_heap_with_duplicates() {
+        let mut data = [3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9];
+
+        make_heap(&mut data, &Less::new());
+        assert_eq!(data, [9, 6, 9, 5, 5, 9, 7, 1, 1, 3, 5, 8, 3, 4, 2]);
+        assert!(is_heap(&data, &Less::new()));
+
+        sort_heap(&mut data, &Less::new());
+        assert_eq!(data, [1, 1, 2, 3, 3, 4, 5, 5, 5, 6, 7, 8, 9, 9, 9]);
+    }
+
+    #[test]
+    fn push_and_pop_heap() {
+        let mut data = [5, 3, 8, 1, 8, 9, 2, 7, 3];
+
+        for len in 1..=data.len() {
+            push_heap(&mut data[..len], &Less::new());
+        }
+
+        assert_eq!(data, [9, 8, 8, 7, 3, 5, 2, 1, 3]);
+
+        for len in (data.len() - 2..=data.len()).rev() {
+            pop_heap(&mut data[..len], &Less::new());
+        }
+
+        assert_eq!(data, [7, 3, 5, 1, 2, 3, 8, 8, 9]);
+    }
+
+    #[test]
+    fn custom_comparator_moves_equal_keys_like_msvc() {
+        let mut data = [
+            (2, 0),
+            (1, 1),
+            (2, 2),
+            (0, 3),
+            (1, 4),
+            (2, 5),
+            (0, 6),
+            (1, 7),
+            (0, 8),
+        ];
+
+        make_heap(&mut data[..8], &KeyGreater);
+        assert_eq!(
+            data[..8],
+            [
+                (0, 6),
+                (0, 3),
+                (2, 2),
+                (1, 7),
+                (1, 4),
+                (2, 5),
+                (2, 0),
+                (1, 1)
+            ]
+        );
+
+        push_heap(&mut data, &KeyGreater);
+        assert_eq!(
+            data,
+            [
+                (0, 6),
+                (0, 3),
+                (2, 2),
+                (0, 8),
+                (1, 4),
+                (2, 5),
+                (2, 0),
+                (1, 1),
+                (1, 7)
+            ]
+        );
+
+        pop_heap(&mut data, &KeyGreater);
+        assert_eq!(
+            data,
+            [
+                (0, 3),
+                (0, 8),
+                (2, 2),
+                (1, 1),
+                (1, 4),
+                (2, 5),
+                (2, 0),
+                (1, 7),
+                (0, 6)
+            ]
+        );
+
+        sort_heap(&mut data[..8], &KeyGreater);
+        assert_eq!(
+            data[..8],
+            [
+                (2, 0),
+                (2, 5),
+                (2, 2),
+                (1, 1),
+                (1, 7),
+                (1, 4),
+                (0, 8),
+                (0, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn greater_builds_min_heap() {
+        let mut data = [3, 1, 4, 1, 5];
+
+        make_heap(&mut data, &Greater::new());
+        assert_eq!(data, [1, 1, 4, 3, 5]);
+        assert!(is_heap(&data, &Greater::new()));
+        assert!(!is_heap(&data, &Less::new()));
+    }
+
+    #[test]
+    fn short_slices_are_heaps() {
+        let mut empty: [u8; 0] = [];
+        make_heap(&mut empty, &Less::new());
+        sort_heap(&mut empty, &Less::new());
+
+        let mut one = [1];
+        push_heap(&mut one, &Less::new());
+        pop_heap(&mut one, &Less::new());
+        assert_eq!(one, [1]);
+    }
+}
@@ -0,0 +1,161 @@
+//! Container adapters.
+//!
+//! [`CxxStack`] and [`CxxQueue`] wrap a `std::deque` by default, and
+//! [`CxxPriorityQueue`] wraps a `std::vector`, like their C++ counterparts.
+
+use crate::{
+    alloc::{CxxProxy, WithCxxProxy},
+    deque::{CxxDequeLayout, RawDeque},
+    vec::{CxxVecLayout, RawVec},
+};
+
+pub use priority_queue::{CxxPriorityQueue, CxxPriorityQueueLayout};
+pub use queue::CxxQueue;
+pub use stack::CxxStack;
+
+pub mod heap;
+pub mod priority_queue;
+pub mod queue;
+pub mod stack;
+
+/// Sequence container that [`CxxStack`] can wrap.
+pub trait BackContainer<T>: Extend<T> {
+    type Iter<'a>: DoubleEndedIterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Elements from the front to the back.
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn front(&self) -> Option<&T>;
+
+    fn front_mut(&mut self) -> Option<&mut T>;
+
+    fn back(&self) -> Option<&T>;
+
+    fn back_mut(&mut self) -> Option<&mut T>;
+
+    fn push_back(&mut self, value: T);
+
+    fn pop_back(&mut self) -> Option<T>;
+
+    fn clear(&mut self);
+}
+
+/// Sequence container that [`CxxQueue`] can wrap.
+pub trait FrontContainer<T>: BackContainer<T> {
+    fn pop_front(&mut self) -> Option<T>;
+}
+
+impl<T, A, L> BackContainer<T> for CxxVecLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    type Iter<'a>
+        = core::slice::Iter<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.as_slice().iter()
+    }
+
+    fn front(&self) -> Option<&T> {
+        self.first()
+    }
+
+    fn front_mut(&mut self) -> Option<&mut T> {
+        self.first_mut()
+    }
+
+    fn back(&self) -> Option<&T> {
+        self.last()
+    }
+
+    fn back_mut(&mut self) -> Option<&mut T> {
+        self.last_mut()
+    }
+
+    fn push_back(&mut self, value: T) {
+        self.push(value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        self.pop()
+    }
+
+    fn clear(&mut self) {
+        CxxVecLayout::clear(self);
+    }
+}
+
+impl<T, A, L> BackContainer<T> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    type Iter<'a>
+        = crate::deque::Iter<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn len(&self) -> usize {
+        CxxDequeLayout::len(self)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        CxxDequeLayout::iter(self)
+    }
+
+    fn front(&self) -> Option<&T> {
+        CxxDequeLayout::front(self)
+    }
+
+    fn front_mut(&mut self) -> Option<&mut T> {
+        CxxDequeLayout::front_mut(self)
+    }
+
+    fn back(&self) -> Option<&T> {
+        CxxDequeLayout::back(self)
+    }
+
+    fn back_mut(&mut self) -> Option<&mut T> {
+        CxxDequeLayout::back_mut(self)
+    }
+
+    fn push_back(&mut self, value: T) {
+        CxxDequeLayout::push_back(self, value);
+    }
+
+    fn pop_back(&mut self) -> Option<T> {
+        CxxDequeLayout::pop_back(self)
+    }
+
+    fn clear(&mut self) {
+        CxxDequeLayout::clear(self);
+    }
+}
+
+impl<T, A, L> FrontContainer<T> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn pop_front(&mut self) -> Option<T> {
+        CxxDequeLayout::pop_front(self)
+    }
+}
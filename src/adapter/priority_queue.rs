@@ -0,0 +1,231 @@
+//! `std::priority_queue` over `std::vector`.
+
+use core::fmt;
+
+use crate::{
+    adapter::heap,
+    alloc::{CxxProxy, DefaultAlloc, WithCxxProxy},
+    compare::{Compare, Less},
+    vec::{CxxVec, CxxVecLayout, Layout, RawVec},
+};
+
+pub type CxxPriorityQueue<T, C = Less, A = DefaultAlloc> =
+    CxxPriorityQueueLayout<T, C, A, Layout<A>>;
+
+/// `std::priority_queue<T, std::vector<T>, C>`.
+///
+/// The vector is kept as a heap with the MSVC algorithms, so that the
+/// largest element according to `C` is at the front.
+#[repr(C)]
+pub struct CxxPriorityQueueLayout<T, C, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    c: CxxVecLayout<T, A, L>,
+    comp: C,
+}
+
+impl<T, C: Compare<T>> CxxPriorityQueue<T, C, DefaultAlloc> {
+    pub const fn new(comp: C) -> Self {
+        Self {
+            c: CxxVec::new(),
+            comp,
+        }
+    }
+}
+
+impl<T, C: Compare<T>, A: CxxProxy> CxxPriorityQueue<T, C, A> {
+    pub const fn new_in(comp: C, alloc: A) -> Self {
+        Self {
+            c: CxxVec::new_in(alloc),
+            comp,
+        }
+    }
+}
+
+impl<T, C, A, L> CxxPriorityQueueLayout<T, C, A, L>
+where
+    C: Compare<T>,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Creates a queue from the elements of `c`, arranged into a heap with `std::make_heap`.
+    pub fn from_vec(mut c: CxxVecLayout<T, A, L>, comp: C) -> Self {
+        heap::make_heap(&mut c, &comp);
+        Self { c, comp }
+    }
+
+    /// Returns the underlying heap.
+    pub fn into_vec(self) -> CxxVecLayout<T, A, L> {
+        self.c
+    }
+
+    /// Returns the elements sorted in ascending order with `std::sort_heap`.
+    pub fn into_sorted_vec(mut self) -> CxxVecLayout<T, A, L> {
+        heap::sort_heap(&mut self.c, &self.comp);
+        self.c
+    }
+
+    /// Elements in heap order.
+    pub fn as_slice(&self) -> &[T] {
+        self.c.as_slice()
+    }
+
+    pub fn compare(&self) -> &C {
+        &self.comp
+    }
+
+    pub fn len(&self) -> usize {
+        self.c.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.c.len() == 0
+    }
+
+    /// Returns the largest element, `top`.
+    pub fn peek(&self) -> Option<&T> {
+        self.c.first()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.c.push(value);
+        heap::push_heap(&mut self.c, &self.comp);
+    }
+
+    /// Removes the largest element.
+    pub fn pop(&mut self) -> Option<T> {
+        heap::pop_heap(&mut self.c, &self.comp);
+        self.c.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.c.clear();
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.c.reserve(additional);
+    }
+}
+
+impl<T, C, A, L> fmt::Debug for CxxPriorityQueueLayout<T, C, A, L>
+where
+    T: fmt::Debug,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.c.iter()).finish()
+    }
+}
+
+impl<T, C, A, L> Default for CxxPriorityQueueLayout<T, C, A, L>
+where
+    C: Default,
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn default() -> Self {
+        Self {
+            c: CxxVecLayout::default(),
+            comp: C::default(),
+        }
+    }
+}
+
+impl<T, C, A, L> Clone for CxxPriorityQueueLayout<T, C, A, L>
+where
+    T: Clone,
+    C: Clone,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            c: self.c.clone(),
+            comp: self.comp.clone(),
+        }
+    }
+}
+
+impl<T, C, A, L> Extend<T> for CxxPriorityQueueLayout<T, C, A, L>
+where
+    C: Compare<T>,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|value| self.push(value));
+    }
+}
+
+/// Collects the elements and arranges them into a heap, like the range constructor.
+impl<T, C, A, L> FromIterator<T> for CxxPriorityQueueLayout<T, C, A, L>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut c = CxxVecLayout::default();
+        c.extend(iter);
+        Self::from_vec(c, C::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::Greater;
+
+    // The expected orders were worked out from the heap algorithms in MSVC's <xutility>.
+
+    #[test]
+    fn push_and_pop_keep_msvc_order() {
+        let mut queue = CxxPriorityQueue::new(Less::new());
+        queue.extend([5, 3, 8, 1, 8, 9, 2, 7, 3]);
+
+        assert_eq!(queue.as_slice(), [9, 8, 8, 7, 3, 5, 2, 1, 3]);
+        assert_eq!(queue.peek(), Some(&9));
+
+        assert_eq!(queue.pop(), Some(9));
+        assert_eq!(queue.pop(), Some(8));
+        assert_eq!(queue.pop(), Some(8));
+        assert_eq!(queue.as_slice(), [7, 3, 5, 1, 2, 3]);
+        assert_eq!(queue.len(), 6);
+    }
+
+    #[test]
+    fn from_vec_makes_heap() {
+        let mut vec = CxxVec::new();
+        vec.extend([3, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9]);
+
+        let queue = CxxPriorityQueue::from_vec(vec, Less::new());
+        assert_eq!(
+            queue.as_slice(),
+            [9, 6, 9, 5, 5, 9, 7, 1, 1, 3, 5, 8, 3, 4, 2]
+        );
+
+        let sorted = queue.into_sorted_vec();
+        assert_eq!(
+            sorted.as_slice(),
+            [1, 1, 2, 3, 3, 4, 5, 5, 5, 6, 7, 8, 9, 9, 9]
+        );
+    }
+
+    #[test]
+    fn greater_pops_smallest_first() {
+        let mut queue: CxxPriorityQueue<i32, Greater> = [3, 1, 4, 1, 5].into_iter().collect();
+        assert_eq!(queue.as_slice(), [1, 1, 4, 3, 5]);
+
+        let mut popped = [0; 5];
+        popped.fill_with(|| queue.pop().unwrap());
+
+        assert_eq!(popped, [1, 1, 3, 4, 5]);
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+}
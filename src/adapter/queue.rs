@@ -0,0 +1,206 @@
+//! `std::queue`.
+
+use core::{fmt, marker::PhantomData};
+
+use crate::{
+    adapter::FrontContainer,
+    alloc::{CxxProxy, DefaultAlloc},
+    deque::CxxDeque,
+};
+
+/// `std::queue<T, C>`, over a `std::deque` by default.
+///
+/// Elements are pushed to the back of the container and popped from its front.
+#[repr(C)]
+pub struct CxxQueue<T, C = CxxDeque<T, DefaultAlloc>>
+where
+    C: FrontContainer<T>,
+{
+    c: C,
+    _marker: PhantomData<T>,
+}
+
+impl<T> CxxQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            c: CxxDeque::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, A: CxxProxy> CxxQueue<T, CxxDeque<T, A>> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            c: CxxDeque::new_in(alloc),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, C: FrontContainer<T>> CxxQueue<T, C> {
+    /// Creates a queue with the front of `c` as its front.
+    pub const fn from_container(c: C) -> Self {
+        Self {
+            c,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_container(self) -> C {
+        self.c
+    }
+
+    pub fn container(&self) -> &C {
+        &self.c
+    }
+
+    /// Elements from the front to the back.
+    pub fn iter(&self) -> C::Iter<'_> {
+        self.c.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.c.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.c.is_empty()
+    }
+
+    /// Returns the element that is popped next, `front`.
+    pub fn front(&self) -> Option<&T> {
+        self.c.front()
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.c.front_mut()
+    }
+
+    /// Returns the element that was pushed last, `back`.
+    pub fn back(&self) -> Option<&T> {
+        self.c.back()
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.c.back_mut()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.c.push_back(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.c.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.c.clear();
+    }
+}
+
+impl<T, C> fmt::Debug for CxxQueue<T, C>
+where
+    T: fmt::Debug,
+    C: FrontContainer<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.c.iter()).finish()
+    }
+}
+
+impl<T, C> Default for CxxQueue<T, C>
+where
+    C: FrontContainer<T> + Default,
+{
+    fn default() -> Self {
+        Self::from_container(C::default())
+    }
+}
+
+impl<T, C> Clone for CxxQueue<T, C>
+where
+    C: FrontContainer<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self::from_container(self.c.clone())
+    }
+}
+
+impl<T, C1, C2> PartialEq<CxxQueue<T, C2>> for CxxQueue<T, C1>
+where
+    T: PartialEq,
+    C1: FrontContainer<T>,
+    C2: FrontContainer<T>,
+{
+    fn eq(&self, other: &CxxQueue<T, C2>) -> bool {
+        self.c.len() == other.c.len() && self.c.iter().eq(other.c.iter())
+    }
+}
+
+impl<T, C> Eq for CxxQueue<T, C>
+where
+    T: Eq,
+    C: FrontContainer<T>,
+{
+}
+
+impl<T, C> Extend<T> for CxxQueue<T, C>
+where
+    C: FrontContainer<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.c.extend(iter);
+    }
+}
+
+/// Pushes the elements in order, leaving the first one in front.
+impl<T, C> FromIterator<T> for CxxQueue<T, C>
+where
+    C: FrontContainer<T> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut c = C::default();
+        c.extend(iter);
+        Self::from_container(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::{format, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn first_in_first_out() {
+        let mut queue: CxxQueue<u8> = (0..30).collect();
+
+        assert_eq!(queue.front(), Some(&0));
+        assert_eq!(queue.back(), Some(&29));
+
+        let popped: Vec<_> = (0..10).map_while(|_| queue.pop()).collect();
+        assert_eq!(popped, (0..10).collect::<Vec<_>>());
+
+        queue.extend(30..40);
+        assert_eq!(queue.len(), 30);
+        assert!(queue.iter().copied().eq(10..40));
+    }
+
+    #[test]
+    fn clone_and_format() {
+        let mut queue = CxxQueue::new();
+        queue.push(1);
+        queue.push(2);
+        queue.pop();
+        queue.push(3);
+
+        let clone = queue.clone();
+        assert_eq!(clone, queue);
+        assert_eq!(format!("{clone:?}"), "[2, 3]");
+
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+}
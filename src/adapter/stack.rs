@@ -0,0 +1,204 @@
+//! `std::stack`.
+
+use core::{fmt, marker::PhantomData};
+
+use crate::{
+    adapter::BackContainer,
+    alloc::{CxxProxy, DefaultAlloc, WithCxxProxy},
+    deque::CxxDeque,
+    vec::{CxxVecLayout, RawVec},
+};
+
+/// `std::stack<T, C>`, over a `std::deque` by default.
+///
+/// The top of the stack is the back of the container.
+#[repr(C)]
+pub struct CxxStack<T, C = CxxDeque<T, DefaultAlloc>>
+where
+    C: BackContainer<T>,
+{
+    c: C,
+    _marker: PhantomData<T>,
+}
+
+impl<T> CxxStack<T> {
+    pub fn new() -> Self {
+        Self {
+            c: CxxDeque::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, A: CxxProxy> CxxStack<T, CxxDeque<T, A>> {
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            c: CxxDeque::new_in(alloc),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, C: BackContainer<T>> CxxStack<T, C> {
+    /// Creates a stack with the back of `c` on top.
+    pub const fn from_container(c: C) -> Self {
+        Self {
+            c,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn into_container(self) -> C {
+        self.c
+    }
+
+    pub fn container(&self) -> &C {
+        &self.c
+    }
+
+    /// Elements from the bottom to the top.
+    pub fn iter(&self) -> C::Iter<'_> {
+        self.c.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.c.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.c.is_empty()
+    }
+
+    /// Returns the top element, `top`.
+    pub fn peek(&self) -> Option<&T> {
+        self.c.back()
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.c.back_mut()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.c.push_back(value);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.c.pop_back()
+    }
+
+    pub fn clear(&mut self) {
+        self.c.clear();
+    }
+}
+
+impl<T, A, L> CxxStack<T, CxxVecLayout<T, A, L>>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Elements from the bottom to the top.
+    pub fn as_slice(&self) -> &[T] {
+        self.c.as_slice()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.c.reserve(additional);
+    }
+}
+
+impl<T, C> fmt::Debug for CxxStack<T, C>
+where
+    T: fmt::Debug,
+    C: BackContainer<T>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.c.iter()).finish()
+    }
+}
+
+impl<T, C> Default for CxxStack<T, C>
+where
+    C: BackContainer<T> + Default,
+{
+    fn default() -> Self {
+        Self::from_container(C::default())
+    }
+}
+
+impl<T, C> Clone for CxxStack<T, C>
+where
+    C: BackContainer<T> + Clone,
+{
+    fn clone(&self) -> Self {
+        Self::from_container(self.c.clone())
+    }
+}
+
+impl<T, C1, C2> PartialEq<CxxStack<T, C2>> for CxxStack<T, C1>
+where
+    T: PartialEq,
+    C1: BackContainer<T>,
+    C2: BackContainer<T>,
+{
+    fn eq(&self, other: &CxxStack<T, C2>) -> bool {
+        self.c.len() == other.c.len() && self.c.iter().eq(other.c.iter())
+    }
+}
+
+impl<T, C> Eq for CxxStack<T, C>
+where
+    T: Eq,
+    C: BackContainer<T>,
+{
+}
+
+impl<T, C> Extend<T> for CxxStack<T, C>
+where
+    C: BackContainer<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.c.extend(iter);
+    }
+}
+
+/// Pushes the elements in order, leaving the last one on top.
+impl<T, C> FromIterator<T> for CxxStack<T, C>
+where
+    C: BackContainer<T> + Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut c = C::default();
+        c.extend(iter);
+        Self::from_container(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec::CxxVec;
+
+    #[test]
+    fn last_in_first_out() {
+        let mut stack = CxxStack::new();
+        stack.extend(0..20);
+
+        assert_eq!(stack.peek(), Some(&19));
+        assert_eq!(stack.pop(), Some(19));
+
+        *stack.peek_mut().unwrap() = 100;
+        assert_eq!(stack.pop(), Some(100));
+        assert_eq!(stack.len(), 18);
+        assert!(stack.iter().copied().eq(0..18));
+    }
+
+    #[test]
+    fn compare_across_containers() {
+        let deque: CxxStack<i32> = (0..5).collect();
+        let vec: CxxStack<i32, CxxVec<i32>> = (0..5).collect();
+
+        assert_eq!(deque, vec);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3, 4]);
+        assert_ne!(deque, CxxStack::<i32>::new());
+    }
+}
@@ -0,0 +1,268 @@
+//! Bump allocator for short-lived containers.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::{Cell, RefCell},
+    fmt, ptr,
+};
+
+use rust_alloc::vec::Vec;
+
+use super::{CxxProxy, DEFAULT_ALLOC};
+
+/// Alignment of the chunks of an [`Arena`].
+const CHUNK_ALIGN: usize = 16;
+
+/// Default size of the first chunk of an [`Arena`].
+const DEFAULT_CHUNK_SIZE: usize = 4096;
+
+/// Bump allocator that frees memory all at once.
+///
+/// A shared reference to an arena is a [`CxxProxy`], so containers that
+/// allocate from an arena borrow it and cannot outlive it or a [`Arena::reset`].
+/// Frees are no-ops. Memory is taken from [`DefaultAlloc`](super::DefaultAlloc)
+/// in chunks that double in size.
+pub struct Arena {
+    chunks: RefCell<Vec<(*mut u8, Layout)>>,
+    cursor: Cell<*mut u8>,
+    end: Cell<*mut u8>,
+    allocated: Cell<usize>,
+}
+
+/// Allocator handed out by the [`CxxProxy`] of an [`Arena`].
+#[derive(Clone, Copy)]
+struct ArenaProxy<'a> {
+    arena: &'a Arena,
+}
+
+impl Arena {
+    pub const fn new() -> Self {
+        Self {
+            chunks: RefCell::new(Vec::new()),
+            cursor: Cell::new(ptr::null_mut()),
+            end: Cell::new(ptr::null_mut()),
+            allocated: Cell::new(0),
+        }
+    }
+
+    /// Creates an arena with a first chunk of at least `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let arena = Self::new();
+
+        if capacity > 0 {
+            arena.grow(capacity);
+        }
+
+        arena
+    }
+
+    /// Bytes handed out since the arena was created or reset.
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated.get()
+    }
+
+    /// Bytes reserved from the system.
+    pub fn capacity(&self) -> usize {
+        self.chunks.borrow().iter().map(|(_, l)| l.size()).sum()
+    }
+
+    /// Frees everything allocated from the arena, keeping the largest chunk for reuse.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+
+        if let Some(last) = chunks.pop() {
+            for (chunk, layout) in chunks.drain(..) {
+                unsafe {
+                    DEFAULT_ALLOC.dealloc(chunk, layout);
+                }
+            }
+
+            self.cursor.set(last.0);
+            self.end.set(unsafe { last.0.add(last.1.size()) });
+            chunks.push(last);
+        }
+
+        self.allocated.set(0);
+    }
+
+    fn bump_alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = match self.bump(layout) {
+            Some(ptr) => ptr,
+            None => {
+                self.grow(layout.size().saturating_add(layout.align()));
+
+                match self.bump(layout) {
+                    Some(ptr) => ptr,
+                    None => return ptr::null_mut(),
+                }
+            }
+        };
+
+        self.allocated.set(self.allocated.get() + layout.size());
+        ptr
+    }
+
+    /// Allocates from the current chunk, if `layout` fits.
+    fn bump(&self, layout: Layout) -> Option<*mut u8> {
+        let cursor = self.cursor.get();
+
+        if cursor.is_null() {
+            return None;
+        }
+
+        let padding = cursor.align_offset(layout.align());
+        let available = self.end.get() as usize - cursor as usize;
+
+        if padding.checked_add(layout.size())? > available {
+            return None;
+        }
+
+        unsafe {
+            let start = cursor.add(padding);
+            self.cursor.set(start.add(layout.size()));
+            Some(start)
+        }
+    }
+
+    /// Adds a chunk of at least `min_size` bytes, and at least twice the size of the last one.
+    fn grow(&self, min_size: usize) {
+        let mut chunks = self.chunks.borrow_mut();
+
+        let last_size = chunks
+            .last()
+            .map_or(DEFAULT_CHUNK_SIZE / 2, |(_, l)| l.size());
+        let size = min_size.max(last_size.saturating_mul(2));
+
+        let Ok(layout) = Layout::from_size_align(size, CHUNK_ALIGN) else {
+            return;
+        };
+
+        let chunk = unsafe { DEFAULT_ALLOC.alloc(layout) };
+
+        if !chunk.is_null() {
+            chunks.push((chunk, layout));
+            self.cursor.set(chunk);
+            self.end.set(unsafe { chunk.add(size) });
+        }
+    }
+}
+
+impl CxxProxy for &Arena {
+    fn proxy<'b>(&self) -> impl GlobalAlloc + 'b
+    where
+        Self: 'b,
+    {
+        ArenaProxy { arena: self }
+    }
+
+    /// Allocators are equal if they allocate from the same arena.
+    fn is_equal(&self, other: &Self) -> bool {
+        ptr::eq(*self, *other)
+    }
+}
+
+/// Frees are no-ops.
+unsafe impl GlobalAlloc for ArenaProxy<'_> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.arena.bump_alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for (chunk, layout) in self.chunks.get_mut().drain(..) {
+            unsafe {
+                DEFAULT_ALLOC.dealloc(chunk, layout);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec::CxxVec;
+
+    fn alloc(arena: &Arena, size: usize, align: usize) -> *mut u8 {
+        arena.bump_alloc(Layout::from_size_align(size, align).unwrap())
+    }
+
+    #[test]
+    fn bumps_within_chunk() {
+        let arena = Arena::new();
+        assert_eq!(arena.capacity(), 0);
+
+        let a = alloc(&arena, 3, 1);
+        let b = alloc(&arena, 8, 8);
+        let c = alloc(&arena, 1, 1);
+
+        assert_eq!(a as usize % CHUNK_ALIGN, 0);
+        assert_eq!(b as usize, a as usize + 8);
+        assert_eq!(c as usize, b as usize + 8);
+        assert_eq!(arena.allocated_bytes(), 12);
+        assert_eq!(arena.capacity(), DEFAULT_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn grows_by_doubling() {
+        let arena = Arena::with_capacity(100);
+        assert_eq!(arena.capacity(), DEFAULT_CHUNK_SIZE);
+
+        alloc(&arena, 1, 1);
+        alloc(&arena, DEFAULT_CHUNK_SIZE, 1);
+        assert_eq!(arena.capacity(), DEFAULT_CHUNK_SIZE * 3);
+
+        // Chunks are at least as large as the allocation that needs them.
+        alloc(&arena, DEFAULT_CHUNK_SIZE * 10, 16);
+        assert_eq!(
+            arena.capacity(),
+            DEFAULT_CHUNK_SIZE * 3 + DEFAULT_CHUNK_SIZE * 10 + 16
+        );
+        assert_eq!(arena.allocated_bytes(), 1 + DEFAULT_CHUNK_SIZE * 11);
+    }
+
+    #[test]
+    fn reset_keeps_last_chunk() {
+        let mut arena = Arena::new();
+
+        alloc(&arena, 16, 1);
+        let big = alloc(&arena, DEFAULT_CHUNK_SIZE, 1);
+        assert_eq!(arena.capacity(), DEFAULT_CHUNK_SIZE * 3);
+
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), 0);
+        assert_eq!(arena.capacity(), DEFAULT_CHUNK_SIZE * 2);
+        assert_eq!(alloc(&arena, 16, 1), big);
+    }
+
+    #[test]
+    fn frees_are_no_ops() {
+        let arena = Arena::new();
+
+        let mut vec = CxxVec::new_in(&arena);
+        vec.extend(0..100u32);
+        let bytes = arena.allocated_bytes();
+        drop(vec);
+
+        assert!(bytes >= 400);
+        assert_eq!(arena.allocated_bytes(), bytes);
+        assert_ne!(alloc(&arena, 1, 1), ptr::null_mut());
+    }
+}
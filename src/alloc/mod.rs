@@ -0,0 +1,1111 @@
+//! C++ allocation interface.
+//!
+//! Types that implement [`CxxProxy`] can be used as C++ compatible allocators.
+//! Containers that are not given one use [`DefaultAlloc`]. Other [`GlobalAlloc`]
+//! allocators implement [`CxxProxy`] themselves, which tells when two of them
+//! are equal. Allocators can be shared without cloning them through a
+//! [`SharedAlloc`] over `&A`, `Rc<A>` or `Arc<A>`.
+//!
+//! Containers spawn a proxy of their allocator for every operation.
+//! To spawn it once for a batch of short-lived containers, use [`CxxProxy::batch`]:
+//!
+//! ```
+//! use cxx_stl::{
+//!     alloc::{CxxProxy, TrackingAlloc},
+//!     vec::CxxVec,
+//! };
+//!
+//! let alloc = TrackingAlloc::new(std::alloc::System);
+//!
+//! alloc.batch(|alloc| {
+//!     let mut vec = CxxVec::new_in(alloc);
+//!     vec.extend(0..100);
+//! });
+//!
+//! assert_eq!(alloc.stats().allocs, 1);
+//! ```
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
+    fmt,
+    marker::PhantomData,
+    mem,
+    ops::Deref,
+    ptr::{self, NonNull},
+};
+#[cfg(all(feature = "std", not(feature = "cxx-new-alloc")))]
+use std::alloc::System as SysAlloc;
+
+use cstl_sys::CSTL_Alloc;
+
+pub use arena::Arena;
+pub use new::{CrtHeap, CxxNewAlloc, NewHeap};
+#[cfg(feature = "nightly")]
+pub use nightly::{AllocatorProxy, CxxAllocator};
+pub use scoped::{ScopedAlloc, UsesAllocator};
+#[cfg(feature = "std")]
+pub use tracking::{AllocStats, Allocation, Checkpoint, LeakReport, TrackingAlloc, TrackingError};
+
+pub mod arena;
+pub mod new;
+#[cfg(feature = "nightly")]
+pub mod nightly;
+pub mod scoped;
+#[cfg(feature = "std")]
+pub mod tracking;
+
+/// Allocator of containers that are not given one.
+///
+/// `System` with the `std` feature, and [`CxxNewAlloc`] on top of the C runtime
+/// without it or with the `cxx-new-alloc` feature.
+#[cfg(all(feature = "std", not(feature = "cxx-new-alloc")))]
+pub type DefaultAlloc = SysAlloc;
+
+/// Allocator of containers that are not given one.
+///
+/// `System` with the `std` feature, and [`CxxNewAlloc`] on top of the C runtime
+/// without it or with the `cxx-new-alloc` feature. [`CrtHeap`] links to `malloc`
+/// and `free` of the C runtime directly.
+#[cfg(any(not(feature = "std"), feature = "cxx-new-alloc"))]
+pub type DefaultAlloc = CxxNewAlloc;
+
+/// The [`DefaultAlloc`] instance.
+#[cfg(all(feature = "std", not(feature = "cxx-new-alloc")))]
+pub const DEFAULT_ALLOC: DefaultAlloc = SysAlloc;
+
+/// The [`DefaultAlloc`] instance.
+#[cfg(any(not(feature = "std"), feature = "cxx-new-alloc"))]
+pub const DEFAULT_ALLOC: DefaultAlloc = CxxNewAlloc::new();
+
+/// Trait for types that can spawn an opaque allocator instance from itself
+/// via [`CxxProxy::proxy`].
+///
+/// Types that implement [`CxxProxy`] can be used as C++ compatible allocators.
+///
+/// The associated constants are the `std::allocator_traits` of the allocator,
+/// which containers follow when they are assigned or swapped.
+pub trait CxxProxy {
+    /// `propagate_on_container_copy_assignment`.
+    const PROPAGATE_ON_COPY_ASSIGNMENT: bool = false;
+
+    /// `propagate_on_container_move_assignment`.
+    const PROPAGATE_ON_MOVE_ASSIGNMENT: bool = false;
+
+    /// `propagate_on_container_swap`.
+    const PROPAGATE_ON_SWAP: bool = false;
+
+    /// `is_always_equal`.
+    const IS_ALWAYS_EQUAL: bool = false;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a;
+
+    /// Whether memory allocated by one allocator can be freed by the other, `operator==`.
+    fn is_equal(&self, other: &Self) -> bool
+    where
+        Self: Sized,
+    {
+        let _ = other;
+        Self::IS_ALWAYS_EQUAL
+    }
+
+    /// The [`Batch`] this allocator is, or wraps, if any.
+    fn as_batch(&self) -> Option<Batch<'_>> {
+        None
+    }
+
+    /// Spawns a proxy once and lends it to `f` as a [`Batch`] allocator,
+    /// so that the containers `f` creates with it do not spawn one for every operation.
+    fn batch<R, F>(&self, f: F) -> R
+    where
+        Self: Sized,
+        F: for<'b> FnOnce(Batch<'b>) -> R,
+    {
+        let mut proxy_alloc = self.proxy();
+        let raw_alloc = RawAlloc::from_ref_mut(&mut proxy_alloc);
+
+        f(Batch {
+            raw: raw_alloc.base,
+            source: (self as *const Self).cast(),
+            source_eq: source_eq::<Self>,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Compares the sources of two [`Batch`] allocators.
+///
+/// # Safety
+///
+/// Both pointers must point to an `A`.
+unsafe fn source_eq<A: CxxProxy>(a: *const c_void, b: *const c_void) -> bool {
+    unsafe { (*a.cast::<A>()).is_equal(&*b.cast::<A>()) }
+}
+
+#[cfg(feature = "std")]
+impl CxxProxy for std::alloc::System {
+    const IS_ALWAYS_EQUAL: bool = true;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        *self
+    }
+}
+
+/// Allocator shared through the pointer `P`, like `&A`, `Rc<A>` or `Arc<A>`.
+///
+/// The allocator traits are those of the allocator it points to. Allocators
+/// are equal if they point to the same allocator or to equal ones.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SharedAlloc<P>(pub P);
+
+impl<P> CxxProxy for SharedAlloc<P>
+where
+    P: Deref + Clone,
+    P::Target: CxxProxy + Sized,
+{
+    const PROPAGATE_ON_COPY_ASSIGNMENT: bool = P::Target::PROPAGATE_ON_COPY_ASSIGNMENT;
+
+    const PROPAGATE_ON_MOVE_ASSIGNMENT: bool = P::Target::PROPAGATE_ON_MOVE_ASSIGNMENT;
+
+    const PROPAGATE_ON_SWAP: bool = P::Target::PROPAGATE_ON_SWAP;
+
+    const IS_ALWAYS_EQUAL: bool = P::Target::IS_ALWAYS_EQUAL;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        self.0.proxy()
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        ptr::eq(&*self.0, &*other.0) || self.0.is_equal(&other.0)
+    }
+
+    fn as_batch(&self) -> Option<Batch<'_>> {
+        self.0.as_batch()
+    }
+}
+
+/// Proxy of an allocator that is spawned once for a batch of containers by [`CxxProxy::batch`].
+///
+/// Containers reuse its raw allocator instead of spawning a proxy for every operation.
+/// Allocators are equal if they belong to the same batch, or if the allocators
+/// their batches were spawned from are equal.
+#[derive(Clone, Copy, Debug)]
+pub struct Batch<'a> {
+    raw: CSTL_Alloc,
+    source: *const c_void,
+    source_eq: unsafe fn(*const c_void, *const c_void) -> bool,
+    _marker: PhantomData<&'a c_void>,
+}
+
+/// Allocator handed out by the [`CxxProxy`] of a [`Batch`].
+struct BatchProxy<'a>(Batch<'a>);
+
+impl CxxProxy for Batch<'_> {
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        BatchProxy(*self)
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        // `source_eq` is generic over the type of the source, so batches with
+        // the same function were spawned from allocators of the same type,
+        // or of types for which it compiled to the same code.
+        self.raw.opaque == other.raw.opaque
+            || ptr::fn_addr_eq(self.source_eq, other.source_eq)
+                && unsafe { (self.source_eq)(self.source, other.source) }
+    }
+
+    fn as_batch(&self) -> Option<Batch<'_>> {
+        Some(*self)
+    }
+}
+
+unsafe impl GlobalAlloc for BatchProxy<'_> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let raw = &self.0.raw;
+
+        unsafe {
+            (raw.aligned_alloc.unwrap_unchecked())(raw.opaque, layout.size(), layout.align()).cast()
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let raw = &self.0.raw;
+
+        unsafe {
+            (raw.aligned_free.unwrap_unchecked())(
+                raw.opaque,
+                ptr.cast(),
+                layout.size(),
+                layout.align(),
+            )
+        }
+    }
+}
+
+pub trait WithCxxProxy: Sized {
+    type Value;
+    type Alloc: CxxProxy;
+
+    fn value_as_ref(&self) -> &Self::Value;
+
+    fn value_as_mut(&mut self) -> &mut Self::Value;
+
+    fn alloc_as_ref(&self) -> &Self::Alloc;
+
+    fn new_in(alloc: Self::Alloc) -> Self;
+
+    #[inline]
+    fn with_proxy<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&Self::Value, &mut CSTL_Alloc) -> R,
+    {
+        if let Some(mut raw_alloc) = self.alloc_as_ref().as_batch().map(|batch| batch.raw) {
+            return f(self.value_as_ref(), &mut raw_alloc);
+        }
+
+        let mut proxy_alloc = self.alloc_as_ref().proxy();
+        let mut raw_alloc = RawAlloc::from_ref_mut(&mut proxy_alloc);
+        f(self.value_as_ref(), &mut raw_alloc.base)
+    }
+
+    #[inline]
+    fn with_proxy_mut<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut Self::Value, &mut CSTL_Alloc) -> R,
+    {
+        if let Some(mut raw_alloc) = self.alloc_as_ref().as_batch().map(|batch| batch.raw) {
+            return f(self.value_as_mut(), &mut raw_alloc);
+        }
+
+        let mut proxy_alloc = self.alloc_as_ref().proxy();
+        let mut raw_alloc = RawAlloc::from_ref_mut(&mut proxy_alloc);
+        f(self.value_as_mut(), &mut raw_alloc.base)
+    }
+}
+
+struct RawAlloc<'a, A>
+where
+    A: GlobalAlloc + 'a,
+{
+    base: CSTL_Alloc,
+    _marker: PhantomData<&'a mut A>,
+}
+
+impl<'a, A> RawAlloc<'a, A>
+where
+    A: GlobalAlloc + 'a,
+{
+    #[inline]
+    fn from_ref_mut(value: &'a mut A) -> Self {
+        Self {
+            base: CSTL_Alloc {
+                opaque: value as *mut A as _,
+                aligned_alloc: Some(Self::RAW_ALLOC_PTR),
+                aligned_free: Some(Self::RAW_FREE_PTR),
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    const RAW_ALLOC_PTR: unsafe extern "C" fn(*mut c_void, usize, usize) -> *mut c_void =
+        unsafe { mem::transmute(Self::raw_alloc as *const ()) };
+
+    const RAW_FREE_PTR: unsafe extern "C" fn(*mut c_void, *mut c_void, usize, usize) =
+        unsafe { mem::transmute(Self::raw_free as *const ()) };
+
+    unsafe extern "C" fn raw_alloc(
+        opaque: NonNull<A>,
+        size: usize,
+        alignment: usize,
+    ) -> *mut c_void {
+        unsafe {
+            let alloc = opaque.as_ref();
+            let layout =
+                Layout::from_size_align(size, alignment).expect("bad layout passed from CSTL");
+            alloc.alloc(layout) as _
+        }
+    }
+
+    unsafe extern "C" fn raw_free(opaque: NonNull<A>, ptr: *mut u8, size: usize, alignment: usize) {
+        unsafe {
+            if !ptr.is_null() {
+                let alloc = opaque.as_ref();
+                let layout =
+                    Layout::from_size_align(size, alignment).expect("bad layout passed from CSTL");
+                alloc.dealloc(ptr, layout);
+            }
+        }
+    }
+}
+
+/// Signature and vtable index of the allocation function of a C++ heap object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocSlot {
+    /// `void* allocate(size_t size)`.
+    Size(usize),
+    /// `void* allocate(size_t size, size_t alignment)`.
+    SizeAlign(usize),
+}
+
+/// Signature and vtable index of the deallocation function of a C++ heap object.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FreeSlot {
+    /// `void free(void* ptr)`.
+    Ptr(usize),
+    /// `void free(void* ptr, size_t size)`.
+    PtrSize(usize),
+    /// `void free(void* ptr, size_t size, size_t alignment)`.
+    PtrSizeAlign(usize),
+}
+
+/// Describes the vtable of a polymorphic C++ heap for [`VTableAlloc`].
+///
+/// The functions are virtual member functions, so they use
+/// the `thiscall` calling convention on 32-bit x86 Windows.
+pub trait HeapVTable {
+    const ALLOC: AllocSlot;
+
+    const FREE: FreeSlot;
+
+    /// Alignment guaranteed by [`AllocSlot::Size`].
+    ///
+    /// Allocations with a larger alignment fail.
+    const ALIGN: usize = 2 * mem::size_of::<usize>();
+}
+
+/// Allocator that allocates from a polymorphic C++ heap object `H`.
+///
+/// It is a single pointer to the heap, like the C++ allocators
+/// that wrap such heaps usually are.
+///
+/// ```
+/// use std::{
+///     alloc::{GlobalAlloc, Layout, System},
+///     ptr::NonNull,
+/// };
+///
+/// use cxx_stl::{
+///     alloc::{AllocSlot, FreeSlot, HeapVTable, VTableAlloc},
+///     vec::CxxVec,
+/// };
+///
+/// /// Mock of a C++ heap, `class Heap { virtual ~Heap(); virtual void* Alloc(size_t, size_t);
+/// /// virtual void Free(void*, size_t, size_t); }`.
+/// #[repr(C)]
+/// struct MockHeap {
+///     vtable: &'static MockVTable,
+///     live: usize,
+/// }
+///
+/// #[repr(C)]
+/// struct MockVTable {
+///     destructor: usize,
+///     alloc: unsafe extern "C" fn(*mut MockHeap, usize, usize) -> *mut u8,
+///     free: unsafe extern "C" fn(*mut MockHeap, *mut u8, usize, usize),
+/// }
+///
+/// unsafe extern "C" fn alloc(heap: *mut MockHeap, size: usize, align: usize) -> *mut u8 {
+///     unsafe {
+///         (*heap).live += 1;
+///         System.alloc(Layout::from_size_align_unchecked(size, align))
+///     }
+/// }
+///
+/// unsafe extern "C" fn free(heap: *mut MockHeap, ptr: *mut u8, size: usize, align: usize) {
+///     unsafe {
+///         (*heap).live -= 1;
+///         System.dealloc(ptr, Layout::from_size_align_unchecked(size, align))
+///     }
+/// }
+///
+/// static VTABLE: MockVTable = MockVTable { destructor: 0, alloc, free };
+///
+/// struct MockSlots;
+///
+/// impl HeapVTable for MockSlots {
+///     const ALLOC: AllocSlot = AllocSlot::SizeAlign(1);
+///     const FREE: FreeSlot = FreeSlot::PtrSizeAlign(2);
+/// }
+///
+/// # #[cfg(not(all(windows, target_arch = "x86")))]
+/// # {
+/// let heap = Box::into_raw(Box::new(MockHeap { vtable: &VTABLE, live: 0 }));
+/// let alloc = unsafe { VTableAlloc::<MockSlots>::from_raw(NonNull::new(heap).unwrap().cast()) };
+///
+/// let mut vec = CxxVec::new_in(alloc);
+/// vec.extend(0..100u32);
+/// assert_eq!(unsafe { (*heap).live }, 1);
+///
+/// drop(vec);
+/// assert_eq!(unsafe { (*heap).live }, 0);
+///
+/// drop(unsafe { Box::from_raw(heap) });
+/// # }
+/// ```
+#[repr(transparent)]
+pub struct VTableAlloc<'a, H: HeapVTable> {
+    heap: NonNull<c_void>,
+    _marker: PhantomData<(&'a c_void, H)>,
+}
+
+/// Allocator handed out by the [`CxxProxy`] of a [`VTableAlloc`].
+struct HeapProxy<'a, H: HeapVTable>(VTableAlloc<'a, H>);
+
+impl<'a, H: HeapVTable> VTableAlloc<'a, H> {
+    /// Wraps a pointer to a heap object with a vtable described by `H`.
+    ///
+    /// # Safety
+    ///
+    /// `heap` must point to such an object that outlives `'a`.
+    pub const unsafe fn from_raw(heap: NonNull<c_void>) -> Self {
+        Self {
+            heap,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *mut c_void {
+        self.heap.as_ptr()
+    }
+
+    /// Reads the function at `index` of the vtable of the heap.
+    unsafe fn slot(&self, index: usize) -> *const () {
+        unsafe { *(*self.heap.as_ptr().cast::<*const *const ()>()).add(index) }
+    }
+}
+
+unsafe impl<H: HeapVTable> GlobalAlloc for HeapProxy<'_, H> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let heap = self.0.as_ptr();
+
+        unsafe {
+            match H::ALLOC {
+                AllocSlot::Size(_) if layout.align() > H::ALIGN => ptr::null_mut(),
+                AllocSlot::Size(index) => {
+                    let f: member_fn!(fn(*mut c_void, usize) -> *mut c_void) =
+                        mem::transmute(self.0.slot(index));
+                    f(heap, layout.size()).cast()
+                }
+                AllocSlot::SizeAlign(index) => {
+                    let f: member_fn!(fn(*mut c_void, usize, usize) -> *mut c_void) =
+                        mem::transmute(self.0.slot(index));
+                    f(heap, layout.size(), layout.align()).cast()
+                }
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let heap = self.0.as_ptr();
+
+        unsafe {
+            match H::FREE {
+                FreeSlot::Ptr(index) => {
+                    let f: member_fn!(fn(*mut c_void, *mut c_void)) =
+                        mem::transmute(self.0.slot(index));
+                    f(heap, ptr.cast())
+                }
+                FreeSlot::PtrSize(index) => {
+                    let f: member_fn!(fn(*mut c_void, *mut c_void, usize)) =
+                        mem::transmute(self.0.slot(index));
+                    f(heap, ptr.cast(), layout.size())
+                }
+                FreeSlot::PtrSizeAlign(index) => {
+                    let f: member_fn!(fn(*mut c_void, *mut c_void, usize, usize)) =
+                        mem::transmute(self.0.slot(index));
+                    f(heap, ptr.cast(), layout.size(), layout.align())
+                }
+            }
+        }
+    }
+}
+
+/// Allocators are equal if they allocate from the same heap.
+impl<H: HeapVTable> CxxProxy for VTableAlloc<'_, H> {
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        HeapProxy(*self)
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        self.heap == other.heap
+    }
+}
+
+impl<H: HeapVTable> Clone for VTableAlloc<'_, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<H: HeapVTable> Copy for VTableAlloc<'_, H> {}
+
+impl<H: HeapVTable> fmt::Debug for VTableAlloc<'_, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("VTableAlloc").field(&self.heap).finish()
+    }
+}
+
+/// Allocators are equal if they allocate from the same heap.
+impl<H: HeapVTable> PartialEq for VTableAlloc<'_, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.heap == other.heap
+    }
+}
+
+impl<H: HeapVTable> Eq for VTableAlloc<'_, H> {}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::{Cell, RefCell};
+
+    use rust_alloc::{alloc, rc::Rc, vec::Vec};
+
+    use super::*;
+    use crate::{deque::CxxDeque, string::CxxNarrowString, vec::CxxVec};
+
+    /// Call made to [`MockHeap`], with the arguments its slot receives.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Call {
+        Alloc(usize, Option<usize>),
+        Free(Option<usize>, Option<usize>),
+    }
+
+    /// Heap with a vtable of every supported signature, after a virtual destructor.
+    #[repr(C)]
+    struct MockHeap {
+        vtable: &'static [*const (); 6],
+        calls: RefCell<Vec<Call>>,
+        live: RefCell<Vec<(*mut u8, Layout)>>,
+    }
+
+    struct VTable([*const (); 6]);
+
+    unsafe impl Sync for VTable {}
+
+    static VTABLE: VTable = VTable([
+        ptr::null(),
+        alloc_size as *const (),
+        alloc_size_align as *const (),
+        free_ptr as *const (),
+        free_ptr_size as *const (),
+        free_ptr_size_align as *const (),
+    ]);
+
+    impl MockHeap {
+        fn new() -> Self {
+            Self {
+                vtable: &VTABLE.0,
+                calls: RefCell::default(),
+                live: RefCell::default(),
+            }
+        }
+
+        fn alloc<H: HeapVTable>(&self) -> VTableAlloc<'_, H> {
+            unsafe { VTableAlloc::from_raw(NonNull::from(self).cast()) }
+        }
+
+        fn allocate(&self, call: Call, size: usize, align: usize) -> *mut c_void {
+            let layout = Layout::from_size_align(size, align).unwrap();
+            let ptr = unsafe { alloc::alloc(layout) };
+
+            self.calls.borrow_mut().push(call);
+            self.live.borrow_mut().push((ptr, layout));
+            ptr.cast()
+        }
+
+        fn free(&self, call: Call, ptr: *mut c_void) {
+            let mut live = self.live.borrow_mut();
+            let index = live
+                .iter()
+                .position(|&(live, _)| live == ptr.cast())
+                .expect("pointer not allocated from the heap");
+            let (ptr, layout) = live.swap_remove(index);
+
+            self.calls.borrow_mut().push(call);
+            unsafe { alloc::dealloc(ptr, layout) }
+        }
+
+        fn take_calls(&self) -> Vec<Call> {
+            self.calls.take()
+        }
+    }
+
+    member_fn! {
+        fn alloc_size(heap: *mut MockHeap, size: usize) -> *mut c_void {
+            unsafe { (*heap).allocate(Call::Alloc(size, None), size, 16) }
+        }
+    }
+
+    member_fn! {
+        fn alloc_size_align(heap: *mut MockHeap, size: usize, align: usize) -> *mut c_void {
+            unsafe { (*heap).allocate(Call::Alloc(size, Some(align)), size, align) }
+        }
+    }
+
+    member_fn! {
+        fn free_ptr(heap: *mut MockHeap, ptr: *mut c_void) {
+            unsafe { (*heap).free(Call::Free(None, None), ptr) }
+        }
+    }
+
+    member_fn! {
+        fn free_ptr_size(heap: *mut MockHeap, ptr: *mut c_void, size: usize) {
+            unsafe { (*heap).free(Call::Free(Some(size), None), ptr) }
+        }
+    }
+
+    member_fn! {
+        fn free_ptr_size_align(heap: *mut MockHeap, ptr: *mut c_void, size: usize, align: usize) {
+            unsafe { (*heap).free(Call::Free(Some(size), Some(align)), ptr) }
+        }
+    }
+
+    struct SizeAlign;
+
+    impl HeapVTable for SizeAlign {
+        const ALLOC: AllocSlot = AllocSlot::SizeAlign(2);
+        const FREE: FreeSlot = FreeSlot::PtrSizeAlign(5);
+    }
+
+    struct Size;
+
+    impl HeapVTable for Size {
+        const ALLOC: AllocSlot = AllocSlot::Size(1);
+        const FREE: FreeSlot = FreeSlot::Ptr(3);
+        const ALIGN: usize = 16;
+    }
+
+    struct SizeSized;
+
+    impl HeapVTable for SizeSized {
+        const ALLOC: AllocSlot = AllocSlot::Size(1);
+        const FREE: FreeSlot = FreeSlot::PtrSize(4);
+    }
+
+    #[test]
+    fn calls_slots_with_layout() {
+        let heap = MockHeap::new();
+        let alloc = HeapProxy(heap.alloc::<SizeAlign>());
+        let layout = Layout::from_size_align(24, 64).unwrap();
+
+        unsafe {
+            let ptr = alloc.alloc(layout);
+            assert_eq!(ptr as usize % 64, 0);
+            alloc.dealloc(ptr, layout);
+        }
+
+        assert_eq!(
+            heap.take_calls(),
+            [Call::Alloc(24, Some(64)), Call::Free(Some(24), Some(64))]
+        );
+    }
+
+    #[test]
+    fn size_slot_rejects_overaligned_layouts() {
+        let heap = MockHeap::new();
+        let alloc = HeapProxy(heap.alloc::<Size>());
+
+        unsafe {
+            let ptr = alloc.alloc(Layout::from_size_align(8, 32).unwrap());
+            assert!(ptr.is_null());
+
+            let layout = Layout::from_size_align(8, 16).unwrap();
+            let ptr = alloc.alloc(layout);
+            assert!(!ptr.is_null());
+            alloc.dealloc(ptr, layout);
+        }
+
+        assert_eq!(
+            heap.take_calls(),
+            [Call::Alloc(8, None), Call::Free(None, None)]
+        );
+    }
+
+    #[test]
+    fn containers_allocate_from_heap() {
+        let heap = MockHeap::new();
+        let alloc = heap.alloc::<SizeSized>();
+
+        let mut vec = CxxVec::new_in(alloc);
+        vec.extend(0..10u64);
+        assert_eq!(heap.live.borrow().len(), 1);
+
+        drop(vec);
+        assert!(heap.live.borrow().is_empty());
+        assert!(heap
+            .take_calls()
+            .iter()
+            .all(|call| matches!(call, Call::Alloc(_, None) | Call::Free(Some(_), None))));
+    }
+
+    #[test]
+    fn equal_if_same_heap() {
+        let (a, b) = (MockHeap::new(), MockHeap::new());
+
+        assert!(a.alloc::<Size>().is_equal(&a.alloc()));
+        assert!(!a.alloc::<Size>().is_equal(&b.alloc()));
+        assert_eq!(a.alloc::<Size>(), a.alloc());
+    }
+
+    /// Allocator that counts the proxies it spawns.
+    #[derive(Default)]
+    struct Counting {
+        spawned: Cell<usize>,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Global;
+
+    unsafe impl GlobalAlloc for Global {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            unsafe { alloc::alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { alloc::dealloc(ptr, layout) }
+        }
+    }
+
+    impl CxxProxy for Global {
+        const IS_ALWAYS_EQUAL: bool = true;
+
+        fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+        where
+            Self: 'a,
+        {
+            *self
+        }
+    }
+
+    impl CxxProxy for Counting {
+        fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+        where
+            Self: 'a,
+        {
+            self.spawned.set(self.spawned.get() + 1);
+            Global
+        }
+    }
+
+    #[test]
+    fn batch_spawns_one_proxy() {
+        let alloc = Counting::default();
+
+        alloc.batch(|alloc| {
+            let mut vec = CxxVec::new_in(alloc);
+            vec.extend(0..100u32);
+            vec.truncate(10);
+            vec.shrink_to_fit();
+
+            let mut deque = CxxDeque::new_in(ScopedAlloc(alloc));
+            deque.extend(0..100u32);
+            deque.clear();
+
+            assert!(alloc.is_equal(vec.allocator()));
+        });
+
+        assert_eq!(alloc.spawned.get(), 1);
+
+        let mut vec = CxxVec::new_in(Global);
+        vec.extend(0..100u32);
+        assert!(!vec.is_empty());
+    }
+
+    #[test]
+    fn batches_equal_if_sources_are_equal() {
+        let (x, y) = (Arena::new(), Arena::new());
+
+        (&x).batch(|a| {
+            assert!(a.is_equal(&a));
+            (&x).batch(|b| assert!(a.is_equal(&b)));
+            (&y).batch(|b| assert!(!a.is_equal(&b)));
+            Global.batch(|b| assert!(!a.is_equal(&b)));
+        });
+
+        Global.batch(|a| Global.batch(|b| assert!(a.is_equal(&b))));
+    }
+
+    #[test]
+    fn batches_of_unequal_source_are_distinct() {
+        let alloc = Counting::default();
+
+        alloc.batch(|a| {
+            assert!(a.is_equal(&a));
+            alloc.batch(|b| assert!(!a.is_equal(&b)));
+        });
+    }
+
+    #[test]
+    fn shared_allocators_equal_if_same_target() {
+        let rc = Rc::new(Counting::default());
+
+        assert!(SharedAlloc(rc.clone()).is_equal(&SharedAlloc(rc)));
+        assert!(!SharedAlloc(Rc::new(Counting::default()))
+            .is_equal(&SharedAlloc(Rc::new(Counting::default()))));
+
+        let mut vec = CxxVec::new_in(SharedAlloc(&Global));
+        vec.push(1u8);
+        assert_eq!(vec.as_slice(), [1]);
+    }
+
+    #[test]
+    fn shared_allocators_forward_allocator_traits() {
+        const { assert!(SharedAlloc::<Rc<Global>>::IS_ALWAYS_EQUAL) };
+        assert!(SharedAlloc(Rc::new(Global)).is_equal(&SharedAlloc(Rc::new(Global))));
+
+        let (a, b) = Propagating::pair();
+        let c = b.retag(0);
+
+        const { assert!(SharedAlloc::<&Propagating>::PROPAGATE_ON_SWAP) };
+        const { assert!(!SharedAlloc::<&Propagating>::IS_ALWAYS_EQUAL) };
+        assert!(SharedAlloc(&a).is_equal(&SharedAlloc(&c)));
+        assert!(!SharedAlloc(&a).is_equal(&SharedAlloc(&b)));
+
+        let mut vec = vec_in(SharedAlloc(&a), 3);
+        vec.push(3);
+        assert_eq!(a.live(), [1, 0]);
+        drop(vec);
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    /// Stateful allocator that is equal to allocators with the same tag,
+    /// and counts the live blocks of each tag.
+    #[derive(Clone)]
+    struct Tagged<const PROPAGATE: bool> {
+        tag: usize,
+        live: Rc<[Cell<isize>; 2]>,
+    }
+
+    impl<const PROPAGATE: bool> Tagged<PROPAGATE> {
+        /// Allocators with the tags 0 and 1, sharing the counts.
+        fn pair() -> (Self, Self) {
+            let live = Rc::new([Cell::new(0), Cell::new(0)]);
+            let first = Self {
+                tag: 0,
+                live: live.clone(),
+            };
+
+            (first, Self { tag: 1, live })
+        }
+
+        fn retag(&self, tag: usize) -> Self {
+            Self {
+                tag,
+                live: self.live.clone(),
+            }
+        }
+
+        fn live(&self) -> [isize; 2] {
+            [self.live[0].get(), self.live[1].get()]
+        }
+    }
+
+    unsafe impl<const PROPAGATE: bool> GlobalAlloc for Tagged<PROPAGATE> {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.live[self.tag].set(self.live[self.tag].get() + 1);
+            unsafe { alloc::alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.live[self.tag].set(self.live[self.tag].get() - 1);
+            unsafe { alloc::dealloc(ptr, layout) }
+        }
+    }
+
+    impl<const PROPAGATE: bool> CxxProxy for Tagged<PROPAGATE> {
+        const PROPAGATE_ON_COPY_ASSIGNMENT: bool = PROPAGATE;
+
+        const PROPAGATE_ON_MOVE_ASSIGNMENT: bool = PROPAGATE;
+
+        const PROPAGATE_ON_SWAP: bool = PROPAGATE;
+
+        fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+        where
+            Self: 'a,
+        {
+            self.clone()
+        }
+
+        fn is_equal(&self, other: &Self) -> bool {
+            self.tag == other.tag
+        }
+    }
+
+    type Propagating = Tagged<true>;
+
+    type Fixed = Tagged<false>;
+
+    /// Long enough to not fit in the small string buffer.
+    const LONG: &str = "a string that is allocated on the heap";
+
+    fn vec_in<A: CxxProxy>(alloc: A, len: u32) -> CxxVec<u32, A> {
+        let mut vec = CxxVec::new_in(alloc);
+        vec.extend(0..len);
+        vec
+    }
+
+    #[test]
+    fn vec_propagating_allocator() {
+        let (a, b) = Propagating::pair();
+
+        let mut vec = vec_in(a.clone(), 3);
+        vec.move_assign(vec_in(b.clone(), 5));
+        assert_eq!(vec.allocator().tag, 1);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3, 4]);
+        assert_eq!(a.live(), [0, 1]);
+
+        let mut other = vec_in(a.clone(), 2);
+        vec.swap_with(&mut other);
+        assert_eq!((vec.allocator().tag, other.allocator().tag), (0, 1));
+        assert_eq!(vec.as_slice(), [0, 1]);
+
+        vec.clone_from(&other);
+        assert_eq!(vec.allocator().tag, 1);
+        assert_eq!(vec, other);
+
+        drop((vec, other));
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    fn vec_equal_allocator_takes_storage() {
+        let (a, b) = Fixed::pair();
+
+        let mut vec = vec_in(a.clone(), 3);
+        let source = vec_in(b.retag(0), 5);
+        let ptr = source.as_ptr();
+        vec.move_assign(source);
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(a.live(), [1, 0]);
+
+        let mut other = vec_in(a.retag(0), 2);
+        let ptrs = (vec.as_ptr(), other.as_ptr());
+        vec.swap_with(&mut other);
+        assert_eq!((other.as_ptr(), vec.as_ptr()), ptrs);
+
+        vec.clone_from(&other);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3, 4]);
+
+        drop((vec, other));
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    fn vec_unequal_allocator_moves_elements() {
+        let (a, b) = Fixed::pair();
+
+        let mut vec = vec_in(a.clone(), 3);
+        let source = vec_in(b.clone(), 5);
+        let ptr = source.as_ptr();
+        vec.move_assign(source);
+        assert_ne!(vec.as_ptr(), ptr);
+        assert_eq!(vec.allocator().tag, 0);
+        assert_eq!(vec.as_slice(), [0, 1, 2, 3, 4]);
+        assert_eq!(a.live(), [1, 0]);
+
+        vec.clone_from(&vec_in(b.clone(), 7));
+        assert_eq!(vec.allocator().tag, 0);
+        assert_eq!(vec.len(), 7);
+
+        drop(vec);
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    #[should_panic = "vectors with unequal allocators cannot be swapped"]
+    fn vec_unequal_allocator_swap_panics() {
+        let (a, b) = Fixed::pair();
+
+        vec_in(a, 1).swap_with(&mut vec_in(b, 1));
+    }
+
+    #[test]
+    fn string_propagating_allocator() {
+        let (a, b) = Propagating::pair();
+
+        let mut string = CxxNarrowString::from_bytes_in("short", a.clone());
+        string.move_assign(CxxNarrowString::from_bytes_in(LONG, b.clone()));
+        assert_eq!(string.allocator().tag, 1);
+        assert_eq!(string.as_bytes(), LONG.as_bytes());
+        assert_eq!(a.live(), [0, 1]);
+
+        let mut other = CxxNarrowString::from_bytes_in("short", a.clone());
+        string.swap_with(&mut other);
+        assert_eq!((string.allocator().tag, other.allocator().tag), (0, 1));
+        assert_eq!(string.as_bytes(), "short".as_bytes());
+
+        string.clone_from(&other);
+        assert_eq!(string.allocator().tag, 1);
+        assert_eq!(string.as_bytes(), LONG.as_bytes());
+
+        drop((string, other));
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    fn string_equal_allocator_takes_storage() {
+        let (a, b) = Fixed::pair();
+
+        let mut string = CxxNarrowString::from_bytes_in("short", a.clone());
+        let source = CxxNarrowString::from_bytes_in(LONG, b.retag(0));
+        let ptr = source.as_ptr();
+        string.move_assign(source);
+        assert_eq!(string.as_ptr(), ptr);
+        assert_eq!(a.live(), [1, 0]);
+
+        let mut other = CxxNarrowString::from_bytes_in(LONG.repeat(2), a.retag(0));
+        let ptrs = (string.as_ptr(), other.as_ptr());
+        string.swap_with(&mut other);
+        assert_eq!((other.as_ptr(), string.as_ptr()), ptrs);
+
+        string.clone_from(&other);
+        assert_eq!(string.as_bytes(), LONG.as_bytes());
+
+        drop((string, other));
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    fn string_unequal_allocator_copies_contents() {
+        let (a, b) = Fixed::pair();
+
+        let mut string = CxxNarrowString::from_bytes_in("short", a.clone());
+        let source = CxxNarrowString::from_bytes_in(LONG, b.clone());
+        let ptr = source.as_ptr();
+        string.move_assign(source);
+        assert_ne!(string.as_ptr(), ptr);
+        assert_eq!(string.allocator().tag, 0);
+        assert_eq!(string.as_bytes(), LONG.as_bytes());
+        assert_eq!(a.live(), [1, 0]);
+
+        string.clone_from(&CxxNarrowString::from_bytes_in(LONG.repeat(2), b.clone()));
+        assert_eq!(string.allocator().tag, 0);
+        assert_eq!(string.len(), 2 * LONG.len());
+
+        drop(string);
+        assert_eq!(a.live(), [0, 0]);
+    }
+
+    #[test]
+    #[should_panic = "strings with unequal allocators cannot be swapped"]
+    fn string_unequal_allocator_swap_panics() {
+        let (a, b) = Fixed::pair();
+
+        CxxNarrowString::from_bytes_in("a", a)
+            .swap_with(&mut CxxNarrowString::from_bytes_in("b", b));
+    }
+}
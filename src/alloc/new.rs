@@ -0,0 +1,399 @@
+//! Allocator compatible with the C++ global `operator new` and `operator delete`.
+//!
+//! `std::allocator` of MSVC adds a header in front of big and over-aligned blocks,
+//! so they can only be freed by an allocator that knows about it.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    mem, ptr,
+};
+
+use super::CxxProxy;
+
+/// `_Big_allocation_threshold`, only x86 and x64 align big blocks.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+const BIG_ALLOCATION_THRESHOLD: usize = 4096;
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+const BIG_ALLOCATION_THRESHOLD: usize = usize::MAX;
+
+/// `_Big_allocation_alignment`.
+const BIG_ALLOCATION_ALIGNMENT: usize = 32;
+
+/// `_Non_user_size`.
+const NON_USER_SIZE: usize = 2 * mem::size_of::<usize>() + BIG_ALLOCATION_ALIGNMENT - 1;
+
+/// `_Big_allocation_sentinel`.
+const BIG_ALLOCATION_SENTINEL: usize = usize::from_ne_bytes([0xFA; mem::size_of::<usize>()]);
+
+/// `__STDCPP_DEFAULT_NEW_ALIGNMENT__`.
+const DEFAULT_NEW_ALIGNMENT: usize = 2 * mem::size_of::<usize>();
+
+/// `_bAlignLandFill` of the debug CRT.
+const ALIGN_LAND_FILL: u8 = 0xED;
+
+/// Raw heap behind `operator new`, usually `malloc` and `free` of the C runtime.
+///
+/// # Safety
+///
+/// [`NewHeap::malloc`] must return null or a block of at least `size` bytes
+/// aligned to `2 * size_of::<usize>()`, which [`NewHeap::free`] can free.
+pub unsafe trait NewHeap {
+    /// Whether the host was built with `_DEBUG`, which adds sentinels to the headers.
+    const DEBUG: bool = false;
+
+    fn malloc(&self, size: usize) -> *mut u8;
+
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by [`NewHeap::malloc`] of this heap.
+    unsafe fn free(&self, ptr: *mut u8);
+}
+
+/// `malloc` and `free` of the C runtime linked into the program.
+///
+/// They are declared as `extern "C"` functions, so the program fails to link
+/// without a C runtime that provides them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrtHeap;
+
+/// `std::allocator` on top of the heap `H`.
+///
+/// Blocks of at least 4096 bytes are aligned to 32 bytes and store a pointer to
+/// the heap block in front of them on x86 and x64, like `_Allocate_manually_vector_aligned`.
+/// Over-aligned blocks come from `_aligned_malloc`, like the aligned `operator new`.
+///
+/// Allocators are equal if their heaps are, and always equal if the heap is stateless.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CxxNewAlloc<H: NewHeap = CrtHeap>(pub H);
+
+extern "C" {
+    fn malloc(size: usize) -> *mut u8;
+
+    fn free(ptr: *mut u8);
+}
+
+unsafe impl NewHeap for CrtHeap {
+    fn malloc(&self, size: usize) -> *mut u8 {
+        unsafe { malloc(size) }
+    }
+
+    unsafe fn free(&self, ptr: *mut u8) {
+        unsafe { free(ptr) }
+    }
+}
+
+impl CxxNewAlloc {
+    pub const fn new() -> Self {
+        Self(CrtHeap)
+    }
+}
+
+impl<H: NewHeap> CxxNewAlloc<H> {
+    /// `_aligned_malloc`.
+    fn aligned_malloc(&self, layout: Layout) -> *mut u8 {
+        let align = layout.align().max(mem::size_of::<usize>());
+        let header = if H::DEBUG { 2 } else { 1 } * mem::size_of::<usize>();
+
+        let Some(size) = layout.size().checked_add(header + align - 1) else {
+            return ptr::null_mut();
+        };
+
+        let block = self.0.malloc(size);
+
+        if block.is_null() {
+            return block;
+        }
+
+        unsafe {
+            let user = block
+                .add(header)
+                .map_addr(|addr| (addr + align - 1) & !(align - 1));
+            let head = user.cast::<*mut u8>().sub(header / mem::size_of::<usize>());
+
+            head.write_unaligned(block);
+
+            if H::DEBUG {
+                head.add(1)
+                    .cast::<u8>()
+                    .write_bytes(ALIGN_LAND_FILL, mem::size_of::<usize>());
+            }
+
+            user
+        }
+    }
+
+    /// `_aligned_free`.
+    unsafe fn aligned_free(&self, ptr: *mut u8) {
+        unsafe {
+            let head = ptr.cast::<*mut u8>().sub(if H::DEBUG { 2 } else { 1 });
+
+            if H::DEBUG {
+                let gap = head
+                    .add(1)
+                    .cast::<[u8; mem::size_of::<usize>()]>()
+                    .read_unaligned();
+
+                if gap != [ALIGN_LAND_FILL; mem::size_of::<usize>()] {
+                    corrupt_header();
+                }
+            }
+
+            self.0.free(head.read_unaligned());
+        }
+    }
+
+    /// `_Allocate_manually_vector_aligned`.
+    fn big_malloc(&self, size: usize) -> *mut u8 {
+        let Some(size) = size.checked_add(NON_USER_SIZE) else {
+            return ptr::null_mut();
+        };
+
+        let block = self.0.malloc(size);
+
+        if block.is_null() {
+            return block;
+        }
+
+        unsafe {
+            let user = block
+                .add(NON_USER_SIZE)
+                .map_addr(|addr| addr & !(BIG_ALLOCATION_ALIGNMENT - 1));
+            let header = user.cast::<usize>();
+
+            header.sub(1).write(block as usize);
+
+            if H::DEBUG {
+                header.sub(2).write(BIG_ALLOCATION_SENTINEL);
+            }
+
+            user
+        }
+    }
+
+    /// `_Adjust_manually_vector_aligned`.
+    unsafe fn big_free(&self, ptr: *mut u8) {
+        let header = ptr.cast::<usize>();
+        let block = unsafe { header.sub(1).read() };
+
+        if H::DEBUG && unsafe { header.sub(2).read() } != BIG_ALLOCATION_SENTINEL {
+            corrupt_header();
+        }
+
+        let min_back_shift = if H::DEBUG { 2 } else { 1 } * mem::size_of::<usize>();
+        let back_shift = (ptr as usize).wrapping_sub(block);
+
+        if !(min_back_shift..=NON_USER_SIZE).contains(&back_shift) {
+            corrupt_header();
+        }
+
+        unsafe { self.0.free(ptr.with_addr(block)) }
+    }
+}
+
+impl<H: NewHeap + Clone + PartialEq> CxxProxy for CxxNewAlloc<H> {
+    const IS_ALWAYS_EQUAL: bool = mem::size_of::<H>() == 0;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        self.clone()
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+unsafe impl<H: NewHeap> GlobalAlloc for CxxNewAlloc<H> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.align() > DEFAULT_NEW_ALIGNMENT {
+            self.aligned_malloc(layout)
+        } else if layout.size() >= BIG_ALLOCATION_THRESHOLD {
+            self.big_malloc(layout.size())
+        } else {
+            self.0.malloc(layout.size())
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            if layout.align() > DEFAULT_NEW_ALIGNMENT {
+                self.aligned_free(ptr);
+            } else if layout.size() >= BIG_ALLOCATION_THRESHOLD {
+                self.big_free(ptr);
+            } else {
+                self.0.free(ptr);
+            }
+        }
+    }
+}
+
+/// Fails on a corrupt block header like `_STL_VERIFY`, by aborting, or panicking without `std`.
+fn corrupt_header() -> ! {
+    #[cfg(feature = "std")]
+    std::process::abort();
+
+    #[cfg(not(feature = "std"))]
+    panic!("corrupt `operator new` block header");
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use rust_alloc::{alloc, vec::Vec};
+
+    use super::*;
+
+    /// Heap that records its blocks, built with `_DEBUG` if `DEBUG`.
+    #[derive(Default)]
+    struct MockHeap<const DEBUG: bool> {
+        blocks: RefCell<Vec<(*mut u8, usize)>>,
+        freed: RefCell<Vec<*mut u8>>,
+    }
+
+    unsafe impl<const DEBUG: bool> NewHeap for MockHeap<DEBUG> {
+        const DEBUG: bool = DEBUG;
+
+        fn malloc(&self, size: usize) -> *mut u8 {
+            let block = unsafe { alloc::alloc(Self::layout(size)) };
+            self.blocks.borrow_mut().push((block, size));
+            block
+        }
+
+        unsafe fn free(&self, ptr: *mut u8) {
+            let (_, size) = *self
+                .blocks
+                .borrow()
+                .iter()
+                .find(|&&(block, _)| block == ptr)
+                .expect("freed block was not allocated");
+
+            self.freed.borrow_mut().push(ptr);
+            unsafe { alloc::dealloc(ptr, Self::layout(size)) }
+        }
+    }
+
+    impl<const DEBUG: bool> MockHeap<DEBUG> {
+        fn layout(size: usize) -> Layout {
+            Layout::from_size_align(size, DEFAULT_NEW_ALIGNMENT).unwrap()
+        }
+
+        fn only_block(&self) -> (*mut u8, usize) {
+            let blocks = self.blocks.borrow();
+            assert_eq!(blocks.len(), 1);
+            blocks[0]
+        }
+    }
+
+    fn big_header<const DEBUG: bool>() {
+        let alloc = CxxNewAlloc(MockHeap::<DEBUG>::default());
+
+        let ptr = alloc.big_malloc(5000);
+        let (block, size) = alloc.0.only_block();
+        let header = ptr.cast::<usize>();
+
+        assert_eq!(size, 5000 + NON_USER_SIZE);
+        assert!((ptr as usize).is_multiple_of(BIG_ALLOCATION_ALIGNMENT));
+        assert!((block..block.wrapping_add(size)).contains(&ptr.wrapping_add(5000 - 1)));
+        assert_eq!(unsafe { header.sub(1).read() }, block as usize);
+
+        if DEBUG {
+            assert_eq!(unsafe { header.sub(2).read() }, BIG_ALLOCATION_SENTINEL);
+        }
+
+        unsafe { alloc.big_free(ptr) };
+        assert_eq!(*alloc.0.freed.borrow(), [block]);
+    }
+
+    fn aligned_header<const DEBUG: bool>() {
+        let alloc = CxxNewAlloc(MockHeap::<DEBUG>::default());
+        let layout = Layout::from_size_align(100, 64).unwrap();
+        let words = if DEBUG { 2 } else { 1 };
+
+        let ptr = unsafe { alloc.alloc(layout) };
+        let (block, size) = alloc.0.only_block();
+        let head = ptr.cast::<*mut u8>().wrapping_sub(words);
+
+        assert_eq!(size, 100 + words * mem::size_of::<usize>() + 64 - 1);
+        assert!((ptr as usize).is_multiple_of(64));
+        assert!(head.cast::<u8>() >= block);
+        assert!(ptr.wrapping_add(100) <= block.wrapping_add(size));
+        assert_eq!(unsafe { head.read_unaligned() }, block);
+
+        if DEBUG {
+            let gap = unsafe {
+                head.add(1)
+                    .cast::<[u8; mem::size_of::<usize>()]>()
+                    .read_unaligned()
+            };
+            assert_eq!(gap, [ALIGN_LAND_FILL; mem::size_of::<usize>()]);
+        }
+
+        unsafe { alloc.dealloc(ptr, layout) };
+        assert_eq!(*alloc.0.freed.borrow(), [block]);
+    }
+
+    #[test]
+    fn big_blocks_store_heap_block() {
+        big_header::<false>();
+    }
+
+    #[test]
+    fn big_blocks_store_sentinel_in_debug() {
+        big_header::<true>();
+    }
+
+    #[test]
+    fn over_aligned_blocks_store_heap_block() {
+        aligned_header::<false>();
+    }
+
+    #[test]
+    fn over_aligned_blocks_store_land_fill_in_debug() {
+        aligned_header::<true>();
+    }
+
+    #[test]
+    fn small_blocks_have_no_header() {
+        let alloc = CxxNewAlloc(MockHeap::<true>::default());
+        let layout = Layout::from_size_align(100, DEFAULT_NEW_ALIGNMENT).unwrap();
+
+        let ptr = unsafe { alloc.alloc(layout) };
+        assert_eq!(alloc.0.only_block(), (ptr, 100));
+
+        unsafe { alloc.dealloc(ptr, layout) };
+        assert_eq!(*alloc.0.freed.borrow(), [ptr]);
+    }
+
+    #[test]
+    fn overflowing_sizes_fail() {
+        let alloc = CxxNewAlloc(MockHeap::<false>::default());
+
+        assert!(alloc.big_malloc(usize::MAX).is_null());
+        assert!(alloc.0.blocks.borrow().is_empty());
+    }
+
+    #[test]
+    fn equal_if_heaps_are_equal() {
+        #[derive(Clone, PartialEq)]
+        struct Tagged(u8);
+
+        unsafe impl NewHeap for Tagged {
+            fn malloc(&self, size: usize) -> *mut u8 {
+                CrtHeap.malloc(size)
+            }
+
+            unsafe fn free(&self, ptr: *mut u8) {
+                unsafe { CrtHeap.free(ptr) }
+            }
+        }
+
+        const { assert!(CxxNewAlloc::<CrtHeap>::IS_ALWAYS_EQUAL) };
+        const { assert!(!CxxNewAlloc::<Tagged>::IS_ALWAYS_EQUAL) };
+        assert!(CxxNewAlloc(Tagged(1)).is_equal(&CxxNewAlloc(Tagged(1))));
+        assert!(!CxxNewAlloc(Tagged(1)).is_equal(&CxxNewAlloc(Tagged(2))));
+    }
+}
@@ -0,0 +1,74 @@
+//! Interoperation with the unstable [`Allocator`] trait.
+//!
+//! Blanket implementations in both directions would overlap with the
+//! implementations for [`System`](std::alloc::System), which implements
+//! both traits, so the allocators are wrapped instead.
+
+use core::{
+    alloc::{AllocError, Allocator, GlobalAlloc, Layout},
+    mem,
+    ptr::{self, NonNull},
+};
+
+use super::CxxProxy;
+
+/// Wraps the Rust allocator `A` as a [`CxxProxy`].
+///
+/// Vectors using it can exchange their storage with `Vec<T, A>` without copying.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocatorProxy<A: Allocator + Clone>(pub A);
+
+/// Wraps the allocator `P` as a Rust [`Allocator`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CxxAllocator<P: CxxProxy>(pub P);
+
+/// Allocator handed out by the [`CxxProxy`] of an [`AllocatorProxy`].
+struct GlobalAllocator<A>(A);
+
+impl<A: Allocator + Clone> CxxProxy for AllocatorProxy<A> {
+    const IS_ALWAYS_EQUAL: bool = mem::size_of::<A>() == 0;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        GlobalAllocator(self.0.clone())
+    }
+}
+
+unsafe impl<A: Allocator> GlobalAlloc for GlobalAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0
+            .allocate(layout)
+            .map_or(ptr::null_mut(), |ptr| ptr.as_ptr().cast())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if let Some(ptr) = NonNull::new(ptr) {
+            unsafe { self.0.deallocate(ptr, layout) }
+        }
+    }
+}
+
+unsafe impl<P: CxxProxy> Allocator for CxxAllocator<P> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        // `GlobalAlloc` does not support zero-sized allocations.
+        let ptr = if layout.size() == 0 {
+            ptr::without_provenance_mut(layout.align())
+        } else {
+            unsafe { self.0.proxy().alloc(layout) }
+        };
+
+        NonNull::new(ptr)
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            unsafe { self.0.proxy().dealloc(ptr.as_ptr(), layout) }
+        }
+    }
+}
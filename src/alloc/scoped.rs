@@ -0,0 +1,159 @@
+//! Allocator that is passed on to the elements of a container.
+
+use core::alloc::GlobalAlloc;
+
+use super::{Batch, CxxProxy};
+
+/// `std::scoped_allocator_adaptor`.
+///
+/// Elements that a vector with this allocator creates itself are constructed
+/// with the allocator `A` through [`UsesAllocator`]: [`Clone`],
+/// [`resize`](crate::vec::CxxVecLayout::resize),
+/// [`resize_empty`](crate::vec::CxxVecLayout::resize_empty),
+/// [`emplace`](crate::vec::CxxVecLayout::emplace) and
+/// [`extend_scoped`](crate::vec::CxxVecLayout::extend_scoped) copy or create
+/// elements with it. Nesting it, as in
+/// `CxxVec<CxxVec<CxxNarrowString<A>, ScopedAlloc<A>>, ScopedAlloc<A>>`,
+/// passes `A` on to every level.
+///
+/// Elements moved in by [`push`](crate::vec::CxxVecLayout::push),
+/// [`extend`](Extend::extend) or [`resize_with`](crate::vec::CxxVecLayout::resize_with)
+/// keep the allocator they were created with.
+///
+/// It does not implement [`Clone`], which keeps containers using it out of
+/// the [`Clone`] and `resize` implementations for other allocators.
+/// [`ScopedAlloc::inner`] is the allocator to copy instead.
+#[repr(transparent)]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ScopedAlloc<A: CxxProxy>(pub A);
+
+/// Trait for containers that can be constructed with the allocator `A`, uses-allocator construction.
+pub trait UsesAllocator<A: CxxProxy>: Sized {
+    /// Creates an empty container.
+    fn new_with(alloc: A) -> Self;
+
+    /// Copies the container into storage allocated by `alloc`.
+    fn clone_with(&self, alloc: A) -> Self;
+}
+
+impl<A: CxxProxy> ScopedAlloc<A> {
+    /// The allocator passed on to the elements, `inner_allocator`.
+    pub fn inner(&self) -> &A {
+        &self.0
+    }
+}
+
+impl<A: CxxProxy> CxxProxy for ScopedAlloc<A> {
+    const PROPAGATE_ON_COPY_ASSIGNMENT: bool = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+
+    const PROPAGATE_ON_MOVE_ASSIGNMENT: bool = A::PROPAGATE_ON_MOVE_ASSIGNMENT;
+
+    const PROPAGATE_ON_SWAP: bool = A::PROPAGATE_ON_SWAP;
+
+    const IS_ALWAYS_EQUAL: bool = A::IS_ALWAYS_EQUAL;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        self.0.proxy()
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        self.0.is_equal(&other.0)
+    }
+
+    fn as_batch(&self) -> Option<Batch<'_>> {
+        self.0.as_batch()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::alloc::System;
+
+    use super::*;
+    use crate::{alloc::TrackingAlloc, string::CxxNarrowString, vec::CxxVec};
+
+    type Tracking = TrackingAlloc<System>;
+
+    type Inner = CxxVec<u32, Tracking>;
+
+    type Outer = CxxVec<Inner, ScopedAlloc<Tracking>>;
+
+    fn inner_in(alloc: &Tracking, len: u32) -> Inner {
+        let mut vec = CxxVec::new_in(alloc.clone());
+        vec.extend(0..len);
+        vec
+    }
+
+    #[test]
+    fn resize_copies_with_outer_allocator() {
+        let (alloc, other) = (Tracking::new(System), Tracking::new(System));
+        let mut outer = Outer::new_in(ScopedAlloc(alloc.clone()));
+
+        outer.resize(3, inner_in(&other, 2));
+
+        assert_eq!(other.stats().allocs, 1);
+        assert_eq!(alloc.stats().allocs, 4);
+        assert!(outer.iter().all(|inner| inner.as_slice() == [0, 1]));
+
+        outer[0].extend(2..100);
+        assert_eq!(other.stats().allocs, 1);
+        assert_eq!(alloc.stats().allocs, 5);
+    }
+
+    #[test]
+    fn clone_copies_with_outer_allocator() {
+        let (alloc, other) = (Tracking::new(System), Tracking::new(System));
+        let mut outer = Outer::new_in(ScopedAlloc(alloc.clone()));
+
+        outer.push(inner_in(&other, 2));
+        outer.emplace().push(5);
+        assert_eq!((alloc.stats().allocs, other.stats().allocs), (3, 1));
+
+        let copy = outer.clone();
+        assert_eq!((alloc.stats().allocs, other.stats().allocs), (6, 1));
+        assert_eq!(copy[0].as_slice(), [0, 1]);
+        assert_eq!(copy[1].as_slice(), [5]);
+
+        let mut assigned = Outer::new_in(ScopedAlloc(alloc.clone()));
+        assigned.push(inner_in(&other, 1));
+        assigned.clone_from(&outer);
+        // The storage of `assigned` grows once to fit the two copies.
+        assert_eq!((alloc.stats().allocs, other.stats().allocs), (10, 2));
+        assert_eq!(assigned, copy);
+
+        drop((outer, copy, assigned));
+        assert_eq!(alloc.stats().live_bytes, 0);
+        assert_eq!(other.stats().live_bytes, 0);
+    }
+
+    #[test]
+    fn nested_containers_get_outer_allocator() {
+        const LONG: &str = "a string that is allocated on the heap";
+
+        let (alloc, other) = (Tracking::new(System), Tracking::new(System));
+        let mut middle = CxxVec::new_in(ScopedAlloc(other.clone()));
+        middle.reserve(2);
+        middle.push(CxxNarrowString::from_bytes_in(LONG, other.clone()));
+        middle.push(CxxNarrowString::from_bytes_in(LONG, other.clone()));
+        assert_eq!(other.stats().allocs, 3);
+
+        let mut outer = CxxVec::new_in(ScopedAlloc(alloc.clone()));
+        outer.resize(2, middle);
+
+        assert_eq!(other.stats().allocs, 3);
+        assert_eq!(alloc.stats().allocs, 1 + 2 * 3);
+        assert!(outer
+            .iter()
+            .flatten()
+            .all(|s| s.as_bytes() == LONG.as_bytes()));
+
+        let copy = outer.clone();
+        assert_eq!(alloc.stats().allocs, 2 * (1 + 2 * 3));
+
+        drop((outer, copy));
+        assert_eq!(alloc.stats().live_bytes, 0);
+    }
+}
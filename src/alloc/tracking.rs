@@ -0,0 +1,461 @@
+//! Allocator that records the allocations passing through it.
+
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    backtrace::Backtrace,
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    vec::Vec,
+};
+
+use super::CxxProxy;
+
+/// Wraps the allocator `A`, recording every allocation and free made through it.
+///
+/// Frees of pointers it did not allocate are recorded as errors and skipped,
+/// and frees with another layout than the allocation are recorded as errors
+/// and freed with the layout of the allocation.
+///
+/// Clones share the records, so containers given clones of one `TrackingAlloc`
+/// are tracked together, and separately created ones keep separate statistics.
+/// Equality and the `std::allocator_traits` are those of `A`, so containers
+/// behave as they would without tracking. Memory that moves between containers
+/// whose allocators do not share the records is reported as an unknown free.
+pub struct TrackingAlloc<A: CxxProxy> {
+    inner: A,
+    tracker: Arc<Mutex<Tracker>>,
+}
+
+/// Allocation statistics of a [`TrackingAlloc`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    pub allocs: usize,
+    pub frees: usize,
+    pub live_bytes: usize,
+    pub peak_bytes: usize,
+}
+
+/// Point in the allocation history of a [`TrackingAlloc`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Checkpoint(u64);
+
+/// An allocation that has not been freed yet.
+#[derive(Clone, Debug)]
+pub struct Allocation {
+    pub addr: usize,
+    pub layout: Layout,
+    /// Captured if the allocator was created with [`TrackingAlloc::with_backtraces`].
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+/// Free that does not match an allocation of a [`TrackingAlloc`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrackingError {
+    /// The pointer was not allocated, or was already freed.
+    UnknownFree { addr: usize, layout: Layout },
+    /// The size or alignment differs from the allocation.
+    LayoutMismatch {
+        addr: usize,
+        allocated: Layout,
+        freed: Layout,
+    },
+}
+
+/// Outstanding allocations and errors of a [`TrackingAlloc`] at a checkpoint.
+#[derive(Clone, Debug)]
+pub struct LeakReport {
+    pub stats: AllocStats,
+    pub outstanding: Vec<Allocation>,
+    pub errors: Vec<TrackingError>,
+}
+
+struct Record {
+    seq: u64,
+    layout: Layout,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+#[derive(Default)]
+struct Tracker {
+    live: HashMap<usize, Record>,
+    errors: Vec<TrackingError>,
+    stats: AllocStats,
+    seq: u64,
+    backtraces: bool,
+}
+
+/// Allocator handed out by [`TrackingAlloc::proxy`](CxxProxy::proxy).
+struct TrackingProxy<P> {
+    inner: P,
+    tracker: Arc<Mutex<Tracker>>,
+}
+
+impl<A: CxxProxy> TrackingAlloc<A> {
+    pub fn new(inner: A) -> Self {
+        Self::with_tracker(inner, Tracker::default())
+    }
+
+    /// Creates an allocator that also captures a backtrace for every allocation.
+    pub fn with_backtraces(inner: A) -> Self {
+        Self::with_tracker(
+            inner,
+            Tracker {
+                backtraces: true,
+                ..Tracker::default()
+            },
+        )
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+
+    pub fn stats(&self) -> AllocStats {
+        self.lock().stats
+    }
+
+    /// Marks the current point in the history, to only report later allocations.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.lock().seq)
+    }
+
+    /// Allocations made since `since` that have not been freed, in allocation order.
+    pub fn outstanding(&self, since: Checkpoint) -> Vec<Allocation> {
+        let tracker = self.lock();
+
+        let mut records: Vec<_> = tracker
+            .live
+            .iter()
+            .filter(|(_, record)| record.seq >= since.0)
+            .collect();
+
+        records.sort_by_key(|(_, record)| record.seq);
+
+        records
+            .into_iter()
+            .map(|(&addr, record)| Allocation {
+                addr,
+                layout: record.layout,
+                backtrace: record.backtrace.clone(),
+            })
+            .collect()
+    }
+
+    pub fn errors(&self) -> Vec<TrackingError> {
+        self.lock().errors.clone()
+    }
+
+    /// Checks that every allocation made since `since` has been freed,
+    /// and that no free was erroneous.
+    pub fn check(&self, since: Checkpoint) -> Result<(), LeakReport> {
+        let report = LeakReport {
+            stats: self.stats(),
+            outstanding: self.outstanding(since),
+            errors: self.errors(),
+        };
+
+        if report.outstanding.is_empty() && report.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(report)
+        }
+    }
+
+    fn with_tracker(inner: A, tracker: Tracker) -> Self {
+        Self {
+            inner,
+            tracker: Arc::new(Mutex::new(tracker)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tracker> {
+        lock(&self.tracker)
+    }
+}
+
+impl<A: CxxProxy> CxxProxy for TrackingAlloc<A> {
+    const PROPAGATE_ON_COPY_ASSIGNMENT: bool = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+
+    const PROPAGATE_ON_MOVE_ASSIGNMENT: bool = A::PROPAGATE_ON_MOVE_ASSIGNMENT;
+
+    const PROPAGATE_ON_SWAP: bool = A::PROPAGATE_ON_SWAP;
+
+    const IS_ALWAYS_EQUAL: bool = A::IS_ALWAYS_EQUAL;
+
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        TrackingProxy {
+            inner: self.inner.proxy(),
+            tracker: self.tracker.clone(),
+        }
+    }
+
+    /// Allocators are equal if their inner allocators are equal.
+    fn is_equal(&self, other: &Self) -> bool {
+        self.inner.is_equal(&other.inner)
+    }
+}
+
+impl<A: CxxProxy + Clone> Clone for TrackingAlloc<A> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            tracker: self.tracker.clone(),
+        }
+    }
+}
+
+impl<A: CxxProxy + fmt::Debug> fmt::Debug for TrackingAlloc<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackingAlloc")
+            .field("inner", &self.inner)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+unsafe impl<P: GlobalAlloc> GlobalAlloc for TrackingProxy<P> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { self.inner.alloc(layout) };
+
+        if !ptr.is_null() {
+            let mut tracker = lock(&self.tracker);
+            let backtrace = tracker
+                .backtraces
+                .then(|| Arc::new(Backtrace::force_capture()));
+            let seq = tracker.seq;
+
+            tracker.seq += 1;
+            tracker.stats.allocs += 1;
+            tracker.stats.live_bytes += layout.size();
+            tracker.stats.peak_bytes = tracker.stats.peak_bytes.max(tracker.stats.live_bytes);
+
+            tracker.live.insert(
+                ptr as usize,
+                Record {
+                    seq,
+                    layout,
+                    backtrace,
+                },
+            );
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let mut tracker = lock(&self.tracker);
+
+        let Some(record) = tracker.live.remove(&(ptr as usize)) else {
+            tracker.errors.push(TrackingError::UnknownFree {
+                addr: ptr as usize,
+                layout,
+            });
+            return;
+        };
+
+        if record.layout != layout {
+            tracker.errors.push(TrackingError::LayoutMismatch {
+                addr: ptr as usize,
+                allocated: record.layout,
+                freed: layout,
+            });
+        }
+
+        tracker.stats.frees += 1;
+        tracker.stats.live_bytes -= record.layout.size();
+        drop(tracker);
+
+        unsafe { self.inner.dealloc(ptr, record.layout) }
+    }
+}
+
+/// Locks the records, which stay consistent even if a thread panicked while holding them.
+fn lock(tracker: &Mutex<Tracker>) -> MutexGuard<'_, Tracker> {
+    tracker.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl fmt::Display for TrackingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownFree { addr, layout } => write!(
+                f,
+                "free of unknown pointer {addr:#x} ({} bytes, alignment {})",
+                layout.size(),
+                layout.align()
+            ),
+            Self::LayoutMismatch {
+                addr,
+                allocated,
+                freed,
+            } => write!(
+                f,
+                "free of {addr:#x} with {} bytes, alignment {}, allocated with {} bytes, alignment {}",
+                freed.size(),
+                freed.align(),
+                allocated.size(),
+                allocated.align()
+            ),
+        }
+    }
+}
+
+impl core::error::Error for TrackingError {}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes: usize = self.outstanding.iter().map(|a| a.layout.size()).sum();
+
+        write!(
+            f,
+            "{} outstanding allocations ({bytes} bytes), {} errors",
+            self.outstanding.len(),
+            self.errors.len()
+        )?;
+
+        for allocation in &self.outstanding {
+            write!(
+                f,
+                "\n  {:#x}: {} bytes, alignment {}",
+                allocation.addr,
+                allocation.layout.size(),
+                allocation.layout.align()
+            )?;
+
+            if let Some(backtrace) = &allocation.backtrace {
+                write!(f, "\n{backtrace}")?;
+            }
+        }
+
+        for error in &self.errors {
+            write!(f, "\n  {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl core::error::Error for LeakReport {}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::System;
+
+    use super::*;
+    use crate::{alloc::Arena, vec::CxxVec};
+
+    #[test]
+    fn reports_leaks_since_checkpoint() {
+        let alloc = TrackingAlloc::new(System);
+        let proxy = alloc.proxy();
+        let layout = Layout::new::<u64>();
+
+        let old = unsafe { proxy.alloc(layout) };
+        let since = alloc.checkpoint();
+        let new = unsafe { proxy.alloc(layout) };
+
+        let report = alloc.check(since).unwrap_err();
+        assert_eq!(report.outstanding.len(), 1);
+        assert_eq!(report.outstanding[0].addr, new as usize);
+        assert_eq!(report.outstanding[0].layout, layout);
+        assert!(report.errors.is_empty());
+
+        unsafe {
+            proxy.dealloc(new, layout);
+            proxy.dealloc(old, layout);
+        }
+
+        assert!(alloc.check(since).is_ok());
+        assert_eq!(
+            alloc.stats(),
+            AllocStats {
+                allocs: 2,
+                frees: 2,
+                live_bytes: 0,
+                peak_bytes: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_double_free() {
+        let alloc = TrackingAlloc::new(System);
+        let proxy = alloc.proxy();
+        let layout = Layout::new::<u32>();
+
+        unsafe {
+            let ptr = proxy.alloc(layout);
+            proxy.dealloc(ptr, layout);
+            proxy.dealloc(ptr, layout);
+
+            assert_eq!(
+                alloc.errors(),
+                [TrackingError::UnknownFree {
+                    addr: ptr as usize,
+                    layout,
+                }]
+            );
+        }
+
+        assert_eq!(alloc.stats().frees, 1);
+        assert_eq!(alloc.check(alloc.checkpoint()).unwrap_err().errors.len(), 1);
+    }
+
+    #[test]
+    fn reports_layout_mismatch() {
+        let alloc = TrackingAlloc::new(System);
+        let proxy = alloc.proxy();
+        let allocated = Layout::from_size_align(32, 8).unwrap();
+        let freed = Layout::from_size_align(16, 8).unwrap();
+
+        unsafe {
+            let ptr = proxy.alloc(allocated);
+            proxy.dealloc(ptr, freed);
+
+            assert_eq!(
+                alloc.errors(),
+                [TrackingError::LayoutMismatch {
+                    addr: ptr as usize,
+                    allocated,
+                    freed,
+                }]
+            );
+        }
+
+        // The allocation is still freed, with its own layout.
+        assert_eq!(alloc.stats().live_bytes, 0);
+        assert!(alloc.outstanding(Checkpoint(0)).is_empty());
+    }
+
+    #[test]
+    fn clones_share_records() {
+        let alloc = TrackingAlloc::new(System);
+        let mut a = CxxVec::new_in(alloc.clone());
+        let mut b = CxxVec::new_in(alloc.clone());
+
+        a.push(1u8);
+        b.push(2u8);
+        assert_eq!(alloc.stats().allocs, 2);
+
+        drop((a, b));
+        assert!(alloc.check(Checkpoint(0)).is_ok());
+    }
+
+    #[test]
+    fn forwards_allocator_traits() {
+        let (a, b) = (TrackingAlloc::new(System), TrackingAlloc::new(System));
+
+        const { assert!(TrackingAlloc::<System>::IS_ALWAYS_EQUAL) };
+        assert!(a.is_equal(&b));
+
+        let (x, y) = (Arena::new(), Arena::new());
+        let c = TrackingAlloc::new(&x);
+
+        const { assert!(!TrackingAlloc::<&Arena>::IS_ALWAYS_EQUAL) };
+        assert!(c.is_equal(&TrackingAlloc::new(&x)));
+        assert!(!c.is_equal(&TrackingAlloc::new(&y)));
+    }
+}
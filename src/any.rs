@@ -0,0 +1,476 @@
+//! `std::any` with the MSVC layout.
+//!
+//! Values are stored inline in one of two representations when they fit,
+//! and allocated otherwise. The representation and the `std::type_info` of
+//! the value are packed into a single pointer-sized tag.
+//!
+//! Rust values get a generated `std::type_info` that is unique for each type,
+//! so C++ can hold and copy them, but not cast them to any C++ type.
+
+use core::{
+    ffi::c_void,
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+};
+
+use rust_alloc::boxed::Box;
+
+use crate::rtti;
+
+/// Number of pointers in the storage of `std::any`.
+const SMALL_PTRS: usize = 6 + 16 / mem::size_of::<*const c_void>();
+
+/// Size of the inline buffer of trivially copyable values.
+const TRIVIAL_SPACE: usize = (SMALL_PTRS - 1) * mem::size_of::<*const c_void>();
+
+/// Size of the inline buffer of other values.
+const SMALL_SPACE: usize = (SMALL_PTRS - 2) * mem::size_of::<*const c_void>();
+
+/// Alignment of `max_align_t`, the largest alignment of inline values.
+const MAX_ALIGN: usize = 8;
+
+/// Bits of the tag holding the [`AnyKind`].
+const KIND_MASK: usize = 3;
+
+/// Representation of the value of a [`CxxAny`], `_Any_representation`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(usize)]
+pub enum AnyKind {
+    /// Trivially copyable value stored inline.
+    Trivial = 0,
+    /// Allocated value.
+    Big = 1,
+    /// Value stored inline, copied and destroyed through a table of functions.
+    Small = 2,
+}
+
+/// Functions of a value stored inline, `_Any_small_RTTI`.
+#[repr(C)]
+struct SmallRtti {
+    destroy: unsafe extern "C" fn(*mut c_void),
+    copy: unsafe extern "C" fn(*mut c_void, *const c_void),
+    move_: unsafe extern "C" fn(*mut c_void, *mut c_void),
+}
+
+/// Functions of an allocated value, `_Any_big_RTTI`.
+#[repr(C)]
+struct BigRtti {
+    destroy: unsafe extern "C" fn(*mut c_void),
+    copy: unsafe extern "C" fn(*const c_void) -> *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SmallStorage {
+    data: MaybeUninit<[u8; SMALL_SPACE]>,
+    rtti: *const SmallRtti,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BigStorage {
+    _padding: MaybeUninit<[u8; SMALL_SPACE - mem::size_of::<*const c_void>()]>,
+    ptr: *mut c_void,
+    rtti: *const BigRtti,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+union Storage {
+    trivial: MaybeUninit<[u8; TRIVIAL_SPACE]>,
+    small: SmallStorage,
+    big: BigStorage,
+}
+
+/// `std::any`.
+///
+/// A `CxxAny` is moved bitwise by Rust. Values stored inline by C++ are
+/// required to be nothrow move constructible instead, so an owned `CxxAny`
+/// holding a C++ value, such as a clone of one shared by C++, may only be
+/// moved if the value is trivially relocatable, as most types are.
+#[repr(C, align(8))]
+pub struct CxxAny {
+    storage: Storage,
+    type_data: usize,
+}
+
+/// Functions of the Rust type `T` for both representations.
+struct Funcs<T>(PhantomData<T>);
+
+impl CxxAny {
+    /// Creates an empty any.
+    pub const fn new() -> Self {
+        Self {
+            storage: Storage {
+                trivial: MaybeUninit::uninit(),
+            },
+            type_data: 0,
+        }
+    }
+
+    /// Creates an any holding `value`, stored inline if it fits.
+    ///
+    /// C++ copies the value with [`Clone`].
+    pub fn from_value<T: Clone + 'static>(value: T) -> Self {
+        let mut new = Self::new();
+
+        unsafe {
+            if mem::size_of::<T>() <= SMALL_SPACE && mem::align_of::<T>() <= MAX_ALIGN {
+                new.storage.small.rtti = Funcs::<T>::SMALL;
+                new.small_ptr().cast::<T>().write(value);
+                new.set_type_data::<T>(AnyKind::Small);
+            } else {
+                new.storage.big.rtti = Funcs::<T>::BIG;
+                new.storage.big.ptr = Box::into_raw(Box::new(value)).cast();
+                new.set_type_data::<T>(AnyKind::Big);
+            }
+        }
+
+        new
+    }
+
+    /// Creates an any holding `value`, stored inline as a trivially copyable value
+    /// if it fits, like C++ does for such types.
+    pub fn from_copy<T: Copy + 'static>(value: T) -> Self {
+        if mem::size_of::<T>() > TRIVIAL_SPACE || mem::align_of::<T>() > MAX_ALIGN {
+            return Self::from_value(value);
+        }
+
+        let mut new = Self::new();
+
+        unsafe {
+            new.storage.trivial.as_mut_ptr().cast::<T>().write(value);
+            new.set_type_data::<T>(AnyKind::Trivial);
+        }
+
+        new
+    }
+
+    pub fn has_value(&self) -> bool {
+        self.type_data != 0
+    }
+
+    /// Representation of the value, if any.
+    pub fn kind(&self) -> Option<AnyKind> {
+        match self.type_data & KIND_MASK {
+            _ if !self.has_value() => None,
+            0 => Some(AnyKind::Trivial),
+            1 => Some(AnyKind::Big),
+            _ => Some(AnyKind::Small),
+        }
+    }
+
+    /// Pointer to the `std::type_info` of the value, or null if there is none.
+    pub fn type_info(&self) -> *const c_void {
+        (self.type_data & !KIND_MASK) as *const c_void
+    }
+
+    /// Whether the value is of the Rust type `T`.
+    pub fn is<T: 'static>(&self) -> bool {
+        self.has_value()
+            && rtti::find_type_info::<T>()
+                .is_some_and(|info| unsafe { rtti::type_info_eq(self.type_info(), info) })
+    }
+
+    /// Returns the value if it is of the Rust type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        let info = rtti::find_type_info::<T>()?;
+        unsafe { self.downcast_ref_raw(info) }
+    }
+
+    /// Returns the value if it is of the Rust type `T`.
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        let info = rtti::find_type_info::<T>()?;
+        unsafe { self.downcast_mut_raw(info) }
+    }
+
+    /// Returns the value if its `std::type_info` equals `type_info`, like `std::any_cast`.
+    ///
+    /// # Safety
+    ///
+    /// `type_info` must point to the `std::type_info` of the C++ equivalent of `T`.
+    pub unsafe fn downcast_ref_raw<T>(&self, type_info: *const c_void) -> Option<&T> {
+        unsafe { self.value_ptr(type_info).map(|value| &*value.cast::<T>()) }
+    }
+
+    /// Returns the value if its `std::type_info` equals `type_info`, like `std::any_cast`.
+    ///
+    /// # Safety
+    ///
+    /// `type_info` must point to the `std::type_info` of the C++ equivalent of `T`.
+    pub unsafe fn downcast_mut_raw<T>(&mut self, type_info: *const c_void) -> Option<&mut T> {
+        unsafe {
+            self.value_ptr(type_info)
+                .map(|value| &mut *value.cast_mut().cast::<T>())
+        }
+    }
+
+    /// Destroys the value, if any.
+    pub fn reset(&mut self) {
+        unsafe {
+            match self.kind() {
+                Some(AnyKind::Small) => ((*self.storage.small.rtti).destroy)(self.small_ptr()),
+                Some(AnyKind::Big) => ((*self.storage.big.rtti).destroy)(self.storage.big.ptr),
+                _ => (),
+            }
+        }
+
+        self.type_data = 0;
+    }
+
+    fn set_type_data<T: 'static>(&mut self, kind: AnyKind) {
+        self.type_data = rtti::type_info_of::<T>() as usize | kind as usize;
+    }
+
+    fn small_ptr(&mut self) -> *mut c_void {
+        unsafe { self.storage.small.data.as_mut_ptr().cast() }
+    }
+
+    /// Pointer to the value if its `std::type_info` equals `type_info`.
+    unsafe fn value_ptr(&self, type_info: *const c_void) -> Option<*const c_void> {
+        if !self.has_value() || !unsafe { rtti::type_info_eq(self.type_info(), type_info) } {
+            return None;
+        }
+
+        unsafe {
+            Some(match self.kind() {
+                Some(AnyKind::Big) => self.storage.big.ptr.cast_const(),
+                _ => self.storage.trivial.as_ptr().cast(),
+            })
+        }
+    }
+}
+
+impl<T: Clone + 'static> Funcs<T> {
+    const SMALL: &'static SmallRtti = &SmallRtti {
+        destroy: Self::destroy_small,
+        copy: Self::copy_small,
+        move_: Self::move_small,
+    };
+
+    const BIG: &'static BigRtti = &BigRtti {
+        destroy: Self::destroy_big,
+        copy: Self::copy_big,
+    };
+
+    unsafe extern "C" fn destroy_small(target: *mut c_void) {
+        unsafe {
+            target.cast::<T>().drop_in_place();
+        }
+    }
+
+    unsafe extern "C" fn copy_small(target: *mut c_void, source: *const c_void) {
+        unsafe {
+            target.cast::<T>().write((*source.cast::<T>()).clone());
+        }
+    }
+
+    unsafe extern "C" fn move_small(target: *mut c_void, source: *mut c_void) {
+        // A C++ move has to leave the source initialized, so the value is cloned.
+        unsafe { Self::copy_small(target, source) }
+    }
+
+    unsafe extern "C" fn destroy_big(target: *mut c_void) {
+        unsafe {
+            drop(Box::from_raw(target.cast::<T>()));
+        }
+    }
+
+    unsafe extern "C" fn copy_big(source: *const c_void) -> *mut c_void {
+        unsafe { Box::into_raw(Box::new((*source.cast::<T>()).clone())).cast() }
+    }
+}
+
+impl Clone for CxxAny {
+    fn clone(&self) -> Self {
+        let mut new = Self::new();
+
+        unsafe {
+            match self.kind() {
+                Some(AnyKind::Small) => {
+                    let rtti = self.storage.small.rtti;
+                    let source = self.storage.small.data.as_ptr().cast();
+
+                    ((*rtti).copy)(new.small_ptr(), source);
+                    new.storage.small.rtti = rtti;
+                }
+                Some(AnyKind::Big) => {
+                    let rtti = self.storage.big.rtti;
+
+                    new.storage.big.ptr = ((*rtti).copy)(self.storage.big.ptr);
+                    new.storage.big.rtti = rtti;
+                }
+                Some(AnyKind::Trivial) => new.storage = self.storage,
+                None => (),
+            }
+        }
+
+        new.type_data = self.type_data;
+        new
+    }
+}
+
+impl fmt::Debug for CxxAny {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let type_name = self
+            .has_value()
+            .then(|| unsafe { rtti::decorated_name(self.type_info()) });
+
+        f.debug_struct("CxxAny")
+            .field("kind", &self.kind())
+            .field("type_name", &type_name)
+            .finish()
+    }
+}
+
+impl Default for CxxAny {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for CxxAny {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn downcast_to_stored_type() {
+        let mut any = CxxAny::from_value(5u32);
+
+        assert!(any.is::<u32>());
+        assert_eq!(any.downcast_ref::<u32>(), Some(&5));
+
+        *any.downcast_mut::<u32>().unwrap() = 6;
+        assert_eq!(any.downcast_ref::<u32>(), Some(&6));
+    }
+
+    #[test]
+    fn queries_do_not_register_types() {
+        struct Absent;
+
+        let mut any = CxxAny::from_copy(1u8);
+
+        assert!(!any.is::<Absent>());
+        assert!(any.downcast_ref::<Absent>().is_none());
+        assert!(any.downcast_mut::<Absent>().is_none());
+        assert!(!CxxAny::default().is::<Absent>());
+        assert!(rtti::find_type_info::<Absent>().is_none());
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn layout_x64() {
+        assert_eq!(mem::size_of::<CxxAny>(), 64);
+        assert_eq!(mem::align_of::<CxxAny>(), 8);
+        assert_eq!(mem::offset_of!(CxxAny, type_data), 56);
+        assert_eq!(TRIVIAL_SPACE, 56);
+        assert_eq!(SMALL_SPACE, 48);
+    }
+
+    #[test]
+    fn kind_follows_size_and_alignment() {
+        #[derive(Clone, Copy)]
+        #[repr(align(16))]
+        struct Overaligned;
+
+        assert_eq!(CxxAny::new().kind(), None);
+        assert_eq!(CxxAny::from_copy(1u64).kind(), Some(AnyKind::Trivial));
+        assert_eq!(
+            CxxAny::from_copy([0u8; TRIVIAL_SPACE]).kind(),
+            Some(AnyKind::Trivial)
+        );
+        assert_eq!(
+            CxxAny::from_copy([0u8; TRIVIAL_SPACE + 1]).kind(),
+            Some(AnyKind::Big)
+        );
+        assert_eq!(CxxAny::from_copy(Overaligned).kind(), Some(AnyKind::Big));
+
+        assert_eq!(CxxAny::from_value(1u64).kind(), Some(AnyKind::Small));
+        assert_eq!(
+            CxxAny::from_value([0u8; SMALL_SPACE]).kind(),
+            Some(AnyKind::Small)
+        );
+        assert_eq!(
+            CxxAny::from_value([0u8; SMALL_SPACE + 1]).kind(),
+            Some(AnyKind::Big)
+        );
+        assert_eq!(CxxAny::from_value(Overaligned).kind(), Some(AnyKind::Big));
+    }
+
+    #[test]
+    fn clone_trivial() {
+        let any = CxxAny::from_copy([1u32, 2, 3]);
+        let mut copy = any.clone();
+
+        assert_eq!(copy.kind(), Some(AnyKind::Trivial));
+        copy.downcast_mut::<[u32; 3]>().unwrap()[0] = 4;
+
+        assert_eq!(any.downcast_ref::<[u32; 3]>(), Some(&[1, 2, 3]));
+        assert_eq!(copy.downcast_ref::<[u32; 3]>(), Some(&[4, 2, 3]));
+    }
+
+    #[test]
+    fn clone_small() {
+        let value = Rc::new(5);
+        let any = CxxAny::from_value(value.clone());
+        let copy = any.clone();
+
+        assert_eq!(copy.kind(), Some(AnyKind::Small));
+        assert_eq!(Rc::strong_count(&value), 3);
+        assert!(Rc::ptr_eq(copy.downcast_ref::<Rc<i32>>().unwrap(), &value));
+
+        drop(any);
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(copy);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn clone_big() {
+        #[derive(Clone)]
+        struct Big {
+            value: Rc<i32>,
+            _padding: [u8; SMALL_SPACE],
+        }
+
+        let value = Rc::new(5);
+        let any = CxxAny::from_value(Big {
+            value: value.clone(),
+            _padding: [0; SMALL_SPACE],
+        });
+        let copy = any.clone();
+
+        assert_eq!(copy.kind(), Some(AnyKind::Big));
+        assert_eq!(Rc::strong_count(&value), 3);
+        assert!(Rc::ptr_eq(
+            &copy.downcast_ref::<Big>().unwrap().value,
+            &value
+        ));
+        assert_ne!(
+            any.downcast_ref::<Big>().unwrap() as *const Big,
+            copy.downcast_ref::<Big>().unwrap() as *const Big
+        );
+
+        drop(any);
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(copy);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn clone_empty() {
+        let copy = CxxAny::new().clone();
+
+        assert!(!copy.has_value());
+        assert_eq!(copy.kind(), None);
+    }
+}
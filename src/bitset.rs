@@ -0,0 +1,577 @@
+//! `std::bitset` with the MSVC layout.
+
+use core::{
+    fmt,
+    hash::Hash,
+    ops::{
+        BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+        ShrAssign,
+    },
+};
+
+/// Marker for the number of bits of a [`CxxBitset`].
+pub struct Bits<const N: usize>;
+
+/// Trait for words of a `std::bitset`, `unsigned long` or `unsigned long long`.
+pub trait BitsetWord: Copy + Eq + Hash {
+    const BITS: usize;
+
+    const ZERO: Self;
+
+    const ONES: Self;
+
+    fn count_ones(self) -> u32;
+
+    fn bit(self, index: usize) -> bool;
+
+    fn with_bit(self, index: usize, value: bool) -> Self;
+
+    /// Word with the lowest `len` bits set.
+    fn low_bits(len: usize) -> Self;
+
+    fn and(self, other: Self) -> Self;
+
+    fn or(self, other: Self) -> Self;
+
+    fn xor(self, other: Self) -> Self;
+
+    fn not(self) -> Self;
+}
+
+/// Trait for the layout of a `std::bitset` with a given number of bits.
+///
+/// MSVC uses an array of `unsigned long` for up to 32 bits and of
+/// `unsigned long long` otherwise, with at least one word.
+/// Implemented for [`Bits<N>`] with `N` of up to 1024.
+pub trait BitsetLayout {
+    type Word: BitsetWord;
+
+    type Words: Copy + Eq + Hash + AsRef<[Self::Word]> + AsMut<[Self::Word]>;
+
+    const ZERO: Self::Words;
+}
+
+/// `std::bitset<N>`.
+///
+/// `N` is at most 1024, since [`BitsetLayout`] is only implemented up to there.
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CxxBitset<const N: usize>
+where
+    Bits<N>: BitsetLayout,
+{
+    words: <Bits<N> as BitsetLayout>::Words,
+}
+
+type Word<const N: usize> = <Bits<N> as BitsetLayout>::Word;
+
+impl<const N: usize> CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    /// Creates a bitset with all bits unset.
+    pub const fn new() -> Self {
+        Self {
+            words: <Bits<N> as BitsetLayout>::ZERO,
+        }
+    }
+
+    /// Creates a bitset from the lowest `N` bits of `value`.
+    pub fn from_u64(value: u64) -> Self {
+        let mut new = Self::new();
+
+        for index in 0..N.min(64) {
+            new.set(index, value & (1 << index) != 0);
+        }
+
+        new
+    }
+
+    /// Converts the bitset to an integer,
+    /// or returns [`None`] if a bit past the 64th one is set.
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.iter().skip(64).any(|bit| bit) {
+            return None;
+        }
+
+        Some(
+            self.iter()
+                .take(64)
+                .enumerate()
+                .fold(0, |value, (index, bit)| value | (bit as u64) << index),
+        )
+    }
+
+    /// Words holding the bits, least significant bit first.
+    pub fn as_words(&self) -> &[Word<N>] {
+        self.words.as_ref()
+    }
+
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < N).then(|| self.bit(index))
+    }
+
+    /// Sets the bit at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < N, "index {index} out of range for length {N}");
+
+        let words = self.words.as_mut();
+        let word = &mut words[index / Word::<N>::BITS];
+        *word = word.with_bit(index % Word::<N>::BITS, value);
+    }
+
+    /// Flips the bit at `index`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn toggle(&mut self, index: usize) {
+        let value = self.get(index).is_some_and(|bit| !bit);
+        self.set(index, value);
+    }
+
+    /// Sets every bit.
+    pub fn set_all(&mut self) {
+        self.words.as_mut().fill(Word::<N>::ONES);
+        self.trim();
+    }
+
+    /// Unsets every bit.
+    pub fn reset(&mut self) {
+        self.words.as_mut().fill(Word::<N>::ZERO);
+    }
+
+    /// Flips every bit.
+    pub fn flip(&mut self) {
+        self.words
+            .as_mut()
+            .iter_mut()
+            .for_each(|word| *word = word.not());
+        self.trim();
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.as_words()
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        N - self.count_ones()
+    }
+
+    pub fn all(&self) -> bool {
+        self.count_ones() == N
+    }
+
+    pub fn any(&self) -> bool {
+        self.as_words().iter().any(|&word| word != Word::<N>::ZERO)
+    }
+
+    pub fn none(&self) -> bool {
+        !self.any()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = bool> + ExactSizeIterator + '_ {
+        (0..N).map(|index| self.bit(index))
+    }
+
+    fn bit(&self, index: usize) -> bool {
+        self.as_words()[index / Word::<N>::BITS].bit(index % Word::<N>::BITS)
+    }
+
+    /// Clears the bits past `N` in the last word.
+    fn trim(&mut self) {
+        let used = N % Word::<N>::BITS;
+
+        if used != 0 || N == 0 {
+            if let Some(last) = self.words.as_mut().last_mut() {
+                *last = last.and(Word::<N>::low_bits(used));
+            }
+        }
+    }
+
+    fn zip_words<F>(&mut self, other: &Self, f: F)
+    where
+        F: Fn(Word<N>, Word<N>) -> Word<N>,
+    {
+        self.words
+            .as_mut()
+            .iter_mut()
+            .zip(other.as_words())
+            .for_each(|(word, &other)| *word = f(*word, other));
+    }
+}
+
+impl<const N: usize> fmt::Debug for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CxxBitset({self})")
+    }
+}
+
+/// Formats the bits like `std::bitset::to_string`, most significant bit first.
+impl<const N: usize> fmt::Display for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter()
+            .rev()
+            .try_for_each(|bit| f.write_str(if bit { "1" } else { "0" }))
+    }
+}
+
+impl<const N: usize> Default for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// # Panics
+///
+/// If the iterator yields more than `N` bits.
+impl<const N: usize> FromIterator<bool> for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut new = Self::new();
+
+        for (index, bit) in iter.into_iter().enumerate() {
+            assert!(index < N, "too many bits for a bitset of length {N}");
+            new.set(index, bit);
+        }
+
+        new
+    }
+}
+
+impl<const N: usize> Not for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    type Output = Self;
+
+    fn not(mut self) -> Self::Output {
+        self.flip();
+        self
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($($trait:ident $fn:ident $assign_trait:ident $assign_fn:ident $op:ident),*) => {$(
+        impl<const N: usize> $assign_trait for CxxBitset<N>
+        where
+            Bits<N>: BitsetLayout,
+        {
+            fn $assign_fn(&mut self, rhs: Self) {
+                self.zip_words(&rhs, BitsetWord::$op);
+            }
+        }
+
+        impl<const N: usize> $trait for CxxBitset<N>
+        where
+            Bits<N>: BitsetLayout,
+        {
+            type Output = Self;
+
+            fn $fn(mut self, rhs: Self) -> Self::Output {
+                self.$assign_fn(rhs);
+                self
+            }
+        }
+    )*};
+}
+
+impl_bit_op!(
+    BitAnd bitand BitAndAssign bitand_assign and,
+    BitOr bitor BitOrAssign bitor_assign or,
+    BitXor bitxor BitXorAssign bitxor_assign xor
+);
+
+impl<const N: usize> ShlAssign<usize> for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn shl_assign(&mut self, rhs: usize) {
+        let old = *self;
+        self.reset();
+
+        for (index, bit) in (rhs..N).zip(old.iter()) {
+            self.set(index, bit);
+        }
+    }
+}
+
+impl<const N: usize> ShrAssign<usize> for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    fn shr_assign(&mut self, rhs: usize) {
+        let old = *self;
+        self.reset();
+
+        for (index, bit) in old.iter().skip(rhs).enumerate() {
+            self.set(index, bit);
+        }
+    }
+}
+
+impl<const N: usize> Shl<usize> for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    type Output = Self;
+
+    fn shl(mut self, rhs: usize) -> Self::Output {
+        self <<= rhs;
+        self
+    }
+}
+
+impl<const N: usize> Shr<usize> for CxxBitset<N>
+where
+    Bits<N>: BitsetLayout,
+{
+    type Output = Self;
+
+    fn shr(mut self, rhs: usize) -> Self::Output {
+        self >>= rhs;
+        self
+    }
+}
+
+macro_rules! impl_bitset_word {
+    ($($t:ty),*) => {$(
+        impl BitsetWord for $t {
+            const BITS: usize = <$t>::BITS as usize;
+
+            const ZERO: Self = 0;
+
+            const ONES: Self = <$t>::MAX;
+
+            fn count_ones(self) -> u32 {
+                <$t>::count_ones(self)
+            }
+
+            fn bit(self, index: usize) -> bool {
+                self & (1 << index) != 0
+            }
+
+            fn with_bit(self, index: usize, value: bool) -> Self {
+                if value {
+                    self | (1 << index)
+                } else {
+                    self & !(1 << index)
+                }
+            }
+
+            fn low_bits(len: usize) -> Self {
+                <$t>::MAX.checked_shr((<Self as BitsetWord>::BITS - len) as u32).unwrap_or(0)
+            }
+
+            fn and(self, other: Self) -> Self {
+                self & other
+            }
+
+            fn or(self, other: Self) -> Self {
+                self | other
+            }
+
+            fn xor(self, other: Self) -> Self {
+                self ^ other
+            }
+
+            fn not(self) -> Self {
+                !self
+            }
+        }
+    )*};
+}
+
+impl_bitset_word!(u32, u64);
+
+/// Selects the word type of a `std::bitset`.
+pub struct SmallBitset<const SMALL: bool>;
+
+pub trait SelectWord {
+    type Word: BitsetWord;
+}
+
+impl SelectWord for SmallBitset<true> {
+    type Word = u32;
+}
+
+impl SelectWord for SmallBitset<false> {
+    type Word = u64;
+}
+
+/// Whether a `std::bitset` with `bits` bits uses `unsigned long` words.
+const fn is_small(bits: usize) -> bool {
+    bits <= 32
+}
+
+/// Number of words of a `std::bitset` with `bits` bits.
+const fn word_count(bits: usize) -> usize {
+    if is_small(bits) {
+        1
+    } else {
+        (bits - 1) / 64 + 1
+    }
+}
+
+macro_rules! impl_bitset_layout {
+    ($th:tt $h:tt $t:tt $u:tt) => {
+        impl BitsetLayout for Bits<{ $th * 1000 + $h * 100 + $t * 10 + $u }> {
+            type Word =
+                <SmallBitset<{ is_small($th * 1000 + $h * 100 + $t * 10 + $u) }> as SelectWord>::Word;
+
+            type Words = [Self::Word; word_count($th * 1000 + $h * 100 + $t * 10 + $u)];
+
+            const ZERO: Self::Words = [0; word_count($th * 1000 + $h * 100 + $t * 10 + $u)];
+        }
+    };
+}
+
+/// Invokes `impl_bitset_layout!` with the decimal digits of every number up to 1024.
+macro_rules! for_each_bits {
+    (@h $th:tt [$($h:tt)*] $d:tt) => {
+        $(for_each_bits!(@t $th $h $d $d);)*
+    };
+    (@t $th:tt $h:tt [$($t:tt)*] $d:tt) => {
+        $(for_each_bits!(@u $th $h $t $d);)*
+    };
+    (@u $th:tt $h:tt $t:tt [$($u:tt)*]) => {
+        $(impl_bitset_layout!($th $h $t $u);)*
+    };
+    () => {
+        for_each_bits!(@h 0 [0 1 2 3 4 5 6 7 8 9] [0 1 2 3 4 5 6 7 8 9]);
+        for_each_bits!(@t 1 0 [0 1] [0 1 2 3 4 5 6 7 8 9]);
+        for_each_bits!(@u 1 0 2 [0 1 2 3 4]);
+    };
+}
+
+for_each_bits!();
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::*;
+
+    #[test]
+    fn word_type_depends_on_size() {
+        let _: &[u32] = CxxBitset::<0>::new().as_words();
+        let _: &[u32] = CxxBitset::<32>::new().as_words();
+        let _: &[u64] = CxxBitset::<33>::new().as_words();
+        let _: &[u64] = CxxBitset::<1024>::new().as_words();
+
+        assert_eq!(size_of::<CxxBitset<0>>(), 4);
+        assert_eq!(size_of::<CxxBitset<1>>(), 4);
+        assert_eq!(size_of::<CxxBitset<32>>(), 4);
+        assert_eq!(size_of::<CxxBitset<33>>(), 8);
+        assert_eq!(size_of::<CxxBitset<64>>(), 8);
+        assert_eq!(size_of::<CxxBitset<65>>(), 16);
+        assert_eq!(size_of::<CxxBitset<1024>>(), 128);
+    }
+
+    #[test]
+    fn counts_bits() {
+        let mut bits = CxxBitset::<100>::new();
+        assert!(bits.none());
+
+        for index in [0, 31, 32, 63, 64, 99] {
+            bits.set(index, true);
+        }
+
+        assert_eq!(bits.count_ones(), 6);
+        assert_eq!(bits.count_zeros(), 94);
+        assert_eq!(
+            bits.as_words(),
+            [1 << 63 | 1 << 32 | 1 << 31 | 1, 1 << 35 | 1]
+        );
+
+        bits.toggle(0);
+        assert_eq!(bits.get(0), Some(false));
+        assert_eq!(bits.get(100), None);
+        assert_eq!(bits.count_ones(), 5);
+    }
+
+    #[test]
+    fn shifts_across_words() {
+        let mut bits = CxxBitset::<100>::from_u64(1 << 63 | 1);
+
+        bits <<= 1;
+        assert_eq!(bits.as_words(), [2, 1]);
+
+        bits <<= 35;
+        assert_eq!(bits.as_words(), [1 << 36, 1 << 35]);
+        assert_eq!(bits.to_u64(), None);
+
+        bits >>= 36;
+        assert_eq!(bits.as_words(), [1 << 63 | 1, 0]);
+        assert_eq!(bits.to_u64(), Some(1 << 63 | 1));
+
+        assert!((bits << 100).none());
+        assert!((bits >> 64).none());
+    }
+
+    #[test]
+    fn unused_bits_stay_clear() {
+        let mut bits = CxxBitset::<40>::new();
+
+        bits.flip();
+        assert_eq!(bits.as_words(), [(1 << 40) - 1]);
+        assert!(bits.all());
+
+        bits <<= 1;
+        assert_eq!(bits.as_words(), [(1 << 40) - 2]);
+        assert_eq!(bits.count_ones(), 39);
+
+        let mut small = !CxxBitset::<5>::new();
+        assert_eq!(small.as_words(), [0b11111]);
+
+        small.reset();
+        small.set_all();
+        assert_eq!(small.as_words(), [0b11111]);
+        assert_eq!((small << 3).as_words(), [0b11000]);
+
+        assert_eq!((!CxxBitset::<0>::new()).as_words(), [0]);
+        assert_eq!((!CxxBitset::<64>::new()).as_words(), [u64::MAX]);
+    }
+
+    #[test]
+    fn formats_most_significant_first() {
+        let bits: CxxBitset<6> = [true, false, true].into_iter().collect();
+
+        assert_eq!(rust_alloc::format!("{bits}"), "000101");
+        assert_eq!(bits.to_u64(), Some(0b101));
+        assert_eq!(
+            bits & CxxBitset::from_u64(0b100),
+            CxxBitset::from_u64(0b100)
+        );
+        assert_eq!(
+            bits ^ CxxBitset::from_u64(0b110),
+            CxxBitset::from_u64(0b011)
+        );
+    }
+}
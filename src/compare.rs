@@ -0,0 +1,62 @@
+//! Comparators of ordered containers and adapters.
+
+use core::fmt;
+
+/// Trait for C++ comparators, such as the `Compare` parameter of `std::priority_queue`.
+///
+/// The comparator is stored in the container, so it must have the layout of
+/// the C++ comparator. Empty C++ classes have a size of 1, like [`Less`].
+pub trait Compare<T: ?Sized> {
+    /// Whether `a` is ordered before `b`.
+    fn less(&self, a: &T, b: &T) -> bool;
+}
+
+/// `std::less<>`.
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Less {
+    _empty: u8,
+}
+
+/// `std::greater<>`.
+#[repr(C)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Greater {
+    _empty: u8,
+}
+
+impl Less {
+    pub const fn new() -> Self {
+        Self { _empty: 0 }
+    }
+}
+
+impl Greater {
+    pub const fn new() -> Self {
+        Self { _empty: 0 }
+    }
+}
+
+impl<T: PartialOrd + ?Sized> Compare<T> for Less {
+    fn less(&self, a: &T, b: &T) -> bool {
+        a < b
+    }
+}
+
+impl<T: PartialOrd + ?Sized> Compare<T> for Greater {
+    fn less(&self, a: &T, b: &T) -> bool {
+        a > b
+    }
+}
+
+impl fmt::Debug for Less {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Less")
+    }
+}
+
+impl fmt::Debug for Greater {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Greater")
+    }
+}
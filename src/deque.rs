@@ -0,0 +1,1048 @@
+//! `std::deque`.
+//!
+//! The elements are stored in fixed-size blocks, which are referenced by a
+//! circular map of block pointers whose size is zero or a power of two.
+//! The block size depends only on the size of the element, like in MSVC:
+//! 16 elements of 1 byte, 8 of 2 bytes, 4 of 4 bytes, 2 of 8 bytes or 1 larger one.
+
+use core::{
+    alloc::{GlobalAlloc, Layout as AllocLayout},
+    ffi::c_void,
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem,
+    ops::{Index, IndexMut},
+    ptr,
+};
+
+use rust_alloc::alloc::handle_alloc_error;
+
+use crate::alloc::{CxxProxy, DefaultAlloc, WithCxxProxy, DEFAULT_ALLOC};
+
+/// `std::pmr::deque`.
+pub mod pmr {
+    use crate::pmr::PmrAlloc;
+
+    pub type CxxDeque<'a, T> = super::CxxDeque<T, PmrAlloc<'a>>;
+}
+
+pub type CxxDeque<T, A = DefaultAlloc> = CxxDequeLayout<T, A, Layout<A>>;
+
+/// `std::_Deque_val`, the value of a deque without its allocator.
+///
+/// Unlike the other containers, it derives from `_Container_base12` in every
+/// build mode, so it starts with a pointer to a [`ContainerProxy`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct RawDeque {
+    /// `_Myproxy`, allocated by every constructor and freed by the destructor.
+    pub proxy: *mut ContainerProxy,
+    /// Circular map of block pointers.
+    pub map: *mut *mut u8,
+    /// Number of block pointers in the map, zero or a power of two.
+    pub map_size: usize,
+    /// Offset of the first element from the start of the first block of the map.
+    pub off: usize,
+    /// Number of elements.
+    pub size: usize,
+}
+
+/// `std::_Container_proxy`, through which iterators reach their deque.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ContainerProxy {
+    /// `_Mycont`, the deque that owns the proxy.
+    pub cont: *const RawDeque,
+    /// `_Myfirstiter`, only used with iterator debugging.
+    pub first_iter: *mut c_void,
+}
+
+#[repr(C)]
+pub struct Layout<A: CxxProxy> {
+    alloc: A,
+    val: RawDeque,
+}
+
+/// `std::deque<T>`.
+///
+/// C++ iterators reach the deque through its heap allocated [`ContainerProxy`],
+/// which points back at the deque. Rust moves do not update it, so every method
+/// points the proxy at the current address of the deque. A deque that was moved
+/// has to be used by one of them, like [`len`](Self::len), before C++ sees it.
+#[repr(C)]
+pub struct CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    inner: L,
+    _marker: PhantomData<(T, A)>,
+}
+
+/// Iterator over the elements of a deque from the front to the back.
+pub struct Iter<'a, T> {
+    map: *const *mut T,
+    map_size: usize,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+/// Mutable iterator over the elements of a deque from the front to the back.
+pub struct IterMut<'a, T> {
+    map: *const *mut T,
+    map_size: usize,
+    front: usize,
+    back: usize,
+    _marker: PhantomData<&'a mut T>,
+}
+
+/// `_Minimum_map_size`.
+const MIN_MAP_SIZE: usize = 8;
+
+/// `_Block_size` of a deque of elements of `size` bytes.
+pub const fn block_size(size: usize) -> usize {
+    match size {
+        0..=1 => 16,
+        2 => 8,
+        3..=4 => 4,
+        5..=8 => 2,
+        _ => 1,
+    }
+}
+
+impl<A: CxxProxy> Layout<A> {
+    pub const fn new_in(alloc: A) -> Self {
+        Self {
+            alloc,
+            val: new_val(),
+        }
+    }
+}
+
+impl<T> CxxDeque<T, DefaultAlloc> {
+    /// Creates an empty deque, which allocates its proxy like in C++.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self::from_alloc(DEFAULT_ALLOC)
+    }
+}
+
+impl<T, A: CxxProxy> CxxDeque<T, A> {
+    /// Creates an empty deque, which allocates its proxy through `alloc` like in C++.
+    pub fn new_in(alloc: A) -> Self {
+        Self::from_alloc(alloc)
+    }
+
+    pub const fn allocator(&self) -> &A {
+        &self.inner.alloc
+    }
+}
+
+impl<T, A, L> CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    const BLOCK_SIZE: usize = block_size(mem::size_of::<T>());
+
+    pub fn len(&self) -> usize {
+        self.raw().size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let raw = self.raw();
+
+        if index < raw.size {
+            unsafe { Some(&*self.slot(raw.off + index)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let raw = self.raw();
+
+        if index < raw.size {
+            unsafe { Some(&mut *self.slot(raw.off + index)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len().wrapping_sub(1))
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        self.get_mut(self.len().wrapping_sub(1))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let raw = self.raw();
+
+        Iter {
+            map: raw.map.cast(),
+            map_size: raw.map_size,
+            front: raw.off,
+            back: raw.off + raw.size,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let raw = self.raw();
+
+        IterMut {
+            map: raw.map.cast(),
+            map_size: raw.map_size,
+            front: raw.off,
+            back: raw.off + raw.size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// `push_back`.
+    pub fn push_back(&mut self, value: T) {
+        let raw = self.raw();
+
+        if (raw.off + raw.size).is_multiple_of(Self::BLOCK_SIZE)
+            && raw.map_size <= (raw.size + Self::BLOCK_SIZE) / Self::BLOCK_SIZE
+        {
+            self.grow_map(1);
+        }
+
+        let raw = self.raw_mut();
+        raw.off &= (raw.map_size * Self::BLOCK_SIZE).wrapping_sub(1);
+        let new_off = raw.off + raw.size;
+
+        unsafe {
+            self.slot_or_alloc(new_off).write(value);
+        }
+
+        self.raw_mut().size += 1;
+    }
+
+    /// `push_front`.
+    pub fn push_front(&mut self, value: T) {
+        let raw = self.raw();
+
+        if raw.off.is_multiple_of(Self::BLOCK_SIZE)
+            && raw.map_size <= (raw.size + Self::BLOCK_SIZE) / Self::BLOCK_SIZE
+        {
+            self.grow_map(1);
+        }
+
+        let raw = self.raw_mut();
+        raw.off &= (raw.map_size * Self::BLOCK_SIZE).wrapping_sub(1);
+        let new_off = if raw.off != 0 {
+            raw.off
+        } else {
+            raw.map_size * Self::BLOCK_SIZE
+        } - 1;
+
+        unsafe {
+            self.slot_or_alloc(new_off).write(value);
+        }
+
+        let raw = self.raw_mut();
+        raw.off = new_off;
+        raw.size += 1;
+    }
+
+    /// `pop_back`, keeping the block of the element.
+    pub fn pop_back(&mut self) -> Option<T> {
+        let raw = self.raw();
+
+        if raw.size == 0 {
+            return None;
+        }
+
+        let value = unsafe { self.slot(raw.off + raw.size - 1).read() };
+
+        let raw = self.raw_mut();
+        raw.size -= 1;
+
+        if raw.size == 0 {
+            raw.off = 0;
+        }
+
+        Some(value)
+    }
+
+    /// `pop_front`, keeping the block of the element.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let raw = self.raw();
+
+        if raw.size == 0 {
+            return None;
+        }
+
+        let value = unsafe { self.slot(raw.off).read() };
+
+        let raw = self.raw_mut();
+        raw.size -= 1;
+
+        if raw.size == 0 {
+            raw.off = 0;
+        } else {
+            raw.off += 1;
+        }
+
+        Some(value)
+    }
+
+    /// Drops the elements and frees the blocks and the map, like `clear` does in MSVC.
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+
+        let raw = self.raw();
+        let (map, map_size) = (raw.map.cast::<*mut T>(), raw.map_size);
+
+        for block in (0..map_size).rev() {
+            let ptr = unsafe { map.add(block).read() };
+
+            if !ptr.is_null() {
+                self.dealloc_array(ptr, Self::BLOCK_SIZE);
+            }
+        }
+
+        if !map.is_null() {
+            self.dealloc_array(map, map_size);
+        }
+
+        let raw = self.raw_mut();
+        raw.map = ptr::null_mut();
+        raw.map_size = 0;
+    }
+
+    /// Returns a pointer to the element at offset `off` of the map.
+    ///
+    /// # Safety
+    ///
+    /// The block of `off` must be allocated.
+    unsafe fn slot(&self, off: usize) -> *mut T {
+        let raw = self.raw();
+        let block = (off / Self::BLOCK_SIZE) & (raw.map_size - 1);
+
+        unsafe {
+            raw.map
+                .cast::<*mut T>()
+                .add(block)
+                .read()
+                .add(off % Self::BLOCK_SIZE)
+        }
+    }
+
+    /// Returns a pointer to the element at offset `off` of the map,
+    /// allocating its block if there is none.
+    ///
+    /// # Safety
+    ///
+    /// The map must not be empty.
+    unsafe fn slot_or_alloc(&mut self, off: usize) -> *mut T {
+        let raw = self.raw();
+        let block = unsafe {
+            raw.map
+                .cast::<*mut T>()
+                .add((off / Self::BLOCK_SIZE) & (raw.map_size - 1))
+        };
+
+        unsafe {
+            if block.read().is_null() {
+                block.write(self.alloc_array(Self::BLOCK_SIZE));
+            }
+
+            block.read().add(off % Self::BLOCK_SIZE)
+        }
+    }
+
+    /// `_Growmap`, grows the map by at least `count` block pointers
+    /// while keeping the blocks at the same offsets.
+    fn grow_map(&mut self, count: usize) {
+        let raw = self.raw();
+        let (map, map_size) = (raw.map.cast::<*mut T>(), raw.map_size);
+        let max_size = isize::MAX as usize / mem::size_of::<T>().max(1);
+        let mut new_size = map_size.max(1);
+
+        while new_size - map_size < count || new_size < MIN_MAP_SIZE {
+            if max_size / Self::BLOCK_SIZE - new_size < new_size {
+                panic!("deque too long");
+            }
+
+            new_size *= 2;
+        }
+
+        let count = new_size - map_size;
+        let first = raw.off / Self::BLOCK_SIZE;
+        let new_map = self.alloc_array::<*mut T>(new_size);
+
+        // The blocks from the first one to the end of the old map stay at
+        // their index, and the ones before wrap around to follow them.
+        unsafe {
+            let mut dst = new_map.add(first);
+            ptr::copy_nonoverlapping(map.add(first), dst, map_size - first);
+            dst = dst.add(map_size - first);
+
+            if first <= count {
+                ptr::copy_nonoverlapping(map, dst, first);
+                dst = dst.add(first);
+                ptr::write_bytes(dst, 0, count - first);
+                ptr::write_bytes(new_map, 0, first);
+            } else {
+                ptr::copy_nonoverlapping(map, dst, count);
+                ptr::copy_nonoverlapping(map.add(count), new_map, first - count);
+                ptr::write_bytes(new_map.add(first - count), 0, count);
+            }
+        }
+
+        if !map.is_null() {
+            self.dealloc_array(map, map_size);
+        }
+
+        let raw = self.raw_mut();
+        raw.map = new_map.cast();
+        raw.map_size = new_size;
+    }
+
+    /// `_Alloc_proxy`, creates a deque with a new proxy.
+    fn from_alloc(alloc: A) -> Self {
+        let mut new = Self {
+            inner: L::new_in(alloc),
+            _marker: PhantomData,
+        };
+
+        let layout = AllocLayout::new::<ContainerProxy>();
+        let proxy = unsafe { new.inner.alloc_as_ref().proxy().alloc(layout) };
+
+        if proxy.is_null() {
+            handle_alloc_error(layout);
+        }
+
+        unsafe {
+            proxy.cast::<ContainerProxy>().write(ContainerProxy {
+                cont: ptr::null(),
+                first_iter: ptr::null_mut(),
+            });
+        }
+
+        new.raw_mut().proxy = proxy.cast();
+        new
+    }
+
+    /// `_Free_proxy`, frees the proxy of the deque.
+    fn free_proxy(&mut self) {
+        let proxy = mem::replace(&mut self.raw_mut().proxy, ptr::null_mut());
+
+        if !proxy.is_null() {
+            unsafe {
+                self.inner
+                    .alloc_as_ref()
+                    .proxy()
+                    .dealloc(proxy.cast(), AllocLayout::new::<ContainerProxy>());
+            }
+        }
+    }
+
+    /// The value of the deque, with the proxy pointed at it.
+    fn raw(&self) -> &RawDeque {
+        let raw = self.inner.value_as_ref();
+
+        if !raw.proxy.is_null() {
+            unsafe {
+                (*raw.proxy).cont = raw;
+            }
+        }
+
+        raw
+    }
+
+    /// The value of the deque, with the proxy pointed at it.
+    fn raw_mut(&mut self) -> &mut RawDeque {
+        let raw = self.inner.value_as_mut();
+
+        if !raw.proxy.is_null() {
+            unsafe {
+                (*raw.proxy).cont = raw;
+            }
+        }
+
+        raw
+    }
+
+    fn alloc_array<U>(&self, len: usize) -> *mut U {
+        const {
+            assert!(
+                mem::size_of::<T>() != 0,
+                "deque elements cannot be zero-sized"
+            )
+        };
+
+        let layout = AllocLayout::array::<U>(len).expect("capacity overflow");
+        let ptr = unsafe { self.inner.alloc_as_ref().proxy().alloc(layout) };
+
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+
+        ptr.cast()
+    }
+
+    fn dealloc_array<U>(&self, ptr: *mut U, len: usize) {
+        let layout = AllocLayout::array::<U>(len).expect("capacity overflow");
+
+        unsafe {
+            self.inner
+                .alloc_as_ref()
+                .proxy()
+                .dealloc(ptr.cast(), layout);
+        }
+    }
+}
+
+impl<T, A, L> Drop for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn drop(&mut self) {
+        self.clear();
+        self.free_proxy();
+    }
+}
+
+impl<T, A, L> fmt::Debug for CxxDequeLayout<T, A, L>
+where
+    T: fmt::Debug,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A, L> Default for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn default() -> Self {
+        Self::from_alloc(A::default())
+    }
+}
+
+impl<T, A, L> Clone for CxxDequeLayout<T, A, L>
+where
+    T: Clone,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn clone(&self) -> Self {
+        let mut clone = Self::from_alloc(self.inner.alloc_as_ref().clone());
+
+        clone.extend(self.iter().cloned());
+        clone
+    }
+}
+
+impl<T, A1, A2, L1, L2> PartialEq<CxxDequeLayout<T, A2, L2>> for CxxDequeLayout<T, A1, L1>
+where
+    T: PartialEq,
+    A1: CxxProxy,
+    A2: CxxProxy,
+    L1: WithCxxProxy<Alloc = A1, Value = RawDeque>,
+    L2: WithCxxProxy<Alloc = A2, Value = RawDeque>,
+{
+    fn eq(&self, other: &CxxDequeLayout<T, A2, L2>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T, A, L> Eq for CxxDequeLayout<T, A, L>
+where
+    T: Eq,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+}
+
+impl<T, A, L> Index<usize> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("deque index out of range")
+    }
+}
+
+impl<T, A, L> IndexMut<usize> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("deque index out of range")
+    }
+}
+
+impl<T, A, L> Extend<T> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push_back(value);
+        }
+    }
+}
+
+impl<T, A, L> FromIterator<T> for CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut deque = Self::default();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<'a, T, A, L> IntoIterator for &'a CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T, A, L> IntoIterator for &'a mut CxxDequeLayout<T, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawDeque>,
+{
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+/// Returns a pointer to the element at offset `off` of the map.
+///
+/// # Safety
+///
+/// The block of `off` must be allocated.
+unsafe fn map_slot<T>(map: *const *mut T, map_size: usize, off: usize) -> *mut T {
+    let block_size = block_size(mem::size_of::<T>());
+    let block = (off / block_size) & (map_size - 1);
+
+    unsafe { map.add(block).read().add(off % block_size) }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let value = unsafe { &*map_slot(self.map, self.map_size, self.front) };
+        self.front += 1;
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+
+        unsafe { Some(&*map_slot(self.map, self.map_size, self.back)) }
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            map: self.map,
+            map_size: self.map_size,
+            front: self.front,
+            back: self.back,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.front == self.back {
+            return None;
+        }
+
+        let value = unsafe { &mut *map_slot(self.map, self.map_size, self.front) };
+        self.front += 1;
+
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+
+        self.back -= 1;
+
+        unsafe { Some(&mut *map_slot(self.map, self.map_size, self.back)) }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+const fn new_val() -> RawDeque {
+    RawDeque {
+        proxy: ptr::null_mut(),
+        map: ptr::null_mut(),
+        map_size: 0,
+        off: 0,
+        size: 0,
+    }
+}
+
+impl<A: CxxProxy> WithCxxProxy for Layout<A> {
+    type Value = RawDeque;
+    type Alloc = A;
+
+    fn value_as_ref(&self) -> &Self::Value {
+        &self.val
+    }
+
+    fn value_as_mut(&mut self) -> &mut Self::Value {
+        &mut self.val
+    }
+
+    fn alloc_as_ref(&self) -> &Self::Alloc {
+        &self.alloc
+    }
+
+    fn new_in(alloc: Self::Alloc) -> Self {
+        Self {
+            alloc,
+            val: new_val(),
+        }
+    }
+}
+
+#[cfg(feature = "msvc2012")]
+pub mod msvc2012 {
+    use crate::alloc::{CxxProxy, WithCxxProxy};
+
+    use super::{new_val, CxxDequeLayout, DefaultAlloc, RawDeque, DEFAULT_ALLOC};
+
+    pub type CxxDeque<T, A = DefaultAlloc> = CxxDequeLayout<T, A, Layout<A>>;
+
+    /// `_Deque_alloc` of MSVC 2012, which also derives from a `_Deque_val` starting
+    /// with the proxy, followed by the allocator if it is not empty.
+    #[repr(C)]
+    pub struct Layout<A: CxxProxy> {
+        val: RawDeque,
+        alloc: A,
+    }
+
+    impl<A: CxxProxy> Layout<A> {
+        pub const fn new_in(alloc: A) -> Self {
+            Self {
+                alloc,
+                val: new_val(),
+            }
+        }
+    }
+
+    impl<T> CxxDeque<T, DefaultAlloc> {
+        /// Creates an empty deque, which allocates its proxy like in C++.
+        #[allow(clippy::new_without_default)]
+        pub fn new() -> Self {
+            Self::from_alloc(DEFAULT_ALLOC)
+        }
+    }
+
+    impl<T, A: CxxProxy> CxxDeque<T, A> {
+        /// Creates an empty deque, which allocates its proxy through `alloc` like in C++.
+        pub fn new_in(alloc: A) -> Self {
+            Self::from_alloc(alloc)
+        }
+
+        pub const fn allocator(&self) -> &A {
+            &self.inner.alloc
+        }
+    }
+
+    impl<A: CxxProxy> WithCxxProxy for Layout<A> {
+        type Value = RawDeque;
+        type Alloc = A;
+
+        fn value_as_ref(&self) -> &Self::Value {
+            &self.val
+        }
+
+        fn value_as_mut(&mut self) -> &mut Self::Value {
+            &mut self.val
+        }
+
+        fn alloc_as_ref(&self) -> &Self::Alloc {
+            &self.alloc
+        }
+
+        fn new_in(alloc: Self::Alloc) -> Self {
+            Self {
+                alloc,
+                val: new_val(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{offset_of, size_of};
+
+    use rust_alloc::{boxed::Box, vec::Vec};
+
+    use super::*;
+
+    fn raw<T, A: CxxProxy>(deque: &CxxDeque<T, A>) -> &RawDeque {
+        deque.inner.value_as_ref()
+    }
+
+    #[test]
+    fn block_size_follows_msvc() {
+        assert_eq!(CxxDeque::<u8>::BLOCK_SIZE, 16);
+        assert_eq!(CxxDeque::<u16>::BLOCK_SIZE, 8);
+        assert_eq!(CxxDeque::<[u8; 3]>::BLOCK_SIZE, 4);
+        assert_eq!(CxxDeque::<u32>::BLOCK_SIZE, 4);
+        assert_eq!(CxxDeque::<u64>::BLOCK_SIZE, 2);
+        assert_eq!(CxxDeque::<[u64; 2]>::BLOCK_SIZE, 1);
+    }
+
+    #[test]
+    fn push_back_grows_map() {
+        let mut deque = CxxDeque::<u64>::new();
+        assert!(raw(&deque).map.is_null());
+
+        deque.push_back(0);
+        assert_eq!(raw(&deque).map_size, MIN_MAP_SIZE);
+
+        // The map grows when a new block is needed and the map
+        // cannot hold one more block than the elements need.
+        deque.extend(1..14);
+        assert_eq!(raw(&deque).map_size, MIN_MAP_SIZE);
+
+        deque.push_back(14);
+        assert_eq!(raw(&deque).map_size, 2 * MIN_MAP_SIZE);
+
+        assert!(deque.iter().copied().eq(0..15));
+        assert_eq!(raw(&deque).off, 0);
+    }
+
+    #[test]
+    fn push_front_wraps_around_the_map() {
+        let mut deque = CxxDeque::<u32>::new();
+
+        deque.push_front(1);
+        assert_eq!(raw(&deque).off, MIN_MAP_SIZE * 4 - 1);
+
+        deque.push_back(2);
+        deque.push_front(0);
+        assert_eq!(raw(&deque).off, MIN_MAP_SIZE * 4 - 2);
+
+        assert_eq!(deque.len(), 3);
+        assert_eq!(deque[0], 0);
+        assert_eq!(deque[2], 2);
+        assert!(deque.iter().rev().copied().eq([2, 1, 0]));
+    }
+
+    #[test]
+    fn grow_map_keeps_wrapped_blocks() {
+        let mut deque = CxxDeque::<u64>::new();
+        let mut expected = Vec::new();
+
+        for i in 0..100 {
+            if i % 3 == 0 {
+                deque.push_front(i);
+                expected.insert(0, i);
+            } else {
+                deque.push_back(i);
+                expected.push(i);
+            }
+        }
+
+        assert!(raw(&deque).map_size.is_power_of_two());
+        assert!(deque.iter().eq(expected.iter()));
+        assert_eq!(deque.get(100), None);
+    }
+
+    #[test]
+    fn pop_from_both_ends() {
+        let mut deque: CxxDeque<u8> = (0..40).collect();
+
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(39));
+        assert_eq!(raw(&deque).off, 1);
+        assert_eq!(deque.front(), Some(&1));
+        assert_eq!(deque.back(), Some(&38));
+
+        while deque.pop_front().is_some() {}
+
+        assert!(deque.is_empty());
+        assert_eq!(raw(&deque).off, 0);
+        assert_eq!(deque.pop_back(), None);
+    }
+
+    #[test]
+    fn clear_frees_the_map() {
+        let mut deque: CxxDeque<u16> = (0..20).collect();
+        deque.clear();
+
+        assert!(deque.is_empty());
+        assert!(raw(&deque).map.is_null());
+        assert_eq!(raw(&deque).map_size, 0);
+
+        deque.push_back(1);
+        assert_eq!(deque.front(), Some(&1));
+    }
+
+    #[test]
+    fn clone_and_compare() {
+        let mut deque: CxxDeque<CxxDeque<i32>> = CxxDeque::new();
+
+        for i in 0..5 {
+            deque.push_front((0..i).collect());
+        }
+
+        deque.iter_mut().for_each(|inner| inner.push_back(-1));
+
+        let clone = deque.clone();
+        assert_eq!(clone, deque);
+        assert_eq!(clone[4].len(), 1);
+        assert!(clone[0].iter().copied().eq([0, 1, 2, 3, -1]));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn layout_matches_msvc_x64() {
+        assert_eq!(size_of::<CxxDeque<u8>>(), 40);
+        assert_eq!(offset_of!(RawDeque, proxy), 0);
+        assert_eq!(offset_of!(RawDeque, map), 8);
+        assert_eq!(offset_of!(RawDeque, map_size), 16);
+        assert_eq!(offset_of!(RawDeque, off), 24);
+        assert_eq!(offset_of!(RawDeque, size), 32);
+        assert_eq!(size_of::<ContainerProxy>(), 16);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn layout_matches_msvc_x86() {
+        assert_eq!(size_of::<CxxDeque<u8>>(), 20);
+        assert_eq!(offset_of!(RawDeque, proxy), 0);
+        assert_eq!(offset_of!(RawDeque, map), 4);
+        assert_eq!(offset_of!(RawDeque, map_size), 8);
+        assert_eq!(offset_of!(RawDeque, off), 12);
+        assert_eq!(offset_of!(RawDeque, size), 16);
+        assert_eq!(size_of::<ContainerProxy>(), 8);
+    }
+
+    #[test]
+    fn proxy_points_at_deque() {
+        let deque = Box::new(CxxDeque::<u32>::new());
+        let proxy = raw(&deque).proxy;
+
+        assert!(!proxy.is_null());
+        assert!(deque.is_empty());
+        assert!(ptr::eq(unsafe { (*proxy).cont }, raw(&deque)));
+        assert!(unsafe { (*proxy).first_iter }.is_null());
+
+        // Moving the deque leaves the proxy behind until the deque is used.
+        let moved = *deque;
+        assert_eq!(moved.len(), 0);
+        assert!(ptr::eq(unsafe { (*proxy).cont }, raw(&moved)));
+
+        let mut clone = moved.clone();
+        clone.push_back(1);
+        assert_ne!(raw(&clone).proxy, proxy);
+        assert!(ptr::eq(unsafe { (*raw(&clone).proxy).cont }, raw(&clone)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn proxy_is_allocated_and_freed() {
+        use crate::alloc::TrackingAlloc;
+
+        let alloc = TrackingAlloc::new(DEFAULT_ALLOC);
+        let start = alloc.checkpoint();
+
+        let mut deque = CxxDeque::<u64, _>::new_in(alloc.clone());
+        let proxy = raw(&deque).proxy;
+        let outstanding = alloc.outstanding(start);
+
+        assert_eq!(outstanding.len(), 1);
+        assert_eq!(outstanding[0].addr, proxy as usize);
+        assert_eq!(outstanding[0].layout, AllocLayout::new::<ContainerProxy>());
+
+        // `clear` frees the map and the blocks, but keeps the proxy.
+        deque.extend(0..10);
+        deque.clear();
+        assert_eq!(alloc.outstanding(start).len(), 1);
+
+        drop(deque);
+        assert!(alloc.check(start).is_ok());
+    }
+}
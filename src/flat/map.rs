@@ -0,0 +1,349 @@
+//! `std::flat_map` over two `std::vector`s.
+
+use core::{borrow::Borrow, fmt, iter::Zip, marker::PhantomData, mem, ops::RangeBounds, slice};
+
+use rust_alloc::vec::Vec;
+
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc, WithCxxProxy},
+    compare::{Compare, Less},
+    flat::{check_sorted, range_indices, search, sort_unique, FlatError},
+    vec::{CxxVec, CxxVecLayout, Layout, RawVec},
+};
+
+pub type CxxFlatMap<K, V, C = Less, A = DefaultAlloc> = CxxFlatMapLayout<K, V, C, A, Layout<A>>;
+
+/// Iterator over the entries of a [`CxxFlatMapLayout`] in key order.
+pub type Iter<'a, K, V> = Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>;
+
+/// Iterator over the entries of a [`CxxFlatMapLayout`] in key order, with mutable values.
+pub type IterMut<'a, K, V> = Zip<slice::Iter<'a, K>, slice::IterMut<'a, V>>;
+
+/// `std::flat_map<K, V, C>` with the default `std::vector` containers.
+///
+/// The keys are sorted and unique, and the value of the key at some index
+/// is at the same index of the value vector.
+///
+/// The comparator `C` is not stored; each method creates it with [`Default`].
+/// Comparators with state, like a `std::function` or a lambda with captures,
+/// are not supported, since their state could not be restored.
+#[repr(C)]
+pub struct CxxFlatMapLayout<K, V, C, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    keys: CxxVecLayout<K, A, L>,
+    values: CxxVecLayout<V, A, L>,
+    _compare: PhantomData<C>,
+}
+
+impl<K, V, C> CxxFlatMap<K, V, C, DefaultAlloc>
+where
+    C: Compare<K> + Default,
+{
+    pub const fn new() -> Self {
+        Self {
+            keys: CxxVec::new(),
+            values: CxxVec::new(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<K, V, C, A> CxxFlatMap<K, V, C, A>
+where
+    C: Compare<K> + Default,
+    A: CxxProxy + Clone,
+{
+    pub fn new_in(alloc: A) -> Self {
+        Self {
+            keys: CxxVec::new_in(alloc.clone()),
+            values: CxxVec::new_in(alloc),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<K, V, C, A, L> CxxFlatMapLayout<K, V, C, A, L>
+where
+    C: Compare<K> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Creates a map from sorted, unique keys and their values,
+    /// like the `sorted_unique_t` constructor.
+    pub fn from_sorted_unique(
+        keys: CxxVecLayout<K, A, L>,
+        values: CxxVecLayout<V, A, L>,
+    ) -> Result<Self, FlatError> {
+        let map = Self {
+            keys,
+            values,
+            _compare: PhantomData,
+        };
+
+        map.validate_sorted()?;
+        Ok(map)
+    }
+
+    /// Checks that the keys are sorted and unique, and that there is a value for each key.
+    ///
+    /// Maps shared with C++ may break these invariants, which leads to
+    /// wrong results of lookups, but not to undefined behavior.
+    pub fn validate_sorted(&self) -> Result<(), FlatError> {
+        if self.keys.len() != self.values.len() {
+            return Err(FlatError::LengthMismatch {
+                keys: self.keys.len(),
+                values: self.values.len(),
+            });
+        }
+
+        check_sorted(&self.keys, &C::default())
+    }
+
+    /// Returns the key and value vectors, `extract`.
+    pub fn into_vecs(self) -> (CxxVecLayout<K, A, L>, CxxVecLayout<V, A, L>) {
+        (self.keys, self.values)
+    }
+
+    pub fn keys(&self) -> &[K] {
+        self.keys.as_slice()
+    }
+
+    pub fn values(&self) -> &[V] {
+        self.values.as_slice()
+    }
+
+    pub fn values_mut(&mut self) -> &mut [V] {
+        self.values.as_mut_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.len() == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.keys.iter().zip(self.values.iter_mut())
+    }
+
+    /// Iterates over the entries with keys in `range`.
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        C: Compare<Q>,
+    {
+        let (start, end) = range_indices(&self.keys, &range, &C::default());
+        self.keys[start..end].iter().zip(&self.values[start..end])
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let index = search(&self.keys, key, &C::default()).ok()?;
+        self.values.get(index)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let index = search(&self.keys, key, &C::default()).ok()?;
+        self.values.get_mut(index)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        search(&self.keys, key, &C::default()).is_ok()
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next()
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.iter().next_back()
+    }
+
+    /// Inserts `value` at `key`, keeping the keys sorted,
+    /// and returns the old value, if any.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match search(&self.keys, &key, &C::default()) {
+            Ok(index) => Some(mem::replace(&mut self.values[index], value)),
+            Err(index) => {
+                self.keys.insert(index, key);
+                self.values.insert(index, value);
+                None
+            }
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let index = search(&self.keys, key, &C::default()).ok()?;
+        Some((self.keys.remove(index), self.values.remove(index)))
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.keys.reserve(additional);
+        self.values.reserve(additional);
+    }
+}
+
+impl<K, V, C, A, L> fmt::Debug for CxxFlatMapLayout<K, V, C, A, L>
+where
+    K: fmt::Debug,
+    V: fmt::Debug,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.keys.iter().zip(self.values.iter()))
+            .finish()
+    }
+}
+
+impl<K, V, C, A, L> Default for CxxFlatMapLayout<K, V, C, A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn default() -> Self {
+        Self {
+            keys: CxxVecLayout::default(),
+            values: CxxVecLayout::default(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<K, V, C, A, L> Clone for CxxFlatMapLayout<K, V, C, A, L>
+where
+    K: Clone,
+    V: Clone,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            values: self.values.clone(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<K, V, C, A1, A2, L1, L2> PartialEq<CxxFlatMapLayout<K, V, C, A2, L2>>
+    for CxxFlatMapLayout<K, V, C, A1, L1>
+where
+    K: PartialEq,
+    V: PartialEq,
+    A1: CxxProxy,
+    A2: CxxProxy,
+    L1: WithCxxProxy<Alloc = A1, Value = RawVec>,
+    L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
+{
+    fn eq(&self, other: &CxxFlatMapLayout<K, V, C, A2, L2>) -> bool {
+        self.keys.as_slice() == other.keys.as_slice()
+            && self.values.as_slice() == other.values.as_slice()
+    }
+}
+
+impl<K, V, C, A, L> Eq for CxxFlatMapLayout<K, V, C, A, L>
+where
+    K: Eq,
+    V: Eq,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+}
+
+/// Inserts the entries one by one, replacing the values of existing keys.
+impl<K, V, C, A, L> Extend<(K, V)> for CxxFlatMapLayout<K, V, C, A, L>
+where
+    C: Compare<K> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|(key, value)| {
+            self.insert(key, value);
+        });
+    }
+}
+
+/// Sorts the entries by key, keeping the first value of duplicate keys.
+impl<K, V, C, A, L> FromIterator<(K, V)> for CxxFlatMapLayout<K, V, C, A, L>
+where
+    C: Compare<K> + Default,
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut entries: Vec<_> = iter.into_iter().collect();
+        sort_unique(&mut entries, |(key, _)| key, &C::default());
+
+        let mut map = Self::default();
+        map.reserve(entries.len());
+
+        for (key, value) in entries {
+            map.keys.push(key);
+            map.values.push(value);
+        }
+
+        map
+    }
+}
+
+impl<'a, K, V, C, A, L> IntoIterator for &'a CxxFlatMapLayout<K, V, C, A, L>
+where
+    C: Compare<K> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    type Item = (&'a K, &'a V);
+
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,275 @@
+//! C++23 `std::flat_map` and `std::flat_set` with the MSVC layout.
+//!
+//! The keys are kept sorted and unique in a `std::vector`. MSVC declares the
+//! comparator `[[msvc::no_unique_address]]`, so the empty comparators supported
+//! here take no space and are created with [`Default`] when needed.
+
+use core::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+use rust_alloc::vec::Vec;
+
+pub use map::{CxxFlatMap, CxxFlatMapLayout};
+pub use set::{CxxFlatSet, CxxFlatSetLayout};
+
+use crate::compare::Compare;
+
+pub mod map;
+pub mod set;
+
+/// Error returned when a flat container breaks the container invariants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlatError {
+    /// The key and value vectors differ in length.
+    LengthMismatch { keys: usize, values: usize },
+    /// The key at `index` is not ordered after the previous one.
+    NotSorted { index: usize },
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch { keys, values } => {
+                write!(f, "{keys} keys do not match {values} values")
+            }
+            Self::NotSorted { index } => {
+                write!(f, "key at {index} is not ordered after the previous key")
+            }
+        }
+    }
+}
+
+impl core::error::Error for FlatError {}
+
+/// Checks that `keys` are sorted and unique.
+fn check_sorted<K, C: Compare<K>>(keys: &[K], comp: &C) -> Result<(), FlatError> {
+    match keys
+        .windows(2)
+        .position(|pair| !comp.less(&pair[0], &pair[1]))
+    {
+        Some(index) => Err(FlatError::NotSorted { index: index + 1 }),
+        None => Ok(()),
+    }
+}
+
+/// Index of the first key not ordered before `key`.
+fn lower_bound<K, Q, C>(keys: &[K], key: &Q, comp: &C) -> usize
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    C: Compare<Q>,
+{
+    keys.partition_point(|k| comp.less(k.borrow(), key))
+}
+
+/// Index of the first key ordered after `key`.
+fn upper_bound<K, Q, C>(keys: &[K], key: &Q, comp: &C) -> usize
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    C: Compare<Q>,
+{
+    keys.partition_point(|k| !comp.less(key, k.borrow()))
+}
+
+/// Index of `key`, or the index where it would be inserted.
+fn search<K, Q, C>(keys: &[K], key: &Q, comp: &C) -> Result<usize, usize>
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    C: Compare<Q>,
+{
+    let index = lower_bound(keys, key, comp);
+
+    match keys.get(index) {
+        Some(k) if !comp.less(key, k.borrow()) => Ok(index),
+        _ => Err(index),
+    }
+}
+
+/// Indices of the keys in `range`.
+fn range_indices<K, Q, R, C>(keys: &[K], range: &R, comp: &C) -> (usize, usize)
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+    C: Compare<Q>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(key) => lower_bound(keys, key, comp),
+        Bound::Excluded(key) => upper_bound(keys, key, comp),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(key) => upper_bound(keys, key, comp),
+        Bound::Excluded(key) => lower_bound(keys, key, comp),
+        Bound::Unbounded => keys.len(),
+    };
+
+    (start, end.max(start))
+}
+
+/// Sorts `items` by key and removes items with duplicate keys, keeping the first one,
+/// like the range constructors of `std::flat_map` and `std::flat_set`.
+fn sort_unique<T, K, C, F>(items: &mut Vec<T>, key: F, comp: &C)
+where
+    C: Compare<K>,
+    F: Fn(&T) -> &K,
+{
+    items.sort_by(|a, b| {
+        if comp.less(key(a), key(b)) {
+            Ordering::Less
+        } else if comp.less(key(b), key(a)) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    });
+
+    // The sort is stable, so the first of the duplicates is the earliest one.
+    items.dedup_by(|later, earlier| !comp.less(key(earlier), key(later)));
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::vec;
+
+    use super::*;
+    use crate::{
+        compare::{Greater, Less},
+        vec::CxxVec,
+    };
+
+    fn vec_of<T>(items: impl IntoIterator<Item = T>) -> CxxVec<T> {
+        let mut vec = CxxVec::new();
+        vec.extend(items);
+        vec
+    }
+
+    #[test]
+    fn sort_unique_keeps_first_duplicate() {
+        let mut items = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd'), (1, 'e'), (3, 'f')];
+        sort_unique(&mut items, |(key, _)| key, &Less::default());
+
+        assert_eq!(items, [(1, 'b'), (2, 'd'), (3, 'a')]);
+    }
+
+    #[test]
+    fn sort_unique_follows_comparator() {
+        let mut items = vec![(1, 'a'), (2, 'b'), (1, 'c')];
+        sort_unique(&mut items, |(key, _)| key, &Greater::default());
+
+        assert_eq!(items, [(2, 'b'), (1, 'a')]);
+    }
+
+    #[test]
+    fn map_from_iter_keeps_first_value() {
+        let map: CxxFlatMap<i32, char> = [(2, 'a'), (1, 'b'), (2, 'c')].into_iter().collect();
+
+        assert_eq!(map.keys(), [1, 2]);
+        assert_eq!(map.get(&2), Some(&'a'));
+    }
+
+    #[test]
+    fn set_insert_and_remove() {
+        let mut set = CxxFlatSet::<i32>::new();
+
+        assert!(set.insert(3));
+        assert!(set.insert(1));
+        assert!(set.insert(2));
+        assert!(!set.insert(3));
+        assert_eq!(set.as_slice(), [1, 2, 3]);
+
+        assert!(set.remove(&2));
+        assert!(!set.remove(&2));
+        assert_eq!(set.take(&1), Some(1));
+        assert_eq!(set.as_slice(), [3]);
+        assert!(!set.contains(&1));
+    }
+
+    #[test]
+    fn set_follows_comparator() {
+        let set: CxxFlatSet<i32, Greater> = [1, 3, 2, 3].into_iter().collect();
+
+        assert_eq!(set.as_slice(), [3, 2, 1]);
+        assert_eq!(set.first(), Some(&3));
+        assert_eq!(set.range(2..).copied().collect::<Vec<_>>(), [2, 1]);
+    }
+
+    #[test]
+    fn set_range() {
+        let set: CxxFlatSet<i32> = [1, 2, 4, 5, 7].into_iter().collect();
+        let range = |r: (Bound<i32>, Bound<i32>)| set.range(r).copied().collect::<Vec<_>>();
+
+        assert_eq!(set.range(2..5).copied().collect::<Vec<_>>(), [2, 4]);
+        assert_eq!(set.range(3..=5).copied().collect::<Vec<_>>(), [4, 5]);
+        assert_eq!(set.range(..).count(), 5);
+        assert_eq!(range((Bound::Excluded(2), Bound::Unbounded)), [4, 5, 7]);
+        assert_eq!(range((Bound::Included(5), Bound::Excluded(2))), []);
+        assert_eq!(set.range(8..).count(), 0);
+    }
+
+    #[test]
+    fn set_check_sorted_errors() {
+        assert!(CxxFlatSet::<i32>::from_sorted_unique(vec_of([1, 2, 3])).is_ok());
+
+        let unsorted = CxxFlatSet::<i32>::from_sorted_unique(vec_of([1, 3, 2]));
+        assert_eq!(unsorted.err(), Some(FlatError::NotSorted { index: 2 }));
+
+        let duplicate = CxxFlatSet::<i32>::from_sorted_unique(vec_of([1, 1]));
+        assert_eq!(duplicate.err(), Some(FlatError::NotSorted { index: 1 }));
+
+        let reversed = CxxFlatSet::<i32, Greater>::from_sorted_unique(vec_of([1, 2]));
+        assert_eq!(reversed.err(), Some(FlatError::NotSorted { index: 1 }));
+    }
+
+    #[test]
+    fn map_insert_and_remove() {
+        let mut map = CxxFlatMap::<i32, char>::new();
+
+        assert_eq!(map.insert(2, 'a'), None);
+        assert_eq!(map.insert(1, 'b'), None);
+        assert_eq!(map.insert(2, 'c'), Some('a'));
+        assert_eq!(map.keys(), [1, 2]);
+        assert_eq!(map.values(), ['b', 'c']);
+
+        *map.get_mut(&1).unwrap() = 'd';
+        assert_eq!(map.remove_entry(&1), Some((1, 'd')));
+        assert_eq!(map.remove(&1), None);
+        assert_eq!(map.remove(&2), Some('c'));
+        assert!(map.is_empty());
+        assert_eq!(map.validate_sorted(), Ok(()));
+    }
+
+    #[test]
+    fn map_range() {
+        let map: CxxFlatMap<i32, char> = [(1, 'a'), (3, 'b'), (5, 'c')].into_iter().collect();
+        let entries =
+            |iter: map::Iter<'_, i32, char>| iter.map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+
+        assert_eq!(entries(map.range(2..=5)), [(3, 'b'), (5, 'c')]);
+        assert_eq!(entries(map.range(..3)), [(1, 'a')]);
+        assert_eq!(entries(map.range(4..4)), []);
+    }
+
+    #[test]
+    fn map_check_sorted_errors() {
+        let map = CxxFlatMap::<i32, char>::from_sorted_unique(vec_of([1, 2]), vec_of(['a', 'b']));
+        assert!(map.is_ok());
+
+        let missing = CxxFlatMap::<i32, char>::from_sorted_unique(vec_of([1, 2]), vec_of(['a']));
+        assert_eq!(
+            missing.err(),
+            Some(FlatError::LengthMismatch { keys: 2, values: 1 })
+        );
+
+        let unsorted =
+            CxxFlatMap::<i32, char>::from_sorted_unique(vec_of([2, 2]), vec_of(['a', 'b']));
+        assert_eq!(unsorted.err(), Some(FlatError::NotSorted { index: 1 }));
+    }
+}
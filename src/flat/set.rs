@@ -0,0 +1,285 @@
+//! `std::flat_set` over a `std::vector`.
+
+use core::{borrow::Borrow, fmt, marker::PhantomData, ops::RangeBounds, slice};
+
+use rust_alloc::vec::Vec;
+
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc, WithCxxProxy},
+    compare::{Compare, Less},
+    flat::{check_sorted, range_indices, search, sort_unique, FlatError},
+    vec::{CxxVec, CxxVecLayout, Layout, RawVec},
+};
+
+pub type CxxFlatSet<T, C = Less, A = DefaultAlloc> = CxxFlatSetLayout<T, C, A, Layout<A>>;
+
+/// `std::flat_set<T, C>` with the default `std::vector` container.
+///
+/// The comparator `C` is not stored; each method creates it with [`Default`],
+/// so comparators with state are not supported.
+#[repr(C)]
+pub struct CxxFlatSetLayout<T, C, A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    keys: CxxVecLayout<T, A, L>,
+    _compare: PhantomData<C>,
+}
+
+impl<T, C> CxxFlatSet<T, C, DefaultAlloc>
+where
+    C: Compare<T> + Default,
+{
+    pub const fn new() -> Self {
+        Self {
+            keys: CxxVec::new(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<T, C, A> CxxFlatSet<T, C, A>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy,
+{
+    pub const fn new_in(alloc: A) -> Self {
+        Self {
+            keys: CxxVec::new_in(alloc),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<T, C, A, L> CxxFlatSetLayout<T, C, A, L>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Creates a set from sorted, unique keys, like the `sorted_unique_t` constructor.
+    pub fn from_sorted_unique(keys: CxxVecLayout<T, A, L>) -> Result<Self, FlatError> {
+        let set = Self {
+            keys,
+            _compare: PhantomData,
+        };
+
+        set.validate_sorted()?;
+        Ok(set)
+    }
+
+    /// Checks that the keys are sorted and unique.
+    ///
+    /// Sets shared with C++ may break this invariant, which leads to
+    /// wrong results of lookups, but not to undefined behavior.
+    pub fn validate_sorted(&self) -> Result<(), FlatError> {
+        check_sorted(&self.keys, &C::default())
+    }
+
+    /// Returns the key vector, `extract`.
+    pub fn into_vec(self) -> CxxVecLayout<T, A, L> {
+        self.keys
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        self.keys.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.len() == 0
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.keys.iter()
+    }
+
+    /// Iterates over the keys in `range`.
+    pub fn range<Q, R>(&self, range: R) -> slice::Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+        C: Compare<Q>,
+    {
+        let (start, end) = range_indices(&self.keys, &range, &C::default());
+        self.keys[start..end].iter()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let index = search(&self.keys, key, &C::default()).ok()?;
+        self.keys.get(index)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        search(&self.keys, key, &C::default()).is_ok()
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.keys.first()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.keys.last()
+    }
+
+    /// Inserts `key`, keeping the keys sorted,
+    /// and returns whether it was not present yet.
+    pub fn insert(&mut self, key: T) -> bool {
+        match search(&self.keys, &key, &C::default()) {
+            Ok(_) => false,
+            Err(index) => {
+                self.keys.insert(index, key);
+                true
+            }
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        self.take(key).is_some()
+    }
+
+    pub fn take<Q>(&mut self, key: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: ?Sized,
+        C: Compare<Q>,
+    {
+        let index = search(&self.keys, key, &C::default()).ok()?;
+        Some(self.keys.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.keys.reserve(additional);
+    }
+}
+
+impl<T, C, A, L> fmt::Debug for CxxFlatSetLayout<T, C, A, L>
+where
+    T: fmt::Debug,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.keys.iter()).finish()
+    }
+}
+
+impl<T, C, A, L> Default for CxxFlatSetLayout<T, C, A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn default() -> Self {
+        Self {
+            keys: CxxVecLayout::default(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<T, C, A, L> Clone for CxxFlatSetLayout<T, C, A, L>
+where
+    T: Clone,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            _compare: PhantomData,
+        }
+    }
+}
+
+impl<T, C, A1, A2, L1, L2> PartialEq<CxxFlatSetLayout<T, C, A2, L2>>
+    for CxxFlatSetLayout<T, C, A1, L1>
+where
+    T: PartialEq,
+    A1: CxxProxy,
+    A2: CxxProxy,
+    L1: WithCxxProxy<Alloc = A1, Value = RawVec>,
+    L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
+{
+    fn eq(&self, other: &CxxFlatSetLayout<T, C, A2, L2>) -> bool {
+        self.keys.as_slice() == other.keys.as_slice()
+    }
+}
+
+impl<T, C, A, L> Eq for CxxFlatSetLayout<T, C, A, L>
+where
+    T: Eq,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+}
+
+/// Inserts the keys one by one.
+impl<T, C, A, L> Extend<T> for CxxFlatSetLayout<T, C, A, L>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|key| {
+            self.insert(key);
+        });
+    }
+}
+
+/// Sorts the keys and removes duplicates.
+impl<T, C, A, L> FromIterator<T> for CxxFlatSetLayout<T, C, A, L>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut keys: Vec<_> = iter.into_iter().collect();
+        sort_unique(&mut keys, |key| key, &C::default());
+
+        let mut set = Self::default();
+        set.reserve(keys.len());
+        set.keys.extend(keys);
+        set
+    }
+}
+
+impl<'a, T, C, A, L> IntoIterator for &'a CxxFlatSetLayout<T, C, A, L>
+where
+    C: Compare<T> + Default,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    type Item = &'a T;
+
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
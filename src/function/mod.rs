@@ -0,0 +1,463 @@
+//! `std::function` with the MSVC layout.
+//!
+//! A `std::function` stores its target inline when it fits, in which case it
+//! points to itself. Such a function must stay in place, which is why
+//! [`CxxFunction`] is [`!Unpin`](Unpin) and functions with targets created
+//! by Rust are only handed out pinned.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
+    fmt,
+    marker::{PhantomData, PhantomPinned},
+    mem::{self, MaybeUninit},
+    pin::Pin,
+    ptr,
+};
+
+use rust_alloc::{alloc::handle_alloc_error, boxed::Box};
+use signature::Head;
+pub use signature::{Callable, FunctionReturn, Signature};
+
+use crate::{
+    alloc::{CxxProxy, DEFAULT_ALLOC},
+    rtti,
+};
+
+pub mod signature;
+
+/// Number of pointers in the storage of `std::function`.
+const SMALL_PTRS: usize = 6 + 16 / mem::size_of::<*const c_void>();
+
+/// Size of the inline buffer of `std::function`.
+const SPACE: usize = (SMALL_PTRS - 1) * mem::size_of::<*const c_void>();
+
+/// Storage of `std::function`, aligned like `max_align_t`.
+///
+/// The last pointer points to the target, which is either stored in `space`
+/// or allocated separately.
+#[repr(C, align(8))]
+struct Storage {
+    space: MaybeUninit<[u8; SPACE]>,
+    target: *mut c_void,
+}
+
+/// Vtable of a `std::function` target, `_Func_base`.
+///
+/// `_Do_call` depends on the signature and is cast to the right type by
+/// [`Signature::call_raw`].
+#[repr(C)]
+struct VTable {
+    copy: member_fn!(fn(*const c_void, *mut c_void) -> *mut c_void),
+    move_: member_fn!(fn(*mut c_void, *mut c_void) -> *mut c_void),
+    do_call: *const (),
+    target_type: member_fn!(fn(*const c_void) -> *const c_void),
+    delete_this: member_fn!(fn(*mut c_void, bool)),
+    get: member_fn!(fn(*const c_void) -> *const c_void),
+}
+
+/// `std::function<Sig>`.
+///
+/// See [`Signature`] for how `Sig` relates to the C++ signature.
+#[repr(C)]
+pub struct CxxFunction<Sig: Signature> {
+    storage: Storage,
+    _marker: PhantomData<Sig>,
+    _pin: PhantomPinned,
+}
+
+/// Target of a Rust closure `F`, allocated through `A` if it does not fit inline.
+#[repr(C)]
+struct ClosureImpl<F, A, Sig> {
+    head: Head<F>,
+    alloc: A,
+    _marker: PhantomData<Sig>,
+}
+
+impl<Sig: Signature> CxxFunction<Sig> {
+    /// Creates an empty function.
+    ///
+    /// Empty functions can be moved freely.
+    // Not `Default`, which would make it a `MoveType` that containers move bitwise.
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            storage: Storage {
+                space: MaybeUninit::uninit(),
+                target: ptr::null_mut(),
+            },
+            _marker: PhantomData,
+            _pin: PhantomPinned,
+        }
+    }
+
+    /// Creates a function with the closure `f` as its target.
+    pub fn from_closure<F: Callable<Sig>>(f: F) -> Pin<Box<Self>> {
+        Self::from_closure_in(f, DEFAULT_ALLOC)
+    }
+
+    /// Creates a function with the closure `f` as its target,
+    /// allocated through `alloc` if it does not fit inline.
+    pub fn from_closure_in<F, A>(f: F, alloc: A) -> Pin<Box<Self>>
+    where
+        F: Callable<Sig>,
+        A: CxxProxy + Clone + 'static,
+    {
+        let mut func = Box::pin(Self::new());
+        func.as_mut().set_in(f, alloc);
+        func
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.target.is_null()
+    }
+
+    /// Calls the target with `args`.
+    ///
+    /// # Panics
+    ///
+    /// If the function is empty, where C++ would throw `std::bad_function_call`.
+    pub fn call(&self, args: Sig::Args) -> Sig::Output {
+        assert!(!self.is_empty(), "bad function call");
+
+        unsafe {
+            let target = self.storage.target;
+            Sig::call_raw(Self::vtable(target).do_call, target, args)
+        }
+    }
+
+    /// Replaces the target with the closure `f`.
+    pub fn set<F: Callable<Sig>>(self: Pin<&mut Self>, f: F) {
+        self.set_in(f, DEFAULT_ALLOC)
+    }
+
+    /// Replaces the target with the closure `f`,
+    /// allocated through `alloc` if it does not fit inline.
+    pub fn set_in<F, A>(self: Pin<&mut Self>, f: F, alloc: A)
+    where
+        F: Callable<Sig>,
+        A: CxxProxy + Clone + 'static,
+    {
+        unsafe {
+            let this = self.get_unchecked_mut();
+            this.tidy();
+
+            let target = ClosureImpl::<F, A, Sig> {
+                head: Head {
+                    vtable: ClosureImpl::<F, A, Sig>::VTABLE as *const _ as *const c_void,
+                    f,
+                },
+                alloc,
+                _marker: PhantomData,
+            };
+
+            this.storage.target = target.place(this.space_ptr());
+        }
+    }
+
+    /// Drops the target, leaving the function empty.
+    pub fn reset(self: Pin<&mut Self>) {
+        unsafe {
+            self.get_unchecked_mut().tidy();
+        }
+    }
+
+    /// Replaces the target with a copy of the target of `source`.
+    pub fn assign(self: Pin<&mut Self>, source: &Self) {
+        unsafe {
+            let this = self.get_unchecked_mut();
+
+            if ptr::eq(this, source) {
+                return;
+            }
+
+            this.tidy();
+
+            if !source.is_empty() {
+                let target = source.storage.target;
+                this.storage.target = (Self::vtable(target).copy)(target, this.space_ptr());
+            }
+        }
+    }
+
+    /// Replaces the target with the target of `source`, leaving it empty.
+    pub fn assign_move(self: Pin<&mut Self>, source: Pin<&mut Self>) {
+        unsafe {
+            let this = self.get_unchecked_mut();
+            let source = source.get_unchecked_mut();
+
+            this.tidy();
+
+            if source.is_empty() {
+                return;
+            }
+
+            if source.is_local() {
+                let target = source.storage.target;
+                this.storage.target = (Self::vtable(target).move_)(target, this.space_ptr());
+                source.tidy();
+            } else {
+                this.storage.target = mem::replace(&mut source.storage.target, ptr::null_mut());
+            }
+        }
+    }
+
+    /// Creates a copy of the function.
+    pub fn clone_boxed(&self) -> Pin<Box<Self>> {
+        let mut func = Box::pin(Self::new());
+        func.as_mut().assign(self);
+        func
+    }
+
+    fn space_ptr(&mut self) -> *mut c_void {
+        self.storage.space.as_mut_ptr().cast()
+    }
+
+    fn is_local(&self) -> bool {
+        ptr::eq(self.storage.target, self.storage.space.as_ptr().cast())
+    }
+
+    unsafe fn vtable<'a>(target: *const c_void) -> &'a VTable {
+        unsafe { &**target.cast::<*const VTable>() }
+    }
+
+    /// Destroys the target, if any.
+    unsafe fn tidy(&mut self) {
+        if !self.is_empty() {
+            let dealloc = !self.is_local();
+            let target = mem::replace(&mut self.storage.target, ptr::null_mut());
+
+            unsafe {
+                (Self::vtable(target).delete_this)(target, dealloc);
+            }
+        }
+    }
+}
+
+impl<F, A, Sig> ClosureImpl<F, A, Sig>
+where
+    F: Callable<Sig>,
+    A: CxxProxy + Clone + 'static,
+    Sig: Signature,
+{
+    const INLINE: bool = mem::size_of::<Self>() <= SPACE && mem::align_of::<Self>() <= 8;
+
+    const VTABLE: &'static VTable = &VTable {
+        copy: Self::copy,
+        move_: Self::move_,
+        do_call: F::DO_CALL,
+        target_type: Self::target_type,
+        delete_this: Self::delete_this,
+        get: Self::get,
+    };
+
+    /// Moves the target to `space` if it fits inline, or to a new allocation otherwise.
+    fn place(self, space: *mut c_void) -> *mut c_void {
+        let target = if Self::INLINE {
+            space.cast::<Self>()
+        } else {
+            let layout = Layout::new::<Self>();
+            let target = unsafe { self.alloc.proxy().alloc(layout).cast::<Self>() };
+
+            if target.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            target
+        };
+
+        unsafe {
+            target.write(self);
+        }
+
+        target.cast()
+    }
+
+    member_fn! {
+        fn copy(this: *const c_void, space: *mut c_void) -> *mut c_void {
+            unsafe {
+                let this = &*this.cast::<Self>();
+
+                let target = Self {
+                    head: Head {
+                        vtable: this.head.vtable,
+                        f: this.head.f.clone(),
+                    },
+                    alloc: this.alloc.clone(),
+                    _marker: PhantomData,
+                };
+
+                target.place(space)
+            }
+        }
+    }
+
+    member_fn! {
+        fn move_(this: *mut c_void, space: *mut c_void) -> *mut c_void {
+            // Only inline targets are moved, and a C++ move has to leave
+            // the source initialized, so the closure is cloned.
+            unsafe { Self::copy(this, space) }
+        }
+    }
+
+    member_fn! {
+        fn target_type(_this: *const c_void) -> *const c_void {
+            rtti::RUST_CLOSURE.as_ptr()
+        }
+    }
+
+    member_fn! {
+        fn delete_this(this: *mut c_void, dealloc: bool) {
+            unsafe {
+                let this = this.cast::<Self>();
+                let alloc = ptr::addr_of!((*this).alloc).read();
+
+                ptr::addr_of_mut!((*this).head.f).drop_in_place();
+
+                if dealloc {
+                    alloc.proxy().dealloc(this.cast(), Layout::new::<Self>());
+                }
+            }
+        }
+    }
+
+    member_fn! {
+        fn get(this: *const c_void) -> *const c_void {
+            unsafe { ptr::addr_of!((*this.cast::<Self>()).head.f).cast() }
+        }
+    }
+}
+
+impl<Sig: Signature> fmt::Debug for CxxFunction<Sig> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CxxFunction")
+            .field("empty", &self.is_empty())
+            .field("local", &self.is_local())
+            .finish()
+    }
+}
+
+impl<Sig: Signature> Drop for CxxFunction<Sig> {
+    fn drop(&mut self) {
+        unsafe {
+            self.tidy();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_alloc::rc::Rc;
+
+    use super::*;
+
+    type Add = CxxFunction<fn(i32, i32) -> i32>;
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn size_matches_msvc() {
+        assert_eq!(mem::size_of::<Add>(), 64);
+        assert_eq!(mem::align_of::<Add>(), 8);
+    }
+
+    #[test]
+    fn calls_closure() {
+        let offset = 10;
+        let add = Add::from_closure(move |a, b| a + b + offset);
+
+        assert!(!add.is_empty());
+        assert_eq!(add.call((1, 2)), 13);
+        assert_eq!(CxxFunction::<fn() -> u64>::from_closure(|| 7).call(()), 7);
+    }
+
+    #[test]
+    #[should_panic = "bad function call"]
+    fn empty_call_panics() {
+        Add::new().call((1, 2));
+    }
+
+    #[test]
+    fn small_targets_are_inline() {
+        let small = [1u64; 2];
+        let add = Add::from_closure(move |a, b| a + b + small[1] as i32);
+
+        assert!(add.is_local());
+        assert_eq!(add.call((1, 2)), 4);
+    }
+
+    #[test]
+    fn large_targets_are_allocated() {
+        let large = [1u64; 16];
+        let add = Add::from_closure(move |a, b| a + b + large[15] as i32);
+
+        assert!(!add.is_local());
+        assert_eq!(add.call((1, 2)), 4);
+    }
+
+    #[test]
+    fn clone_and_assign_copy_target() {
+        for extra in [[2u64; 1].as_slice(), &[2; 16]] {
+            let extra: Rc<[u64]> = extra.into();
+            let add = Add::from_closure(move |a, b| a + b + extra[0] as i32);
+
+            let copy = add.clone_boxed();
+            assert_eq!(copy.is_local(), add.is_local());
+            assert_eq!(copy.call((1, 2)), 5);
+
+            let mut assigned = Add::from_closure(|a, b| a * b);
+            assigned.as_mut().assign(&add);
+            assert_eq!(assigned.call((1, 2)), 5);
+            assert_eq!(add.call((1, 2)), 5);
+
+            let mut empty = Box::pin(Add::new());
+            assigned.as_mut().assign(&empty);
+            assert!(assigned.is_empty());
+
+            empty.as_mut().assign(&add);
+            assert_eq!(empty.call((3, 4)), 9);
+        }
+    }
+
+    #[test]
+    fn assign_move_empties_source() {
+        for large in [false, true] {
+            let mut source = if large {
+                let extra = [3u64; 16];
+                Add::from_closure(move |a, b| a + b + extra[0] as i32)
+            } else {
+                Add::from_closure(|a, b| a + b + 3)
+            };
+            let mut target = Box::pin(Add::new());
+
+            target.as_mut().assign_move(source.as_mut());
+
+            assert!(source.is_empty());
+            assert_eq!(target.is_local(), !large);
+            assert_eq!(target.call((1, 2)), 6);
+        }
+    }
+
+    fn check_drops<const N: usize>(local: bool) {
+        let rc = Rc::new(());
+        let captured = (rc.clone(), [0u64; N]);
+
+        let mut func = CxxFunction::<fn() -> usize>::from_closure(move || {
+            Rc::strong_count(&captured.0) + captured.1.len()
+        });
+        let copy = func.clone_boxed();
+        assert_eq!(func.is_local(), local);
+        assert_eq!(Rc::strong_count(&rc), 3);
+
+        func.as_mut().reset();
+        assert_eq!(Rc::strong_count(&rc), 2);
+
+        drop(copy);
+        assert_eq!(Rc::strong_count(&rc), 1);
+    }
+
+    #[test]
+    fn drops_target() {
+        check_drops::<1>(true);
+        check_drops::<16>(false);
+    }
+}
@@ -0,0 +1,147 @@
+//! Function signatures of [`CxxFunction`](super::CxxFunction).
+
+use core::{ffi::c_void, marker::PhantomData, mem, ptr::NonNull};
+
+/// Trait for function pointer types describing the signature of a `std::function`.
+///
+/// `std::function<R(A, B)>` is written as `CxxFunction<fn(A, B) -> R>`.
+///
+/// The arguments are passed to the target by pointer, as with `A&&` in C++,
+/// so a C++ reference parameter `const A&` is declared as a value parameter `A`.
+/// Implemented for signatures of up to 6 arguments.
+///
+/// # Safety
+///
+/// [`Signature::call_raw`] must call `do_call` as the `_Do_call` member function
+/// of `std::function` with this signature.
+pub unsafe trait Signature {
+    /// Arguments as a tuple.
+    type Args;
+
+    type Output;
+
+    /// Calls `do_call` on the target `this`.
+    ///
+    /// # Safety
+    ///
+    /// `do_call` must be the `_Do_call` vtable entry of the target `this`.
+    unsafe fn call_raw(do_call: *const (), this: *mut c_void, args: Self::Args) -> Self::Output;
+}
+
+/// Trait for Rust closures that can be the target of a `std::function` with signature `Sig`.
+///
+/// Targets are cloned when a `std::function` is copied or moved,
+/// and C++ may call them through a shared reference.
+///
+/// # Safety
+///
+/// [`Callable::DO_CALL`] must point to a `_Do_call` member function with the
+/// signature `Sig`, which takes a target starting with a vtable pointer followed by `Self`.
+pub unsafe trait Callable<Sig: Signature>: Clone + 'static {
+    /// `_Do_call` implementation for a target which starts with a vtable pointer
+    /// followed by the closure.
+    const DO_CALL: *const ();
+}
+
+/// Trait for return types of a `std::function` that are returned in registers.
+///
+/// # Safety
+///
+/// MSVC has to return the C++ equivalent of the type the same way Rust returns
+/// it from an `extern "C"` function, which excludes classes.
+pub unsafe trait FunctionReturn {}
+
+macro_rules! impl_function_return {
+    ($($t:ty),*) => {$(
+        unsafe impl FunctionReturn for $t {}
+    )*};
+}
+
+impl_function_return!(
+    (),
+    bool,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    isize,
+    f32,
+    f64
+);
+
+unsafe impl<T> FunctionReturn for *const T {}
+
+unsafe impl<T> FunctionReturn for *mut T {}
+
+unsafe impl<T> FunctionReturn for Option<NonNull<T>> {}
+
+/// Start of the target of a Rust closure, see [`Callable::DO_CALL`].
+#[repr(C)]
+pub(super) struct Head<F> {
+    pub vtable: *const c_void,
+    pub f: F,
+}
+
+struct Shim<F, Sig>(PhantomData<(F, Sig)>);
+
+macro_rules! impl_signature {
+    ($($arg:ident $val:ident),*) => {
+        unsafe impl<R, $($arg),*> Signature for fn($($arg),*) -> R
+        where
+            R: FunctionReturn,
+        {
+            type Args = ($($arg,)*);
+
+            type Output = R;
+
+            #[allow(unused_mut)]
+            unsafe fn call_raw(do_call: *const (), this: *mut c_void, args: Self::Args) -> R {
+                type DoCall<R, $($arg),*> = member_fn!(fn(*mut c_void $(, *mut $arg)*) -> R);
+
+                let ($(mut $val,)*) = args;
+
+                unsafe {
+                    let do_call = mem::transmute::<*const (), DoCall<R, $($arg),*>>(do_call);
+                    do_call(this $(, &mut $val)*)
+                }
+            }
+        }
+
+        impl<F, R, $($arg),*> Shim<F, fn($($arg),*) -> R>
+        where
+            F: Fn($($arg),*) -> R,
+            $($arg: Clone,)*
+        {
+            member_fn! {
+                fn do_call(this: *mut c_void $(, $val: *mut $arg)*) -> R {
+                    unsafe {
+                        let head = &*this.cast::<Head<F>>();
+                        (head.f)($((*$val).clone()),*)
+                    }
+                }
+            }
+        }
+
+        unsafe impl<F, R, $($arg),*> Callable<fn($($arg),*) -> R> for F
+        where
+            F: Fn($($arg),*) -> R + Clone + 'static,
+            R: FunctionReturn,
+            $($arg: Clone,)*
+        {
+            const DO_CALL: *const () = Shim::<F, fn($($arg),*) -> R>::do_call as *const ();
+        }
+    };
+}
+
+impl_signature!();
+impl_signature!(A0 a0);
+impl_signature!(A0 a0, A1 a1);
+impl_signature!(A0 a0, A1 a1, A2 a2);
+impl_signature!(A0 a0, A1 a1, A2 a2, A3 a3);
+impl_signature!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4);
+impl_signature!(A0 a0, A1 a1, A2 a2, A3 a3, A4 a4, A5 a5);
@@ -1,7 +1,33 @@
 #![doc = include_str!("../README.md")]
+#![no_std]
+#![cfg_attr(feature = "nightly", feature(allocator_api))]
+
+extern crate alloc as rust_alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[macro_use]
+mod abi;
 
 pub mod alloc;
+pub mod pmr;
 pub mod semantics;
 
+pub mod adapter;
+pub mod any;
+pub mod bitset;
+pub mod compare;
+pub mod deque;
+pub mod flat;
+pub mod function;
+pub mod optional;
+pub mod pair;
+pub mod path;
+pub mod remote;
+pub mod span;
 pub mod string;
+pub mod tuple;
+pub mod variant;
 pub mod vec;
+
+mod rtti;
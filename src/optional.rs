@@ -0,0 +1,255 @@
+//! `std::optional` with the MSVC layout.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    mem::{self, ManuallyDrop},
+};
+
+/// Storage of `std::optional`.
+///
+/// Like the C++ union, it takes up at least one byte even if `T` is zero sized.
+#[repr(C)]
+union Storage<T> {
+    value: ManuallyDrop<T>,
+    _dummy: u8,
+}
+
+/// `std::optional<T>`.
+///
+/// The value is followed by the engaged flag, `{ T value; bool engaged; }`,
+/// and the whole is padded to the alignment of `T`.
+#[repr(C)]
+pub struct CxxOptional<T> {
+    storage: Storage<T>,
+    engaged: bool,
+}
+
+impl<T> CxxOptional<T> {
+    /// Creates a disengaged optional.
+    pub const fn none() -> Self {
+        Self {
+            storage: Storage { _dummy: 0 },
+            engaged: false,
+        }
+    }
+
+    /// Creates an engaged optional holding `value`.
+    pub const fn some(value: T) -> Self {
+        Self {
+            storage: Storage {
+                value: ManuallyDrop::new(value),
+            },
+            engaged: true,
+        }
+    }
+
+    pub const fn is_some(&self) -> bool {
+        self.engaged
+    }
+
+    pub const fn is_none(&self) -> bool {
+        !self.engaged
+    }
+
+    pub fn as_ref(&self) -> Option<&T> {
+        if self.engaged {
+            unsafe { Some(&self.storage.value) }
+        } else {
+            None
+        }
+    }
+
+    pub fn as_mut(&mut self) -> Option<&mut T> {
+        if self.engaged {
+            unsafe { Some(&mut self.storage.value) }
+        } else {
+            None
+        }
+    }
+
+    /// Takes the value out of the optional, leaving it disengaged.
+    pub fn take(&mut self) -> Option<T> {
+        if self.engaged {
+            self.engaged = false;
+            unsafe { Some(ManuallyDrop::take(&mut self.storage.value)) }
+        } else {
+            None
+        }
+    }
+
+    /// Replaces the value of the optional with `value`, returning the old value if any.
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        mem::replace(self, Self::some(value)).into_option()
+    }
+
+    /// Inserts `value` into the optional, dropping the old value if any,
+    /// and returns a mutable reference to it.
+    pub fn insert(&mut self, value: T) -> &mut T {
+        *self = Self::some(value);
+        unsafe { &mut self.storage.value }
+    }
+
+    /// Drops the value of the optional if any, leaving it disengaged.
+    pub fn reset(&mut self) {
+        drop(self.take());
+    }
+
+    pub fn into_option(mut self) -> Option<T> {
+        self.take()
+    }
+}
+
+impl<T: Clone> Clone for CxxOptional<T> {
+    fn clone(&self) -> Self {
+        self.as_ref().cloned().into()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CxxOptional<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CxxOptional").field(&self.as_ref()).finish()
+    }
+}
+
+impl<T> Default for CxxOptional<T> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<T> Drop for CxxOptional<T> {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+impl<T: PartialEq> PartialEq for CxxOptional<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<T: Eq> Eq for CxxOptional<T> {}
+
+impl<T: PartialOrd> PartialOrd for CxxOptional<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_ref().partial_cmp(&other.as_ref())
+    }
+}
+
+impl<T: Ord> Ord for CxxOptional<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(&other.as_ref())
+    }
+}
+
+impl<T: Hash> Hash for CxxOptional<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<T> From<T> for CxxOptional<T> {
+    fn from(value: T) -> Self {
+        Self::some(value)
+    }
+}
+
+impl<T> From<Option<T>> for CxxOptional<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Self::some(value),
+            None => Self::none(),
+        }
+    }
+}
+
+impl<T> From<CxxOptional<T>> for Option<T> {
+    fn from(value: CxxOptional<T>) -> Self {
+        value.into_option()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, mem::offset_of};
+
+    use super::*;
+
+    /// Counts how often it is dropped.
+    struct DropCount<'a>(&'a Cell<usize>);
+
+    impl Drop for DropCount<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn engaged_flag_follows_value() {
+        assert_eq!(mem::size_of::<CxxOptional<()>>(), 2);
+        assert_eq!(offset_of!(CxxOptional<()>, engaged), 1);
+
+        assert_eq!(mem::size_of::<CxxOptional<u8>>(), 2);
+        assert_eq!(offset_of!(CxxOptional<u8>, engaged), 1);
+
+        assert_eq!(mem::size_of::<CxxOptional<u32>>(), 8);
+        assert_eq!(offset_of!(CxxOptional<u32>, engaged), 4);
+
+        assert_eq!(mem::size_of::<CxxOptional<[u16; 3]>>(), 8);
+        assert_eq!(offset_of!(CxxOptional<[u16; 3]>, engaged), 6);
+
+        assert_eq!(mem::size_of::<CxxOptional<u64>>(), 16);
+        assert_eq!(offset_of!(CxxOptional<u64>, engaged), 8);
+    }
+
+    #[test]
+    fn take_replace_insert() {
+        let mut opt = CxxOptional::none();
+        assert!(opt.is_none());
+        assert_eq!(opt.take(), None);
+
+        assert_eq!(opt.replace(1), None);
+        assert_eq!(opt.replace(2), Some(1));
+        assert_eq!(opt.as_ref(), Some(&2));
+
+        *opt.insert(3) += 1;
+        assert_eq!(opt.as_ref(), Some(&4));
+
+        assert_eq!(opt.take(), Some(4));
+        assert!(opt.is_none());
+        assert_eq!(opt, CxxOptional::none());
+    }
+
+    #[test]
+    fn drops_value_once() {
+        let drops = Cell::new(0);
+
+        let mut opt = CxxOptional::some(DropCount(&drops));
+        opt.insert(DropCount(&drops));
+        assert_eq!(drops.get(), 1);
+
+        let old = opt.replace(DropCount(&drops));
+        assert_eq!(drops.get(), 1);
+        drop(old);
+        assert_eq!(drops.get(), 2);
+
+        opt.reset();
+        assert_eq!(drops.get(), 3);
+        opt.reset();
+        assert_eq!(drops.get(), 3);
+
+        drop(CxxOptional::some(DropCount(&drops)));
+        assert_eq!(drops.get(), 4);
+
+        let taken = CxxOptional::some(DropCount(&drops)).into_option();
+        assert_eq!(drops.get(), 4);
+        drop(taken);
+        assert_eq!(drops.get(), 5);
+
+        drop(CxxOptional::<DropCount>::none());
+        assert_eq!(drops.get(), 5);
+    }
+}
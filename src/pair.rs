@@ -0,0 +1,80 @@
+//! `std::pair` with the MSVC layout.
+
+/// `std::pair<A, B>`.
+///
+/// Unlike [`CxxTuple`](crate::tuple::CxxTuple), the elements are stored in order.
+///
+/// Zero-sized types take up no space, while C++ empty classes take up a byte
+/// as members, so an empty C++ element is declared as a `u8` placeholder.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CxxPair<A, B> {
+    pub first: A,
+    pub second: B,
+}
+
+impl<A, B> CxxPair<A, B> {
+    pub const fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+
+    pub fn as_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    pub fn as_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+
+    pub fn into_tuple(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A, B> From<(A, B)> for CxxPair<A, B> {
+    fn from((first, second): (A, B)) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A, B> From<CxxPair<A, B>> for (A, B) {
+    fn from(value: CxxPair<A, B>) -> Self {
+        value.into_tuple()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{offset_of, size_of};
+
+    use super::*;
+
+    #[test]
+    fn elements_are_in_order() {
+        assert_eq!(size_of::<CxxPair<u8, u64>>(), 16);
+        assert_eq!(offset_of!(CxxPair<u8, u64>, first), 0);
+        assert_eq!(offset_of!(CxxPair<u8, u64>, second), 8);
+
+        assert_eq!(size_of::<CxxPair<u32, u8>>(), 8);
+        assert_eq!(offset_of!(CxxPair<u32, u8>, second), 4);
+
+        assert_eq!(size_of::<CxxPair<u16, u8>>(), 4);
+        assert_eq!(offset_of!(CxxPair<u16, u8>, second), 2);
+    }
+
+    #[test]
+    fn zero_sized_elements_take_no_space() {
+        assert_eq!(size_of::<CxxPair<u8, ()>>(), 1);
+        assert_eq!(size_of::<CxxPair<(), u32>>(), 4);
+        assert_eq!(offset_of!(CxxPair<(), u32>, second), 0);
+    }
+
+    #[test]
+    fn converts_from_tuple() {
+        let mut pair = CxxPair::from((1, "one"));
+
+        *pair.as_mut().0 += 1;
+        assert_eq!(pair.as_ref(), (&2, &"one"));
+        assert_eq!(<(i32, &str)>::from(pair), (2, "one"));
+    }
+}
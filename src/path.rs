@@ -0,0 +1,685 @@
+//! `std::filesystem::path` with the MSVC layout.
+//!
+//! MSVC stores a path as a `std::wstring`, so [`CxxPath`] wraps a
+//! [`CxxWideString`]. Paths are parsed with the Windows rules, where both `\`
+//! and `/` are separators, by the borrowed [`CxxPathStr`], which works on
+//! plain UTF-16 slices on any platform.
+
+use core::{fmt, ops::Deref};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+use rust_alloc::{string::String, vec::Vec};
+
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc},
+    string::CxxWideString,
+};
+
+/// Separator appended by [`CxxPathStr::join`], `path::preferred_separator`.
+pub const PREFERRED_SEPARATOR: u16 = b'\\' as u16;
+
+/// `std::filesystem::path`.
+#[repr(transparent)]
+pub struct CxxPath<A: CxxProxy = DefaultAlloc> {
+    text: CxxWideString<A>,
+}
+
+/// Borrowed path, the text of a [`CxxPath`].
+#[repr(transparent)]
+#[derive(PartialEq, Eq, Hash)]
+pub struct CxxPathStr([u16]);
+
+/// Element of a path, as yielded by `path::iterator`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Component<'a> {
+    /// Root name such as `C:`, `\\server` or `\\?`.
+    RootName(&'a [u16]),
+    /// Separators following the root name.
+    RootDirectory(&'a [u16]),
+    /// File name, which is empty after a trailing separator.
+    FileName(&'a [u16]),
+}
+
+/// Iterator over the [`Component`]s of a [`CxxPathStr`].
+#[derive(Clone, Debug)]
+pub struct Components<'a> {
+    text: &'a [u16],
+    root_name_end: usize,
+    relative_begin: usize,
+    pos: usize,
+    trailing: bool,
+}
+
+impl CxxPath<DefaultAlloc> {
+    pub const fn new() -> Self {
+        Self {
+            text: CxxWideString::new(),
+        }
+    }
+}
+
+impl<A: CxxProxy> CxxPath<A> {
+    pub const fn new_in(alloc: A) -> Self {
+        Self {
+            text: CxxWideString::new_in(alloc),
+        }
+    }
+
+    pub fn from_bytes_in<T: AsRef<[u16]>>(s: T, alloc: A) -> Self {
+        Self {
+            text: CxxWideString::from_bytes_in(s, alloc),
+        }
+    }
+
+    /// Creates a path from a Rust path.
+    ///
+    /// Outside of Windows, the path is converted lossily through UTF-8.
+    #[cfg(feature = "std")]
+    pub fn from_path_in<P: AsRef<Path>>(path: P, alloc: A) -> Self {
+        Self::from_bytes_in(encode_path(path.as_ref()), alloc)
+    }
+
+    pub const fn from_wide_string(text: CxxWideString<A>) -> Self {
+        Self { text }
+    }
+
+    pub fn into_wide_string(self) -> CxxWideString<A> {
+        self.text
+    }
+
+    pub fn as_wide_string(&self) -> &CxxWideString<A> {
+        &self.text
+    }
+
+    pub fn as_path_str(&self) -> &CxxPathStr {
+        CxxPathStr::new(self.text.as_bytes())
+    }
+
+    pub const fn allocator(&self) -> &A {
+        self.text.allocator()
+    }
+
+    /// Appends `other` like `operator/=`.
+    ///
+    /// See [`CxxPathStr::join`].
+    pub fn push<P: AsRef<CxxPathStr> + ?Sized>(&mut self, other: &P) {
+        let joined = self.as_path_str().join(other);
+        self.text.replace(joined);
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+    }
+}
+
+impl<A: CxxProxy + Clone> CxxPath<A> {
+    /// Creates a path with `other` appended like `operator/`.
+    ///
+    /// See [`CxxPathStr::join`].
+    pub fn join<P: AsRef<CxxPathStr> + ?Sized>(&self, other: &P) -> Self {
+        let joined = self.as_path_str().join(other);
+        Self::from_bytes_in(joined, self.allocator().clone())
+    }
+}
+
+impl CxxPathStr {
+    pub fn new<S: AsRef<[u16]> + ?Sized>(s: &S) -> &Self {
+        unsafe { &*(s.as_ref() as *const [u16] as *const Self) }
+    }
+
+    pub fn as_bytes(&self) -> &[u16] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn components(&self) -> Components<'_> {
+        let root_name_end = root_name_end(&self.0);
+
+        Components {
+            text: &self.0,
+            root_name_end,
+            relative_begin: skip_separators(&self.0, root_name_end),
+            pos: 0,
+            trailing: false,
+        }
+    }
+
+    /// Root name such as `C:`, `\\server` or `\\?`.
+    pub fn root_name(&self) -> Option<&CxxPathStr> {
+        non_empty(&self.0[..root_name_end(&self.0)])
+    }
+
+    pub fn root_directory(&self) -> Option<&CxxPathStr> {
+        let root_name_end = root_name_end(&self.0);
+        non_empty(&self.0[root_name_end..self.relative_begin()])
+    }
+
+    pub fn has_root_directory(&self) -> bool {
+        self.root_directory().is_some()
+    }
+
+    /// Path following the root name and root directory.
+    pub fn relative_path(&self) -> &CxxPathStr {
+        CxxPathStr::new(&self.0[self.relative_begin()..])
+    }
+
+    /// Path without the last file name and the separators before it.
+    pub fn parent_path(&self) -> &CxxPathStr {
+        let relative_begin = self.relative_begin();
+        let mut end = self.0.len();
+
+        while end != relative_begin && !is_separator(self.0[end - 1]) {
+            end -= 1;
+        }
+
+        while end != relative_begin && is_separator(self.0[end - 1]) {
+            end -= 1;
+        }
+
+        CxxPathStr::new(&self.0[..end])
+    }
+
+    /// Last file name, which is missing after a trailing separator.
+    pub fn file_name(&self) -> Option<&CxxPathStr> {
+        non_empty(&self.0[self.file_name_begin()..])
+    }
+
+    /// File name without the extension and any NTFS alternate data stream.
+    pub fn file_stem(&self) -> Option<&CxxPathStr> {
+        let (name, stream) = self.file_name_parts();
+        non_empty(&name[..find_extension(&name[..stream])])
+    }
+
+    /// Extension of the file name, including the dot as in C++.
+    ///
+    /// The file names `.` and `..`, and file names starting with their only
+    /// dot have no extension.
+    pub fn extension(&self) -> Option<&CxxPathStr> {
+        let (name, stream) = self.file_name_parts();
+        non_empty(&name[find_extension(&name[..stream])..stream])
+    }
+
+    /// Whether the path is absolute, such as `C:\dir` or `\\server\share`.
+    ///
+    /// Paths like `C:dir` and `\dir` are relative to a drive or the root
+    /// directory of the current drive.
+    pub fn is_absolute(&self) -> bool {
+        if has_drive_letter(&self.0) {
+            self.0.get(2).is_some_and(|&c| is_separator(c))
+        } else {
+            root_name_end(&self.0) != 0
+        }
+    }
+
+    pub fn is_relative(&self) -> bool {
+        !self.is_absolute()
+    }
+
+    /// Returns the characters of `self` with `other` appended like `operator/`.
+    ///
+    /// An absolute `other`, or one with a different root name, replaces the
+    /// path, and one with a root directory replaces everything following the
+    /// root name. Otherwise a separator is inserted between the two paths
+    /// if needed.
+    pub fn join<P: AsRef<CxxPathStr> + ?Sized>(&self, other: &P) -> Vec<u16> {
+        let other = other.as_ref();
+        let other_root_name_end = root_name_end(&other.0);
+        let root_name_end = root_name_end(&self.0);
+
+        if other.is_absolute()
+            || (other_root_name_end != 0
+                && other.0[..other_root_name_end] != self.0[..root_name_end])
+        {
+            return other.0.to_vec();
+        }
+
+        let mut joined = self.0.to_vec();
+
+        if other.has_root_directory() {
+            joined.truncate(root_name_end);
+        } else if root_name_end == joined.len() {
+            // `\\server` needs a separator before a relative path, `C:` does not.
+            if root_name_end >= 3 {
+                joined.push(PREFERRED_SEPARATOR);
+            }
+        } else if !joined.last().is_some_and(|&c| is_separator(c)) {
+            joined.push(PREFERRED_SEPARATOR);
+        }
+
+        joined.extend_from_slice(&other.0[other_root_name_end..]);
+        joined
+    }
+
+    /// Converts the path to a Rust path.
+    ///
+    /// Outside of Windows, the path is converted lossily through UTF-8.
+    #[cfg(feature = "std")]
+    pub fn to_path_buf(&self) -> PathBuf {
+        decode_path(&self.0)
+    }
+
+    fn relative_begin(&self) -> usize {
+        skip_separators(&self.0, root_name_end(&self.0))
+    }
+
+    fn file_name_begin(&self) -> usize {
+        let relative_begin = self.relative_begin();
+        let mut begin = self.0.len();
+
+        while begin != relative_begin && !is_separator(self.0[begin - 1]) {
+            begin -= 1;
+        }
+
+        begin
+    }
+
+    /// File name and the position of its alternate data stream, if any.
+    fn file_name_parts(&self) -> (&[u16], usize) {
+        let name = &self.0[self.file_name_begin()..];
+        let stream = name
+            .iter()
+            .position(|&c| c == b':' as u16)
+            .unwrap_or(name.len());
+
+        (name, stream)
+    }
+}
+
+impl<'a> Component<'a> {
+    pub fn as_bytes(&self) -> &'a [u16] {
+        match *self {
+            Self::RootName(s) | Self::RootDirectory(s) | Self::FileName(s) => s,
+        }
+    }
+
+    pub fn as_path_str(&self) -> &'a CxxPathStr {
+        CxxPathStr::new(self.as_bytes())
+    }
+}
+
+impl<'a> Iterator for Components<'a> {
+    type Item = Component<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.text;
+
+        if self.pos == 0 && self.root_name_end != 0 {
+            self.pos = self.root_name_end;
+            return Some(Component::RootName(&text[..self.root_name_end]));
+        }
+
+        if self.pos == self.root_name_end && self.relative_begin != self.root_name_end {
+            self.pos = self.relative_begin;
+            return Some(Component::RootDirectory(
+                &text[self.root_name_end..self.relative_begin],
+            ));
+        }
+
+        if self.pos >= text.len() {
+            return core::mem::take(&mut self.trailing)
+                .then(|| Component::FileName(&text[text.len()..]));
+        }
+
+        let begin = self.pos;
+        let end = text[begin..]
+            .iter()
+            .position(|&c| is_separator(c))
+            .map_or(text.len(), |len| begin + len);
+
+        self.pos = skip_separators(text, end);
+        self.trailing = end != text.len() && self.pos == text.len();
+
+        Some(Component::FileName(&text[begin..end]))
+    }
+}
+
+impl core::iter::FusedIterator for Components<'_> {}
+
+impl<'a> IntoIterator for &'a CxxPathStr {
+    type Item = Component<'a>;
+
+    type IntoIter = Components<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.components()
+    }
+}
+
+impl<A: CxxProxy> Deref for CxxPath<A> {
+    type Target = CxxPathStr;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_path_str()
+    }
+}
+
+impl AsRef<CxxPathStr> for CxxPathStr {
+    fn as_ref(&self) -> &CxxPathStr {
+        self
+    }
+}
+
+impl AsRef<CxxPathStr> for [u16] {
+    fn as_ref(&self) -> &CxxPathStr {
+        CxxPathStr::new(self)
+    }
+}
+
+impl AsRef<CxxPathStr> for Vec<u16> {
+    fn as_ref(&self) -> &CxxPathStr {
+        CxxPathStr::new(self)
+    }
+}
+
+impl<A: CxxProxy> AsRef<CxxPathStr> for CxxPath<A> {
+    fn as_ref(&self) -> &CxxPathStr {
+        self.as_path_str()
+    }
+}
+
+impl AsRef<[u16]> for CxxPathStr {
+    fn as_ref(&self) -> &[u16] {
+        &self.0
+    }
+}
+
+impl<A: CxxProxy> AsRef<[u16]> for CxxPath<A> {
+    fn as_ref(&self) -> &[u16] {
+        self.text.as_bytes()
+    }
+}
+
+impl fmt::Debug for CxxPathStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&String::from_utf16_lossy(&self.0), f)
+    }
+}
+
+impl<A: CxxProxy> fmt::Debug for CxxPath<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_path_str(), f)
+    }
+}
+
+impl<A: CxxProxy + Default> Default for CxxPath<A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<A: CxxProxy + Clone> Clone for CxxPath<A> {
+    fn clone(&self) -> Self {
+        Self {
+            text: self.text.clone(),
+        }
+    }
+}
+
+impl<A1: CxxProxy, A2: CxxProxy> PartialEq<CxxPath<A2>> for CxxPath<A1> {
+    fn eq(&self, other: &CxxPath<A2>) -> bool {
+        self.as_path_str() == other.as_path_str()
+    }
+}
+
+impl<A: CxxProxy> Eq for CxxPath<A> {}
+
+#[cfg(feature = "std")]
+impl From<&Path> for CxxPath {
+    fn from(path: &Path) -> Self {
+        Self::from_path_in(path, crate::alloc::DEFAULT_ALLOC)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<PathBuf> for CxxPath {
+    fn from(path: PathBuf) -> Self {
+        Self::from_path_in(path, crate::alloc::DEFAULT_ALLOC)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&CxxPathStr> for PathBuf {
+    fn from(path: &CxxPathStr) -> Self {
+        path.to_path_buf()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: CxxProxy> From<&CxxPath<A>> for PathBuf {
+    fn from(path: &CxxPath<A>) -> Self {
+        path.to_path_buf()
+    }
+}
+
+fn is_separator(c: u16) -> bool {
+    c == b'\\' as u16 || c == b'/' as u16
+}
+
+fn has_drive_letter(text: &[u16]) -> bool {
+    matches!(text, [letter, colon, ..]
+        if (b'A' as u16..=b'Z' as u16).contains(&(letter & !0x20)) && *colon == b':' as u16)
+}
+
+/// End of the root name, `_Find_root_name_end`.
+fn root_name_end(text: &[u16]) -> usize {
+    if text.len() < 2 {
+        return 0;
+    }
+
+    if has_drive_letter(text) {
+        return 2;
+    }
+
+    if !is_separator(text[0]) {
+        return 0;
+    }
+
+    // `\\?\`, `\\.\` and `\??\`
+    if text.len() >= 4
+        && is_separator(text[3])
+        && (text.len() == 4 || !is_separator(text[4]))
+        && ((is_separator(text[1]) && (text[2] == b'?' as u16 || text[2] == b'.' as u16))
+            || (text[1] == b'?' as u16 && text[2] == b'?' as u16))
+    {
+        return 3;
+    }
+
+    // `\\server`
+    if text.len() >= 3 && is_separator(text[1]) && !is_separator(text[2]) {
+        return text[3..]
+            .iter()
+            .position(|&c| is_separator(c))
+            .map_or(text.len(), |len| 3 + len);
+    }
+
+    0
+}
+
+fn skip_separators(text: &[u16], mut pos: usize) -> usize {
+    while pos < text.len() && is_separator(text[pos]) {
+        pos += 1;
+    }
+
+    pos
+}
+
+/// Start of the extension of a file name without an alternate data stream, `_Find_extension`.
+fn find_extension(name: &[u16]) -> usize {
+    let dot = b'.' as u16;
+
+    match name {
+        [] | [_] => name.len(),
+        [.., last] if *last == dot => {
+            if name == [dot, dot] {
+                name.len()
+            } else {
+                name.len() - 1
+            }
+        }
+        [_, rest @ ..] => rest
+            .iter()
+            .rposition(|&c| c == dot)
+            .map_or(name.len(), |pos| pos + 1),
+    }
+}
+
+fn non_empty(text: &[u16]) -> Option<&CxxPathStr> {
+    (!text.is_empty()).then(|| CxxPathStr::new(text))
+}
+
+#[cfg(all(feature = "std", windows))]
+fn encode_path(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    path.as_os_str().encode_wide().collect()
+}
+
+#[cfg(all(feature = "std", not(windows)))]
+fn encode_path(path: &Path) -> Vec<u16> {
+    path.to_string_lossy().encode_utf16().collect()
+}
+
+#[cfg(all(feature = "std", windows))]
+fn decode_path(text: &[u16]) -> PathBuf {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt};
+
+    OsString::from_wide(text).into()
+}
+
+#[cfg(all(feature = "std", not(windows)))]
+fn decode_path(text: &[u16]) -> PathBuf {
+    String::from_utf16_lossy(text).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    fn narrow(s: Option<&CxxPathStr>) -> Option<String> {
+        s.map(|s| String::from_utf16_lossy(s.as_bytes()))
+    }
+
+    fn components(path: &str) -> Vec<(char, String)> {
+        CxxPathStr::new(&wide(path))
+            .components()
+            .map(|c| {
+                let kind = match c {
+                    Component::RootName(_) => 'n',
+                    Component::RootDirectory(_) => 'd',
+                    Component::FileName(_) => 'f',
+                };
+
+                (kind, String::from_utf16_lossy(c.as_bytes()))
+            })
+            .collect()
+    }
+
+    fn owned(components: &[(char, &str)]) -> Vec<(char, String)> {
+        components.iter().map(|&(k, s)| (k, s.into())).collect()
+    }
+
+    #[test]
+    fn components_follow_windows_rules() {
+        assert_eq!(
+            components(r"C:\dir/file.txt"),
+            owned(&[('n', "C:"), ('d', r"\"), ('f', "dir"), ('f', "file.txt")])
+        );
+        assert_eq!(
+            components(r"\\server\share\x"),
+            owned(&[('n', r"\\server"), ('d', r"\"), ('f', "share"), ('f', "x")])
+        );
+        assert_eq!(
+            components(r"\\?\C:\x"),
+            owned(&[('n', r"\\?"), ('d', r"\"), ('f', "C:"), ('f', "x")])
+        );
+        assert_eq!(
+            components(r"a//b\\c"),
+            owned(&[('f', "a"), ('f', "b"), ('f', "c")])
+        );
+        assert_eq!(
+            components("dir/sub/"),
+            owned(&[('f', "dir"), ('f', "sub"), ('f', "")])
+        );
+        assert_eq!(components(""), owned(&[]));
+    }
+
+    #[test]
+    fn decomposition() {
+        let path = wide(r"C:\dir\archive.tar.gz");
+        let path = CxxPathStr::new(&path);
+
+        assert_eq!(narrow(path.root_name()).as_deref(), Some("C:"));
+        assert_eq!(narrow(path.root_directory()).as_deref(), Some(r"\"));
+        assert_eq!(
+            narrow(Some(path.relative_path())).as_deref(),
+            Some(r"dir\archive.tar.gz")
+        );
+        assert_eq!(narrow(Some(path.parent_path())).as_deref(), Some(r"C:\dir"));
+        assert_eq!(narrow(path.file_name()).as_deref(), Some("archive.tar.gz"));
+        assert_eq!(narrow(path.file_stem()).as_deref(), Some("archive.tar"));
+        assert_eq!(narrow(path.extension()).as_deref(), Some(".gz"));
+
+        let root = wide(r"C:\");
+        assert_eq!(
+            narrow(Some(CxxPathStr::new(&root).parent_path())).as_deref(),
+            Some(r"C:\")
+        );
+    }
+
+    #[test]
+    fn extensions() {
+        let extension = |name: &str| narrow(CxxPathStr::new(&wide(name)).extension());
+
+        assert_eq!(extension(".profile"), None);
+        assert_eq!(extension(".."), None);
+        assert_eq!(extension("."), None);
+        assert_eq!(extension("file"), None);
+        assert_eq!(extension("file.").as_deref(), Some("."));
+        assert_eq!(extension("file.txt:stream").as_deref(), Some(".txt"));
+        assert_eq!(extension("dir.d/").as_deref(), None);
+    }
+
+    #[test]
+    fn absolute_paths() {
+        let is_absolute = |path: &str| CxxPathStr::new(&wide(path)).is_absolute();
+
+        assert!(is_absolute(r"C:\x"));
+        assert!(is_absolute(r"\\server\share"));
+        assert!(is_absolute(r"\\?\C:\x"));
+        assert!(!is_absolute("C:x"));
+        assert!(!is_absolute(r"\x"));
+        assert!(!is_absolute("x/y"));
+    }
+
+    #[test]
+    fn join() {
+        let join =
+            |a: &str, b: &str| String::from_utf16_lossy(&CxxPathStr::new(&wide(a)).join(&wide(b)));
+
+        assert_eq!(join(r"C:\dir", "file"), r"C:\dir\file");
+        assert_eq!(join(r"dir\", "file"), r"dir\file");
+        assert_eq!(join("C:", "file"), "C:file");
+        assert_eq!(join(r"\\server", "share"), r"\\server\share");
+        assert_eq!(join(r"C:\dir", r"D:\x"), r"D:\x");
+        assert_eq!(join(r"C:\dir", "D:x"), "D:x");
+        assert_eq!(join(r"C:\dir", r"\root"), r"C:\root");
+        assert_eq!(join(r"C:\dir", "C:file"), r"C:\dir\file");
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn to_path_buf() {
+        assert_eq!(
+            CxxPathStr::new(&wide("dir/file.txt")).to_path_buf(),
+            PathBuf::from("dir/file.txt")
+        );
+    }
+}
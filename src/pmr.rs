@@ -0,0 +1,390 @@
+//! `std::pmr::memory_resource` and `std::pmr::polymorphic_allocator`.
+//!
+//! A `polymorphic_allocator` is a pointer to a `memory_resource`, which is
+//! stored where other containers store their allocator. [`PmrAlloc`] wraps
+//! such a pointer as a [`CxxProxy`], and the
+//! `pmr` modules of [`vec`](crate::vec::pmr) and [`string`](crate::string::pmr)
+//! name the containers that use it.
+//!
+//! Rust allocators are exposed to C++ as a [`MemoryResource`], which starts
+//! with a vtable generated from [`CxxMemoryResource`].
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
+    fmt,
+    marker::PhantomData,
+    ptr::{self, NonNull},
+};
+
+use rust_alloc::alloc::handle_alloc_error;
+
+use crate::alloc::{CxxProxy, DefaultAlloc};
+
+/// Vtable of `std::pmr::memory_resource`.
+#[repr(C)]
+struct VTable {
+    scalar_deleting_destructor: member_fn!(fn(*mut c_void, u32) -> *mut c_void),
+    do_allocate: member_fn!(fn(*mut c_void, usize, usize) -> *mut c_void),
+    do_deallocate: member_fn!(fn(*mut c_void, *mut c_void, usize, usize)),
+    do_is_equal: member_fn!(fn(*const c_void, *const c_void) -> bool),
+}
+
+/// `std::pmr::memory_resource`, the base of any memory resource.
+///
+/// Only ever used behind a reference, since the derived resource follows the vtable pointer.
+#[repr(C)]
+pub struct RawMemoryResource {
+    vftable: *const VTable,
+}
+
+impl RawMemoryResource {
+    /// Allocates memory, `allocate`.
+    ///
+    /// C++ resources report failure by throwing, which aborts the process.
+    pub fn allocate(&self, layout: Layout) -> *mut u8 {
+        unsafe {
+            ((*self.vftable).do_allocate)(self.as_ptr(), layout.size(), layout.align()).cast()
+        }
+    }
+
+    /// Frees memory, `deallocate`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated by an equal resource with `layout`.
+    pub unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
+        unsafe {
+            ((*self.vftable).do_deallocate)(
+                self.as_ptr(),
+                ptr.cast(),
+                layout.size(),
+                layout.align(),
+            );
+        }
+    }
+
+    /// Whether memory allocated by one resource can be freed by the other, `operator==`.
+    pub fn is_equal(&self, other: &RawMemoryResource) -> bool {
+        ptr::eq(self, other)
+            || unsafe { ((*self.vftable).do_is_equal)(self.as_ptr(), other.as_ptr()) }
+    }
+
+    pub fn as_ptr(&self) -> *mut c_void {
+        self as *const Self as *mut c_void
+    }
+}
+
+impl fmt::Debug for RawMemoryResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawMemoryResource")
+            .field(&self.as_ptr())
+            .finish()
+    }
+}
+
+/// Rust allocators that can be exposed to C++ as a [`MemoryResource`].
+pub trait CxxMemoryResource: GlobalAlloc {
+    /// Whether memory allocated by `other` can be freed by `self`, `do_is_equal`.
+    ///
+    /// Only called if `other` is not the same resource.
+    fn is_equal(&self, other: &RawMemoryResource) -> bool {
+        let _ = other;
+        false
+    }
+}
+
+impl CxxMemoryResource for DefaultAlloc {}
+
+/// A `std::pmr::memory_resource` that allocates through the Rust allocator `R`.
+///
+/// C++ must not destroy the resource, which is owned by Rust.
+///
+/// If `R` fails to allocate, `do_allocate` calls [`handle_alloc_error`], which
+/// aborts the process. C++ callers that expect `std::bad_alloc` to be thrown
+/// cannot recover from the failure.
+#[repr(C)]
+pub struct MemoryResource<R: CxxMemoryResource> {
+    base: RawMemoryResource,
+    resource: R,
+}
+
+unsafe impl<R: CxxMemoryResource + Send> Send for MemoryResource<R> {}
+
+unsafe impl<R: CxxMemoryResource + Sync> Sync for MemoryResource<R> {}
+
+impl<R: CxxMemoryResource> MemoryResource<R> {
+    const VTABLE: &'static VTable = &VTable {
+        scalar_deleting_destructor: Self::scalar_deleting_destructor,
+        do_allocate: Self::do_allocate,
+        do_deallocate: Self::do_deallocate,
+        do_is_equal: Self::do_is_equal,
+    };
+
+    pub const fn new(resource: R) -> Self {
+        Self {
+            base: RawMemoryResource {
+                vftable: Self::VTABLE,
+            },
+            resource,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.resource
+    }
+
+    pub fn into_inner(self) -> R {
+        self.resource
+    }
+
+    pub fn as_raw(&self) -> &RawMemoryResource {
+        &self.base
+    }
+
+    /// Returns an allocator that allocates from this resource.
+    pub fn alloc(&self) -> PmrAlloc<'_> {
+        PmrAlloc::new(&self.base)
+    }
+
+    member_fn! {
+        fn scalar_deleting_destructor(this: *mut c_void, _flags: u32) -> *mut c_void {
+            this
+        }
+    }
+
+    member_fn! {
+        fn do_allocate(this: *mut c_void, size: usize, alignment: usize) -> *mut c_void {
+            let layout =
+                Layout::from_size_align(size, alignment).expect("bad layout passed from C++");
+
+            let ptr = unsafe { (*this.cast::<Self>()).resource.alloc(layout) };
+
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+
+            ptr.cast()
+        }
+    }
+
+    member_fn! {
+        fn do_deallocate(this: *mut c_void, ptr: *mut c_void, size: usize, alignment: usize) {
+            unsafe {
+                let layout =
+                    Layout::from_size_align(size, alignment).expect("bad layout passed from C++");
+
+                (*this.cast::<Self>()).resource.dealloc(ptr.cast(), layout);
+            }
+        }
+    }
+
+    member_fn! {
+        fn do_is_equal(this: *const c_void, other: *const c_void) -> bool {
+            ptr::eq(this, other)
+                || unsafe {
+                    (*this.cast::<Self>())
+                        .resource
+                        .is_equal(&*other.cast::<RawMemoryResource>())
+                }
+        }
+    }
+}
+
+impl<R: CxxMemoryResource + Default> Default for MemoryResource<R> {
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl<R: CxxMemoryResource + fmt::Debug> fmt::Debug for MemoryResource<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MemoryResource")
+            .field(&self.resource)
+            .finish()
+    }
+}
+
+/// `std::pmr::polymorphic_allocator`, a pointer to the memory resource it allocates from.
+///
+/// Allocators compare equal if their resources do.
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct PmrAlloc<'a> {
+    resource: NonNull<RawMemoryResource>,
+    _marker: PhantomData<&'a RawMemoryResource>,
+}
+
+/// Allocator handed out by the [`CxxProxy`] of a [`PmrAlloc`].
+struct ResourceProxy<'a>(PmrAlloc<'a>);
+
+impl<'a> PmrAlloc<'a> {
+    pub const fn new(resource: &'a RawMemoryResource) -> Self {
+        Self {
+            resource: unsafe {
+                NonNull::new_unchecked(resource as *const RawMemoryResource as *mut _)
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Wraps a `std::pmr::memory_resource*` created by C++.
+    ///
+    /// # Safety
+    ///
+    /// `resource` must point to a `std::pmr::memory_resource` that outlives `'a`.
+    pub const unsafe fn from_raw(resource: NonNull<c_void>) -> Self {
+        Self {
+            resource: resource.cast(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn resource(&self) -> &'a RawMemoryResource {
+        unsafe { self.resource.as_ref() }
+    }
+}
+
+unsafe impl GlobalAlloc for ResourceProxy<'_> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.0.resource().allocate(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.0.resource().deallocate(ptr, layout) }
+    }
+}
+
+/// `std::pmr::polymorphic_allocator` does not propagate.
+impl CxxProxy for PmrAlloc<'_> {
+    fn proxy<'a>(&self) -> impl GlobalAlloc + 'a
+    where
+        Self: 'a,
+    {
+        ResourceProxy(*self)
+    }
+
+    fn is_equal(&self, other: &Self) -> bool {
+        self.resource().is_equal(other.resource())
+    }
+}
+
+impl fmt::Debug for PmrAlloc<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PmrAlloc").field(&self.resource).finish()
+    }
+}
+
+impl PartialEq for PmrAlloc<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.resource().is_equal(other.resource())
+    }
+}
+
+impl Eq for PmrAlloc<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+
+    use rust_alloc::vec::Vec;
+
+    use super::*;
+    use crate::vec::pmr::CxxVec;
+
+    /// Records the layouts of the calls reaching the resource.
+    #[derive(Default)]
+    struct Recording {
+        allocs: RefCell<Vec<(usize, usize)>>,
+        deallocs: RefCell<Vec<(usize, usize)>>,
+    }
+
+    unsafe impl GlobalAlloc for Recording {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.allocs
+                .borrow_mut()
+                .push((layout.size(), layout.align()));
+            unsafe { DefaultAlloc::default().alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.deallocs
+                .borrow_mut()
+                .push((layout.size(), layout.align()));
+            unsafe { DefaultAlloc::default().dealloc(ptr, layout) }
+        }
+    }
+
+    impl CxxMemoryResource for Recording {}
+
+    /// Equal to any other resource.
+    struct Shared;
+
+    unsafe impl GlobalAlloc for Shared {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            unsafe { DefaultAlloc::default().alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { DefaultAlloc::default().dealloc(ptr, layout) }
+        }
+    }
+
+    impl CxxMemoryResource for Shared {
+        fn is_equal(&self, _other: &RawMemoryResource) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn vec_allocates_from_resource() {
+        let resource = MemoryResource::new(Recording::default());
+
+        let mut vec = CxxVec::<u64>::new_in(resource.alloc());
+        vec.reserve(4);
+        (1..=4).for_each(|i| vec.push(i));
+        assert_eq!(vec.as_slice(), [1, 2, 3, 4]);
+        drop(vec);
+
+        let recording = resource.get_ref();
+        assert_eq!(*recording.allocs.borrow(), [(32, 8)]);
+        assert_eq!(*recording.deallocs.borrow(), [(32, 8)]);
+    }
+
+    #[test]
+    fn raw_resource_reaches_rust_allocator() {
+        let resource = MemoryResource::new(Recording::default());
+        let raw = resource.as_raw();
+        let layout = Layout::from_size_align(24, 16).unwrap();
+
+        let ptr = raw.allocate(layout);
+        assert!(!ptr.is_null());
+        assert!(ptr.cast::<u128>().is_aligned());
+        unsafe { raw.deallocate(ptr, layout) };
+
+        assert_eq!(*resource.get_ref().allocs.borrow(), [(24, 16)]);
+        assert_eq!(*resource.get_ref().deallocs.borrow(), [(24, 16)]);
+    }
+
+    #[test]
+    fn allocators_of_same_resource_are_equal() {
+        let resource = MemoryResource::new(Recording::default());
+
+        assert!(resource.alloc().is_equal(&resource.alloc()));
+        assert_eq!(resource.alloc(), resource.alloc());
+    }
+
+    #[test]
+    fn allocators_of_different_resources() {
+        let first = MemoryResource::new(Recording::default());
+        let second = MemoryResource::new(Recording::default());
+
+        assert!(!first.alloc().is_equal(&second.alloc()));
+        assert_ne!(first.alloc(), second.alloc());
+
+        let shared = MemoryResource::new(Shared);
+
+        assert!(shared.alloc().is_equal(&first.alloc()));
+        assert!(!first.alloc().is_equal(&shared.alloc()));
+    }
+}
@@ -0,0 +1,270 @@
+//! Reading containers out of another address space.
+//!
+//! Pointers stored in the containers of another process or a memory dump
+//! are not valid locally, so values are decoded through a [`MemorySource`]
+//! into owned snapshots instead.
+//!
+//! The target address space may have a different pointer width than the host,
+//! see [`raw::Pointer`].
+
+use core::{error::Error, fmt, mem};
+
+use raw::{NativePtr, Pointer};
+
+use crate::{string::RawStringError, vec::RawVecError};
+
+pub mod raw;
+pub mod string;
+pub mod vec;
+
+pub use string::{
+    RemoteNarrowString, RemoteNarrowString32, RemoteNarrowString64, RemoteStringLayout,
+    RemoteUtf16String, RemoteUtf16String32, RemoteUtf16String64, RemoteUtf32String,
+    RemoteUtf32String32, RemoteUtf32String64, RemoteUtf8String, RemoteUtf8String32,
+    RemoteUtf8String64, RemoteWideString, RemoteWideString32, RemoteWideString64,
+};
+
+use rust_alloc::vec::Vec;
+pub use vec::{RemoteVec, RemoteVec32, RemoteVec64, RemoteVecLayout};
+
+/// Trait for types that can read the memory of another address space.
+pub trait MemorySource {
+    type Error;
+
+    /// Reads `buf.len()` bytes starting at `address` into `buf`.
+    fn read_bytes(&self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+impl<S: MemorySource + ?Sized> MemorySource for &S {
+    type Error = S::Error;
+
+    fn read_bytes(&self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).read_bytes(address, buf)
+    }
+}
+
+/// Memory source backed by a local buffer that is mapped at `base`
+/// in the other address space.
+///
+/// Can stand in for a process or a memory dump.
+#[derive(Clone, Copy, Debug)]
+pub struct BufferSource<'a> {
+    base: u64,
+    bytes: &'a [u8],
+}
+
+impl<'a> BufferSource<'a> {
+    pub const fn new(base: u64, bytes: &'a [u8]) -> Self {
+        Self { base, bytes }
+    }
+
+    pub const fn base(&self) -> u64 {
+        self.base
+    }
+
+    pub const fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+impl MemorySource for BufferSource<'_> {
+    type Error = OutOfBounds;
+
+    fn read_bytes(&self, address: u64, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let out_of_bounds = OutOfBounds {
+            address,
+            len: buf.len(),
+        };
+
+        let start = address
+            .checked_sub(self.base)
+            .and_then(|offset| usize::try_from(offset).ok())
+            .ok_or(out_of_bounds)?;
+
+        let bytes = start
+            .checked_add(buf.len())
+            .and_then(|end| self.bytes.get(start..end))
+            .ok_or(out_of_bounds)?;
+
+        buf.copy_from_slice(bytes);
+
+        Ok(())
+    }
+}
+
+/// Error returned by [`BufferSource`] when a read is not fully contained in its buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub address: u64,
+    pub len: usize,
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read of {} bytes at {:#x} is out of bounds",
+            self.len, self.address
+        )
+    }
+}
+
+impl Error for OutOfBounds {}
+
+/// Error returned when reading or decoding a remote value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemoteError<E> {
+    /// The memory source failed to read the value.
+    Read(E),
+    /// A vector breaks the container invariants.
+    Vec(RawVecError),
+    /// A string breaks the container invariants.
+    String(RawStringError),
+    /// The contents of a container, `len` bytes or more than `usize::MAX`
+    /// if `len` is `usize::MAX`, do not fit in local memory.
+    TooLarge { len: usize },
+}
+
+impl<E: fmt::Display> fmt::Display for RemoteError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(e) => write!(f, "failed to read remote memory: {e}"),
+            Self::Vec(e) => write!(f, "invalid remote vector: {e}"),
+            Self::String(e) => write!(f, "invalid remote string: {e}"),
+            Self::TooLarge { len } => write!(f, "remote container of {len} bytes is too large"),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for RemoteError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Read(e) => Some(e),
+            Self::Vec(e) => Some(e),
+            Self::String(e) => Some(e),
+            Self::TooLarge { .. } => None,
+        }
+    }
+}
+
+/// Trait for types that can be decoded from the memory of another address space
+/// with pointers of type `P`.
+///
+/// Containers of zero-sized values are rejected at compile time:
+///
+/// ```compile_fail
+/// use cxx_stl::remote::{BufferSource, Remote, RemoteVec64};
+///
+/// let source = BufferSource::new(0, &[0; 24]);
+/// let _ = RemoteVec64::<[u8; 0]>::read(&source, 0);
+/// ```
+pub trait Remote<P: Pointer = NativePtr>: Sized {
+    /// Size of the value in the other address space.
+    const SIZE: usize;
+
+    /// Alignment of the value in the other address space.
+    const ALIGN: usize;
+
+    /// Decodes a value from its [`Remote::SIZE`] bytes,
+    /// following any pointers through `source`.
+    fn decode<S>(bytes: &[u8], source: &S) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized;
+
+    /// Reads and decodes a value at `address`.
+    fn read<S>(source: &S, address: u64) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized,
+    {
+        let bytes = read_buf(source, address, Self::SIZE)?;
+        Self::decode(&bytes, source)
+    }
+}
+
+macro_rules! impl_remote_primitive {
+    ($($t:ty),*) => {$(
+        impl<P: Pointer> Remote<P> for $t {
+            const SIZE: usize = mem::size_of::<$t>();
+
+            const ALIGN: usize = mem::size_of::<$t>();
+
+            fn decode<S>(bytes: &[u8], _source: &S) -> Result<Self, RemoteError<S::Error>>
+            where
+                S: MemorySource + ?Sized,
+            {
+                let bytes = bytes[..mem::size_of::<$t>()].try_into().expect("bad remote value size");
+                Ok(<$t>::from_le_bytes(bytes))
+            }
+        }
+    )*};
+}
+
+impl_remote_primitive!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl<P: Pointer> Remote<P> for bool {
+    const SIZE: usize = 1;
+
+    const ALIGN: usize = 1;
+
+    fn decode<S>(bytes: &[u8], _source: &S) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized,
+    {
+        Ok(bytes[0] != 0)
+    }
+}
+
+impl<T: Remote<P>, P: Pointer, const N: usize> Remote<P> for [T; N] {
+    const SIZE: usize = T::SIZE * N;
+
+    const ALIGN: usize = T::ALIGN;
+
+    fn decode<S>(bytes: &[u8], source: &S) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized,
+    {
+        let elements = decode_slice::<T, P, S>(&bytes[..Self::SIZE], source)?;
+
+        Ok(elements.try_into().ok().expect("bad remote array length"))
+    }
+}
+
+/// Reads `len` bytes at `address` into a new buffer.
+pub(crate) fn read_buf<S>(
+    source: &S,
+    address: u64,
+    len: usize,
+) -> Result<Vec<u8>, RemoteError<S::Error>>
+where
+    S: MemorySource + ?Sized,
+{
+    let mut buf = Vec::new();
+
+    buf.try_reserve_exact(len)
+        .map_err(|_| RemoteError::TooLarge { len })?;
+    buf.resize(len, 0);
+
+    source
+        .read_bytes(address, &mut buf)
+        .map_err(RemoteError::Read)?;
+
+    Ok(buf)
+}
+
+/// Decodes consecutive values filling `bytes`.
+pub(crate) fn decode_slice<T, P, S>(
+    bytes: &[u8],
+    source: &S,
+) -> Result<Vec<T>, RemoteError<S::Error>>
+where
+    T: Remote<P>,
+    P: Pointer,
+    S: MemorySource + ?Sized,
+{
+    const { assert!(T::SIZE != 0, "remote values cannot be zero-sized") };
+
+    bytes
+        .chunks_exact(T::SIZE)
+        .map(|chunk| T::decode(chunk, source))
+        .collect()
+}
@@ -0,0 +1,169 @@
+//! Raw container layouts of a target address space with a given pointer width.
+//!
+//! Unlike [`crate::vec::RawVec`] and the string layouts, which are pinned to the
+//! pointer width of the host, these are generic over the [`Pointer`] type of the
+//! target, so that 32-bit containers can be decoded on a 64-bit host and vice versa.
+
+use crate::{
+    string::{validate_capacity, RawStringError},
+    vec::{validate_parts, RawVecError},
+};
+
+/// Size of the `std::basic_string` inline buffer in bytes.
+pub(crate) const BUF_BYTES: usize = 16;
+
+/// Trait for pointer sized integers of a target address space.
+pub trait Pointer: Copy + Eq + Into<u64> {
+    /// Size of a pointer in bytes.
+    const SIZE: usize;
+
+    /// Largest object size in bytes, equal to `PTRDIFF_MAX`.
+    const MAX: u64;
+
+    /// Decodes a little endian pointer at the start of `bytes`.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is shorter than [`Pointer::SIZE`].
+    fn from_le_slice(bytes: &[u8]) -> Self;
+}
+
+impl Pointer for u32 {
+    const SIZE: usize = 4;
+
+    const MAX: u64 = i32::MAX as u64;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        u32::from_le_bytes(bytes[..4].try_into().unwrap())
+    }
+}
+
+impl Pointer for u64 {
+    const SIZE: usize = 8;
+
+    const MAX: u64 = i64::MAX as u64;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        u64::from_le_bytes(bytes[..8].try_into().unwrap())
+    }
+}
+
+/// Pointer type of the host.
+#[cfg(target_pointer_width = "64")]
+pub type NativePtr = u64;
+
+/// Pointer type of the host.
+#[cfg(target_pointer_width = "32")]
+pub type NativePtr = u32;
+
+/// `std::vector` layout with pointers of type `P`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RawVecOf<P> {
+    pub first: P,
+    pub last: P,
+    pub end: P,
+}
+
+pub type RawVec32 = RawVecOf<u32>;
+
+pub type RawVec64 = RawVecOf<u64>;
+
+/// `std::basic_string` layout with pointers of type `P`.
+///
+/// The layout does not depend on the character type, `bx` holds either
+/// the inline buffer or the heap pointer.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RawStringOf<P> {
+    pub bx: [u8; BUF_BYTES],
+    pub size: P,
+    pub res: P,
+}
+
+pub type RawString32 = RawStringOf<u32>;
+
+pub type RawString64 = RawStringOf<u64>;
+
+impl<P: Pointer> RawVecOf<P> {
+    /// Size of the layout in the target address space.
+    pub const SIZE: usize = 3 * P::SIZE;
+
+    /// Decodes the layout at the start of `bytes`.
+    ///
+    /// Returns [`None`] if `bytes` is shorter than [`RawVecOf::SIZE`].
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..Self::SIZE)?;
+
+        Some(Self {
+            first: P::from_le_slice(bytes),
+            last: P::from_le_slice(&bytes[P::SIZE..]),
+            end: P::from_le_slice(&bytes[2 * P::SIZE..]),
+        })
+    }
+
+    /// Checks that the layout upholds the `std::vector` invariants
+    /// for elements of the given size and alignment.
+    pub fn validate(&self, size: usize, align: usize) -> Result<(), RawVecError> {
+        validate_parts(
+            self.first.into(),
+            self.last.into(),
+            self.end.into(),
+            size,
+            align,
+            P::MAX,
+        )
+    }
+
+    /// Length in bytes, assuming the layout is valid.
+    pub fn len_bytes(&self) -> u64 {
+        self.last.into() - self.first.into()
+    }
+
+    /// Capacity in bytes, assuming the layout is valid.
+    pub fn capacity_bytes(&self) -> u64 {
+        self.end.into() - self.first.into()
+    }
+}
+
+impl<P: Pointer> RawStringOf<P> {
+    /// Size of the layout in the target address space.
+    pub const SIZE: usize = BUF_BYTES + 2 * P::SIZE;
+
+    /// Decodes the layout at the start of `bytes`.
+    ///
+    /// Returns [`None`] if `bytes` is shorter than [`RawStringOf::SIZE`].
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..Self::SIZE)?;
+
+        Some(Self {
+            bx: bytes[..BUF_BYTES].try_into().unwrap(),
+            size: P::from_le_slice(&bytes[BUF_BYTES..]),
+            res: P::from_le_slice(&bytes[BUF_BYTES + P::SIZE..]),
+        })
+    }
+
+    /// Heap pointer, only meaningful in heap mode.
+    pub fn ptr(&self) -> P {
+        P::from_le_slice(&self.bx)
+    }
+
+    /// Checks the length and capacity of the layout for characters of
+    /// `char_size` bytes, returning whether it is in heap mode.
+    ///
+    /// The heap allocation, if any, is not checked.
+    /// The capacity may not exceed `max_size()` of the target, nor what fits
+    /// in local memory.
+    pub fn validate_capacity(&self, char_size: usize) -> Result<bool, RawStringError> {
+        let size_max = u64::MAX >> (64 - 8 * P::SIZE);
+        let max = P::MAX.min(size_max / char_size as u64 - 1);
+        let max =
+            usize::try_from(max).map_or(isize::MAX as usize, |max| max.min(isize::MAX as usize));
+
+        // Values that do not fit in a `usize` saturate and fail the checks.
+        let size = usize::try_from(self.size.into()).unwrap_or(usize::MAX);
+        let capacity = usize::try_from(self.res.into()).unwrap_or(usize::MAX);
+
+        validate_capacity(size, capacity, BUF_BYTES / char_size - 1, max)
+    }
+}
@@ -0,0 +1,333 @@
+//! Snapshots of `std::basic_string` values in another address space.
+
+use core::{marker::PhantomData, mem, ops::Deref};
+
+use crate::string::RawStringError;
+
+use super::{
+    decode_slice,
+    raw::{NativePtr, Pointer, RawStringOf, BUF_BYTES},
+    read_buf, MemorySource, Remote, RemoteError,
+};
+
+use rust_alloc::{string::String, vec::Vec};
+
+/// Snapshot of a `std::basic_string<C>` in another address space with pointers of type `P`.
+///
+/// Assumes a stateless allocator, which takes up no space.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteStringLayout<C, P> {
+    address: Option<u64>,
+    capacity: usize,
+    chars: Vec<C>,
+    _marker: PhantomData<P>,
+}
+
+/// Snapshot of a `std::basic_string<C>` in an address space with the pointer width of the host.
+pub type RemoteString<C> = RemoteStringLayout<C, NativePtr>;
+
+pub type RemoteNarrowString = RemoteString<u8>;
+
+pub type RemoteWideString = RemoteString<u16>;
+
+pub type RemoteUtf8String = RemoteString<u8>;
+
+pub type RemoteUtf16String = RemoteString<u16>;
+
+pub type RemoteUtf32String = RemoteString<u32>;
+
+pub type RemoteNarrowString32 = RemoteStringLayout<u8, u32>;
+
+pub type RemoteWideString32 = RemoteStringLayout<u16, u32>;
+
+pub type RemoteUtf8String32 = RemoteStringLayout<u8, u32>;
+
+pub type RemoteUtf16String32 = RemoteStringLayout<u16, u32>;
+
+pub type RemoteUtf32String32 = RemoteStringLayout<u32, u32>;
+
+pub type RemoteNarrowString64 = RemoteStringLayout<u8, u64>;
+
+pub type RemoteWideString64 = RemoteStringLayout<u16, u64>;
+
+pub type RemoteUtf8String64 = RemoteStringLayout<u8, u64>;
+
+pub type RemoteUtf16String64 = RemoteStringLayout<u16, u64>;
+
+pub type RemoteUtf32String64 = RemoteStringLayout<u32, u64>;
+
+/// Trait for character types of remote strings.
+pub trait RemoteChar: Copy + Eq {
+    /// Capacity of the inline buffer, not counting the NUL terminator.
+    const SMALL: usize = BUF_BYTES / mem::size_of::<Self>() - 1;
+
+    const NUL: Self;
+}
+
+impl RemoteChar for u8 {
+    const NUL: Self = 0;
+}
+
+impl RemoteChar for u16 {
+    const NUL: Self = 0;
+}
+
+impl RemoteChar for u32 {
+    const NUL: Self = 0;
+}
+
+impl<C, P> RemoteStringLayout<C, P> {
+    /// Address of the heap allocation in the other address space,
+    /// or [`None`] if the string is stored inline.
+    pub fn heap_address(&self) -> Option<u64> {
+        self.address
+    }
+
+    pub fn is_inline(&self) -> bool {
+        self.address.is_none()
+    }
+
+    /// Capacity of the string in the other address space.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn as_slice(&self) -> &[C] {
+        &self.chars
+    }
+
+    pub fn into_vec(self) -> Vec<C> {
+        self.chars
+    }
+}
+
+impl<P> RemoteStringLayout<u8, P> {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.chars
+    }
+
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.chars).into_owned()
+    }
+}
+
+impl<P> RemoteStringLayout<u16, P> {
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.chars)
+    }
+}
+
+impl<P> RemoteStringLayout<u32, P> {
+    pub fn to_string_lossy(&self) -> String {
+        self.chars
+            .iter()
+            .map(|&c| char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+}
+
+impl<C: RemoteChar + Remote<P>, P: Pointer> Remote<P> for RemoteStringLayout<C, P> {
+    const SIZE: usize = RawStringOf::<P>::SIZE;
+
+    const ALIGN: usize = P::SIZE;
+
+    fn decode<S>(bytes: &[u8], source: &S) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized,
+    {
+        let raw = RawStringOf::<P>::from_bytes(bytes).expect("bad remote value size");
+
+        let heap = raw
+            .validate_capacity(mem::size_of::<C>())
+            .map_err(RemoteError::String)?;
+
+        let too_large = || RemoteError::TooLarge { len: usize::MAX };
+
+        let size = usize::try_from(raw.size.into()).map_err(|_| too_large())?;
+        let capacity = usize::try_from(raw.res.into()).map_err(|_| too_large())?;
+
+        let len = size
+            .checked_add(1)
+            .and_then(|len| len.checked_mul(<C as Remote<P>>::SIZE))
+            .ok_or_else(too_large)?;
+
+        let (address, mut chars) = if heap {
+            let address = raw.ptr().into();
+
+            if address == 0 {
+                return Err(RemoteError::String(RawStringError::NullPointer));
+            }
+
+            let align = <C as Remote<P>>::ALIGN;
+
+            if !address.is_multiple_of(align as u64) {
+                return Err(RemoteError::String(RawStringError::Misaligned { align }));
+            }
+
+            let chars = decode_slice::<C, P, S>(&read_buf(source, address, len)?, source)?;
+
+            (Some(address), chars)
+        } else {
+            (None, decode_slice::<C, P, S>(&bytes[..len], source)?)
+        };
+
+        if chars.pop() != Some(C::NUL) {
+            return Err(RemoteError::String(RawStringError::MissingTerminator));
+        }
+
+        Ok(Self {
+            address,
+            capacity,
+            chars,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<C, P> AsRef<[C]> for RemoteStringLayout<C, P> {
+    fn as_ref(&self) -> &[C] {
+        self.as_slice()
+    }
+}
+
+impl<C, P> Deref for RemoteStringLayout<C, P> {
+    type Target = [C];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote::{BufferSource, OutOfBounds};
+
+    const BASE: u64 = 0x1000;
+
+    /// Memory with a 64-bit string header at `BASE` and `heap` at `BASE + 0x20`.
+    fn memory64(bx: [u8; BUF_BYTES], size: u64, res: u64, heap: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&bx);
+        bytes.extend_from_slice(&size.to_le_bytes());
+        bytes.extend_from_slice(&res.to_le_bytes());
+        bytes.extend_from_slice(heap);
+
+        bytes
+    }
+
+    fn heap_bx(address: u64) -> [u8; BUF_BYTES] {
+        let mut bx = [0; BUF_BYTES];
+        bx[..8].copy_from_slice(&address.to_le_bytes());
+        bx
+    }
+
+    #[test]
+    fn decode_inline() {
+        let mut bx = [0; BUF_BYTES];
+        bx[..5].copy_from_slice(b"hello");
+
+        let memory = memory64(bx, 5, 15, &[]);
+        let source = BufferSource::new(BASE, &memory);
+        let string = RemoteNarrowString64::read(&source, BASE).unwrap();
+
+        assert!(string.is_inline());
+        assert_eq!(string.capacity(), 15);
+        assert_eq!(string.as_bytes(), b"hello");
+    }
+
+    #[test]
+    fn decode_heap() {
+        let memory = memory64(heap_bx(BASE + 0x20), 20, 31, b"a string on the heap\0");
+        let source = BufferSource::new(BASE, &memory);
+        let string = RemoteNarrowString64::read(&source, BASE).unwrap();
+
+        assert_eq!(string.heap_address(), Some(BASE + 0x20));
+        assert_eq!(string.capacity(), 31);
+        assert_eq!(string.as_bytes(), b"a string on the heap");
+    }
+
+    #[test]
+    fn decode_wide_32() {
+        let mut memory = Vec::new();
+        memory.extend_from_slice(&(BASE as u32 + 0x18).to_le_bytes());
+        memory.extend_from_slice(&[0; BUF_BYTES - 4]);
+        memory.extend_from_slice(&8u32.to_le_bytes());
+        memory.extend_from_slice(&15u32.to_le_bytes());
+
+        for c in "wide str\0".encode_utf16() {
+            memory.extend_from_slice(&c.to_le_bytes());
+        }
+
+        let source = BufferSource::new(BASE, &memory);
+        let string = RemoteWideString32::read(&source, BASE).unwrap();
+
+        assert_eq!(string.to_string_lossy(), "wide str");
+    }
+
+    #[test]
+    fn truncated_buffer() {
+        let memory = memory64(heap_bx(BASE + 0x20), 20, 31, b"a string");
+        let source = BufferSource::new(BASE, &memory);
+
+        assert_eq!(
+            RemoteNarrowString64::read(&source, BASE),
+            Err(RemoteError::Read(OutOfBounds {
+                address: BASE + 0x20,
+                len: 21
+            }))
+        );
+
+        assert_eq!(
+            RemoteNarrowString64::read(&source, BASE + 0x10),
+            Err(RemoteError::Read(OutOfBounds {
+                address: BASE + 0x10,
+                len: 32
+            }))
+        );
+    }
+
+    #[test]
+    fn oversized_header() {
+        let memory = memory64(heap_bx(BASE + 0x20), u64::MAX - 1, u64::MAX, &[]);
+        let source = BufferSource::new(BASE, &memory);
+
+        assert_eq!(
+            RemoteNarrowString64::read(&source, BASE),
+            Err(RemoteError::String(RawStringError::CapacityTooLarge {
+                capacity: usize::try_from(u64::MAX).unwrap_or(usize::MAX),
+                max: isize::MAX as usize,
+            }))
+        );
+
+        let mut memory = Vec::new();
+        memory.extend_from_slice(&[0; BUF_BYTES]);
+        memory.extend_from_slice(&u32::MAX.to_le_bytes());
+        memory.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let source = BufferSource::new(BASE, &memory);
+
+        assert_eq!(
+            RemoteUtf32String32::read(&source, BASE),
+            Err(RemoteError::String(RawStringError::CapacityTooLarge {
+                capacity: u32::MAX as usize,
+                max: (u32::MAX / 4 - 1) as usize,
+            }))
+        );
+    }
+
+    #[test]
+    fn size_exceeds_capacity() {
+        let memory = memory64(heap_bx(BASE + 0x20), 40, 31, &[]);
+        let source = BufferSource::new(BASE, &memory);
+
+        assert_eq!(
+            RemoteNarrowString64::read(&source, BASE),
+            Err(RemoteError::String(RawStringError::SizeExceedsCapacity {
+                size: 40,
+                capacity: 31
+            }))
+        );
+    }
+}
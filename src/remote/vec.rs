@@ -0,0 +1,209 @@
+//! Snapshots of `std::vector` values in another address space.
+
+use core::{marker::PhantomData, ops::Deref};
+
+use super::{
+    decode_slice,
+    raw::{NativePtr, Pointer, RawVecOf},
+    read_buf, MemorySource, Remote, RemoteError,
+};
+
+use rust_alloc::vec::Vec;
+
+/// Snapshot of a `std::vector<T>` in another address space with pointers of type `P`.
+///
+/// Assumes a stateless allocator, which takes up no space.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RemoteVecLayout<T, P> {
+    address: u64,
+    capacity: usize,
+    elements: Vec<T>,
+    _marker: PhantomData<P>,
+}
+
+/// Snapshot of a `std::vector<T>` in an address space with the pointer width of the host.
+pub type RemoteVec<T> = RemoteVecLayout<T, NativePtr>;
+
+/// Snapshot of a `std::vector<T>` in a 32-bit address space.
+pub type RemoteVec32<T> = RemoteVecLayout<T, u32>;
+
+/// Snapshot of a `std::vector<T>` in a 64-bit address space.
+pub type RemoteVec64<T> = RemoteVecLayout<T, u64>;
+
+impl<T, P> RemoteVecLayout<T, P> {
+    /// Address of the element storage in the other address space, or 0 if none.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Capacity of the vector in the other address space.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.elements
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.elements
+    }
+}
+
+impl<T: Remote<P>, P: Pointer> Remote<P> for RemoteVecLayout<T, P> {
+    const SIZE: usize = RawVecOf::<P>::SIZE;
+
+    const ALIGN: usize = P::SIZE;
+
+    fn decode<S>(bytes: &[u8], source: &S) -> Result<Self, RemoteError<S::Error>>
+    where
+        S: MemorySource + ?Sized,
+    {
+        const { assert!(T::SIZE != 0, "remote values cannot be zero-sized") };
+
+        let raw = RawVecOf::<P>::from_bytes(bytes).expect("bad remote value size");
+
+        raw.validate(T::SIZE, T::ALIGN).map_err(RemoteError::Vec)?;
+
+        let too_large = || RemoteError::TooLarge { len: usize::MAX };
+
+        let len = usize::try_from(raw.len_bytes()).map_err(|_| too_large())?;
+        let capacity =
+            usize::try_from(raw.capacity_bytes() / T::SIZE as u64).map_err(|_| too_large())?;
+
+        let elements = if len != 0 {
+            decode_slice::<T, P, S>(&read_buf(source, raw.first.into(), len)?, source)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            address: raw.first.into(),
+            capacity,
+            elements,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T, P> AsRef<[T]> for RemoteVecLayout<T, P> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, P> Deref for RemoteVecLayout<T, P> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T, P> From<RemoteVecLayout<T, P>> for Vec<T> {
+    fn from(value: RemoteVecLayout<T, P>) -> Self {
+        value.into_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        remote::{raw::RawVec32, BufferSource, RemoteNarrowString32},
+        vec::RawVecError,
+    };
+
+    const BASE: u32 = 0x1000;
+
+    fn header32(first: u32, last: u32, end: u32) -> Vec<u8> {
+        [first, last, end]
+            .iter()
+            .flat_map(|p| p.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn decode_32() {
+        let mut memory = header32(BASE + 12, BASE + 24, BASE + 28);
+        memory.extend([1u32, 2, 3, 0].iter().flat_map(|e| e.to_le_bytes()));
+
+        let source = BufferSource::new(BASE.into(), &memory);
+        let vec = RemoteVec32::<u32>::read(&source, BASE.into()).unwrap();
+
+        assert_eq!(vec.address(), u64::from(BASE + 12));
+        assert_eq!(vec.capacity(), 4);
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn decode_nested_32() {
+        let mut memory = header32(BASE + 12, BASE + 12 + 48, BASE + 12 + 48);
+
+        for s in [&b"one"[..], b"two"] {
+            let mut bx = [0; 16];
+            bx[..s.len()].copy_from_slice(s);
+
+            memory.extend_from_slice(&bx);
+            memory.extend_from_slice(&(s.len() as u32).to_le_bytes());
+            memory.extend_from_slice(&15u32.to_le_bytes());
+        }
+
+        let source = BufferSource::new(BASE.into(), &memory);
+        let vec = RemoteVec32::<RemoteNarrowString32>::read(&source, BASE.into()).unwrap();
+
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec[0].as_bytes(), b"one");
+        assert_eq!(vec[1].as_bytes(), b"two");
+    }
+
+    #[test]
+    fn decode_empty() {
+        let memory = header32(0, 0, 0);
+        let source = BufferSource::new(BASE.into(), &memory);
+        let vec = RemoteVec32::<u64>::read(&source, BASE.into()).unwrap();
+
+        assert_eq!(vec.address(), 0);
+        assert_eq!(vec.capacity(), 0);
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn reject_broken_headers() {
+        let cases = [
+            (header32(BASE, 0, BASE), RawVecError::NullMismatch),
+            (header32(BASE + 8, BASE, BASE + 8), RawVecError::Misordered),
+            (header32(0x10, 0x10, 0xFFFF_FFF0), RawVecError::Misordered),
+            (
+                header32(BASE + 2, BASE + 6, BASE + 6),
+                RawVecError::Misaligned { align: 4 },
+            ),
+            (
+                header32(BASE, BASE + 6, BASE + 8),
+                RawVecError::SizeNotMultiple { size: 4 },
+            ),
+        ];
+
+        for (memory, error) in cases {
+            let source = BufferSource::new(BASE.into(), &memory);
+
+            assert_eq!(
+                RemoteVec32::<u32>::read(&source, BASE.into()),
+                Err(RemoteError::Vec(error))
+            );
+        }
+    }
+
+    #[test]
+    fn raw_layout_from_short_bytes() {
+        assert_eq!(RawVec32::from_bytes(&[0; 11]), None);
+        assert_eq!(
+            RawVec32::from_bytes(&header32(1, 2, 3)),
+            Some(RawVec32 {
+                first: 1,
+                last: 2,
+                end: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! MSVC run-time type information.
+
+use core::{
+    alloc::Layout,
+    any::{self, TypeId},
+    cell::UnsafeCell,
+    ffi::{c_void, CStr},
+    hint, mem,
+    ops::{Deref, DerefMut},
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use rust_alloc::{alloc, collections::BTreeMap, format, string::String};
+
+/// `std::type_info` with a decorated name of `N` bytes, including the NUL terminator.
+///
+/// The undecorated name is computed and cached by the C++ runtime on demand,
+/// which is why it lives in an [`UnsafeCell`].
+#[repr(C)]
+pub(crate) struct TypeInfo<const N: usize> {
+    vftable: *const c_void,
+    undecorated_name: UnsafeCell<*const u8>,
+    decorated_name: [u8; N],
+}
+
+impl<const N: usize> TypeInfo<N> {
+    pub const fn new(decorated_name: &[u8; N]) -> Self {
+        assert!(
+            decorated_name[N - 1] == 0,
+            "decorated name is not NUL terminated"
+        );
+
+        Self {
+            vftable: ptr::null(),
+            undecorated_name: UnsafeCell::new(ptr::null()),
+            decorated_name: *decorated_name,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const c_void {
+        self as *const Self as *const c_void
+    }
+}
+
+unsafe impl<const N: usize> Sync for TypeInfo<N> {}
+
+/// Type information shared by all Rust closures.
+pub(crate) static RUST_CLOSURE: TypeInfo<26> = TypeInfo::new(b".?AVRustClosure@cxx_stl@@\0");
+
+/// Start of a `std::type_info` generated for a Rust type,
+/// followed by the decorated name.
+#[repr(C)]
+struct TypeInfoHeader {
+    vftable: *const c_void,
+    undecorated_name: UnsafeCell<*const u8>,
+}
+
+/// Addresses of the type information generated for Rust types.
+static RUST_TYPES: SpinLock<BTreeMap<TypeId, usize>> = SpinLock::new(BTreeMap::new());
+
+/// Minimal lock for [`RUST_TYPES`], which must also work without `std`.
+struct SpinLock<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>,
+}
+
+struct SpinGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+}
+
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+
+impl<T> SpinLock<T> {
+    const fn new(value: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    fn lock(&self) -> SpinGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+
+        SpinGuard { lock: self }
+    }
+}
+
+impl<T> Deref for SpinGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for SpinGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
+
+impl<T> Drop for SpinGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Returns the type information of the Rust type `T`, generating it on first use.
+///
+/// The decorated name is unique within the process,
+/// `.?AV<type name>_<index>@rust@cxx_stl@@`.
+pub(crate) fn type_info_of<T: ?Sized + 'static>() -> *const c_void {
+    let mut types = RUST_TYPES.lock();
+    let index = types.len();
+
+    *types.entry(TypeId::of::<T>()).or_insert_with(|| {
+        let ident: String = any::type_name::<T>()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        let name = format!(".?AV{ident}_{index}@rust@cxx_stl@@\0");
+        let header = Layout::new::<TypeInfoHeader>();
+        let (layout, offset) = header
+            .extend(Layout::array::<u8>(name.len()).unwrap())
+            .unwrap();
+
+        unsafe {
+            let info = alloc::alloc(layout);
+
+            if info.is_null() {
+                alloc::handle_alloc_error(layout);
+            }
+
+            info.cast::<TypeInfoHeader>().write(TypeInfoHeader {
+                vftable: ptr::null(),
+                undecorated_name: UnsafeCell::new(ptr::null()),
+            });
+
+            ptr::copy_nonoverlapping(name.as_ptr(), info.add(offset), name.len());
+            info as usize
+        }
+    }) as *const c_void
+}
+
+/// Returns the type information of the Rust type `T`
+/// if it was generated, without generating it.
+///
+/// No value can be of a type whose information does not exist yet.
+pub(crate) fn find_type_info<T: ?Sized + 'static>() -> Option<*const c_void> {
+    RUST_TYPES
+        .lock()
+        .get(&TypeId::of::<T>())
+        .map(|&info| info as *const c_void)
+}
+
+/// Returns the decorated name of the type information `info`.
+///
+/// # Safety
+///
+/// `info` must point to a `std::type_info`.
+pub(crate) unsafe fn decorated_name<'a>(info: *const c_void) -> &'a CStr {
+    unsafe {
+        let name = info
+            .cast::<u8>()
+            .add(mem::size_of::<TypeInfoHeader>())
+            .cast();
+
+        CStr::from_ptr(name)
+    }
+}
+
+/// Compares type information by decorated name, like `type_info::operator==`.
+///
+/// # Safety
+///
+/// `a` and `b` must point to `std::type_info`s.
+pub(crate) unsafe fn type_info_eq(a: *const c_void, b: *const c_void) -> bool {
+    ptr::eq(a, b) || unsafe { decorated_name(a) == decorated_name(b) }
+}
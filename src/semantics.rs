@@ -2,13 +2,22 @@
 //!
 //! They provide the "glue" for CSTL in the form of drop, copy and move function tables.
 
-use std::{
+use core::{
+    ffi::c_void,
     mem::{self, MaybeUninit},
     ptr::{self, NonNull},
 };
 
 use cstl_sys::{CSTL_CopyType, CSTL_DropType, CSTL_MoveType, CSTL_Type};
 
+type RawDropFn = unsafe extern "C" fn(*mut c_void, *mut c_void);
+
+type RawMoveFn = unsafe extern "C" fn(*mut c_void, *mut c_void, *mut c_void);
+
+type RawCopyFn = unsafe extern "C" fn(*const c_void, *const c_void, *mut c_void);
+
+type RawFillFn = unsafe extern "C" fn(*mut c_void, *mut c_void, *const c_void);
+
 /// Trait for sized types.
 ///
 /// Besides the size and alignment, also provides a [`CSTL_DropType`] table.
@@ -34,9 +43,18 @@ pub trait BaseType: Sized {
 
     /// CSTL destructible type table.
     const DROP: CSTL_DropType = CSTL_DropType {
-        drop: unsafe { Some(mem::transmute(Self::raw_drop as *const ())) },
+        drop: unsafe {
+            Some(mem::transmute::<*const (), RawDropFn>(
+                Self::raw_drop as *const (),
+            ))
+        },
     };
 
+    /// Drops the objects in the range `[first, last)`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be a valid range of initialized objects.
     unsafe extern "C" fn raw_drop(first: NonNull<Self>, last: NonNull<Self>) {
         unsafe {
             let len = last
@@ -61,9 +79,19 @@ pub trait MoveType: Default + Sized {
     /// CSTL movable type table.
     const MOVE: CSTL_MoveType = CSTL_MoveType {
         drop_type: <Self as BaseType>::DROP,
-        move_: unsafe { Some(mem::transmute(Self::raw_move as *const ())) },
+        move_: unsafe {
+            Some(mem::transmute::<*const (), RawMoveFn>(
+                Self::raw_move as *const (),
+            ))
+        },
     };
 
+    /// Moves the objects in the range `[first, last)` to `dest`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be a valid range of initialized objects
+    /// and `dest` must be valid for as many writes.
     unsafe extern "C" fn raw_move(first: NonNull<Self>, last: NonNull<Self>, dest: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -84,10 +112,24 @@ pub trait CopyMoveType: Clone + Default + Sized {
     /// CSTL copyable type table.
     const COPY: CSTL_CopyType = CSTL_CopyType {
         move_type: <Self as MoveType>::MOVE,
-        copy: unsafe { Some(mem::transmute(Self::raw_copy as *const ())) },
-        fill: unsafe { Some(mem::transmute(Self::raw_fill as *const ())) },
+        copy: unsafe {
+            Some(mem::transmute::<*const (), RawCopyFn>(
+                Self::raw_copy as *const (),
+            ))
+        },
+        fill: unsafe {
+            Some(mem::transmute::<*const (), RawFillFn>(
+                Self::raw_fill as *const (),
+            ))
+        },
     };
 
+    /// Copies the objects in the range `[first, last)` to `dest`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be a valid range of initialized objects
+    /// and `dest` must be valid for as many writes.
     unsafe extern "C" fn raw_copy(first: NonNull<Self>, last: NonNull<Self>, dest: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -96,6 +138,11 @@ pub trait CopyMoveType: Clone + Default + Sized {
         }
     }
 
+    /// Fills the range `[first, last)` with copies of `value`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be valid for writes and `value` must be initialized.
     unsafe extern "C" fn raw_fill(first: NonNull<Self>, last: NonNull<Self>, value: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -121,12 +168,30 @@ pub trait CopyOnlyType: Clone + Sized {
     const COPY: CSTL_CopyType = CSTL_CopyType {
         move_type: CSTL_MoveType {
             drop_type: <Self as BaseType>::DROP,
-            move_: unsafe { Some(mem::transmute(Self::raw_move as *const ())) },
+            move_: unsafe {
+                Some(mem::transmute::<*const (), RawMoveFn>(
+                    Self::raw_move as *const (),
+                ))
+            },
+        },
+        copy: unsafe {
+            Some(mem::transmute::<*const (), RawCopyFn>(
+                Self::raw_copy as *const (),
+            ))
+        },
+        fill: unsafe {
+            Some(mem::transmute::<*const (), RawFillFn>(
+                Self::raw_fill as *const (),
+            ))
         },
-        copy: unsafe { Some(mem::transmute(Self::raw_copy as *const ())) },
-        fill: unsafe { Some(mem::transmute(Self::raw_fill as *const ())) },
     };
 
+    /// Moves the objects in the range `[first, last)` to `dest`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be a valid range of initialized objects
+    /// and `dest` must be valid for as many writes.
     unsafe extern "C" fn raw_move(first: NonNull<Self>, last: NonNull<Self>, dest: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -135,6 +200,12 @@ pub trait CopyOnlyType: Clone + Sized {
         }
     }
 
+    /// Copies the objects in the range `[first, last)` to `dest`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be a valid range of initialized objects
+    /// and `dest` must be valid for as many writes.
     unsafe extern "C" fn raw_copy(first: NonNull<Self>, last: NonNull<Self>, dest: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -143,6 +214,11 @@ pub trait CopyOnlyType: Clone + Sized {
         }
     }
 
+    /// Fills the range `[first, last)` with copies of `value`.
+    ///
+    /// # Safety
+    ///
+    /// `[first, last)` must be valid for writes and `value` must be initialized.
     unsafe extern "C" fn raw_fill(first: NonNull<Self>, last: NonNull<Self>, value: NonNull<Self>) {
         unsafe {
             for i in 0..last.offset_from(first) {
@@ -0,0 +1,563 @@
+//! `std::span` with the MSVC layout.
+//!
+//! Spans with a dynamic extent are a pointer followed by a length, spans with
+//! a fixed extent are just a pointer. All of them are [`Copy`] and can be
+//! passed by value to C++ functions that take a `std::span`.
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::{
+    alloc::{CxxProxy, WithCxxProxy},
+    string::{
+        narrow::{CxxNarrowStringLayout, RawString},
+        utf16::{CxxUtf16StringLayout, RawUtf16String},
+        utf32::{CxxUtf32StringLayout, RawUtf32String},
+        utf8::{CxxUtf8StringLayout, RawUtf8String},
+        wide::{CxxWideStringLayout, RawWideString},
+    },
+    vec::{CxxVecLayout, RawVec},
+};
+
+/// `std::span<const T>`.
+#[repr(C)]
+pub struct CxxSpan<'a, T> {
+    ptr: *const T,
+    len: usize,
+    _marker: PhantomData<&'a [T]>,
+}
+
+/// `std::span<T>`.
+#[repr(C)]
+pub struct CxxSpanMut<'a, T> {
+    ptr: *mut T,
+    len: usize,
+    _marker: PhantomData<&'a mut [T]>,
+}
+
+/// `std::span<const T, N>`.
+#[repr(C)]
+pub struct CxxFixedSpan<'a, T, const N: usize> {
+    ptr: *const T,
+    _marker: PhantomData<&'a [T; N]>,
+}
+
+/// `std::span<T, N>`.
+#[repr(C)]
+pub struct CxxFixedSpanMut<'a, T, const N: usize> {
+    ptr: *mut T,
+    _marker: PhantomData<&'a mut [T; N]>,
+}
+
+impl<'a, T> CxxSpan<'a, T> {
+    /// Creates an empty span with a null pointer, like a default constructed `std::span`.
+    pub const fn new() -> Self {
+        Self {
+            ptr: ptr::null(),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn from_slice(slice: &'a [T]) -> Self {
+        Self {
+            ptr: slice.as_ptr(),
+            len: slice.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a span from a pointer and a length.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of `len` elements for the duration of `'a`,
+    /// as with [`slice::from_raw_parts`], except that it may be null if `len` is 0.
+    pub const unsafe fn from_raw_parts(ptr: *const T, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &'a [T] {
+        match NonNull::new(self.ptr as *mut T) {
+            Some(ptr) => unsafe { slice::from_raw_parts(ptr.as_ptr(), self.len) },
+            None => &[],
+        }
+    }
+}
+
+impl<'a, T> CxxSpanMut<'a, T> {
+    /// Creates an empty span with a null pointer, like a default constructed `std::span`.
+    pub const fn new() -> Self {
+        Self {
+            ptr: ptr::null_mut(),
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn from_slice(slice: &'a mut [T]) -> Self {
+        Self {
+            ptr: slice.as_mut_ptr(),
+            len: slice.len(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a span from a pointer and a length.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads and writes of `len` elements for the duration
+    /// of `'a`, as with [`slice::from_raw_parts_mut`], except that it may be null
+    /// if `len` is 0.
+    pub const unsafe fn from_raw_parts(ptr: *mut T, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match NonNull::new(self.ptr) {
+            Some(ptr) => unsafe { slice::from_raw_parts(ptr.as_ptr(), self.len) },
+            None => &[],
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match NonNull::new(self.ptr) {
+            Some(ptr) => unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), self.len) },
+            None => &mut [],
+        }
+    }
+
+    pub fn into_slice(self) -> &'a mut [T] {
+        match NonNull::new(self.ptr) {
+            Some(ptr) => unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), self.len) },
+            None => &mut [],
+        }
+    }
+
+    /// Reborrows the span as a shared span.
+    pub fn as_span(&self) -> CxxSpan<'_, T> {
+        CxxSpan {
+            ptr: self.ptr,
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, const N: usize> CxxFixedSpan<'a, T, N> {
+    pub const fn from_array(array: &'a [T; N]) -> Self {
+        Self {
+            ptr: array.as_ptr(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a span from a pointer.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads of `N` elements for the duration of `'a`.
+    pub const unsafe fn from_ptr(ptr: *const T) -> Self {
+        Self {
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    pub fn as_array(&self) -> &'a [T; N] {
+        unsafe { &*self.ptr.cast::<[T; N]>() }
+    }
+}
+
+impl<'a, T, const N: usize> CxxFixedSpanMut<'a, T, N> {
+    pub fn from_array(array: &'a mut [T; N]) -> Self {
+        Self {
+            ptr: array.as_mut_ptr(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Creates a span from a pointer.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for reads and writes of `N` elements for the duration of `'a`.
+    pub const unsafe fn from_ptr(ptr: *mut T) -> Self {
+        Self {
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    pub const fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    pub fn as_array(&self) -> &[T; N] {
+        unsafe { &*self.ptr.cast::<[T; N]>() }
+    }
+
+    pub fn as_mut_array(&mut self) -> &mut [T; N] {
+        unsafe { &mut *self.ptr.cast::<[T; N]>() }
+    }
+
+    pub fn into_array(self) -> &'a mut [T; N] {
+        unsafe { &mut *self.ptr.cast::<[T; N]>() }
+    }
+}
+
+impl<T> Clone for CxxSpan<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for CxxSpan<'_, T> {}
+
+impl<T, const N: usize> Clone for CxxFixedSpan<'_, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, const N: usize> Copy for CxxFixedSpan<'_, T, N> {}
+
+impl<T: fmt::Debug> fmt::Debug for CxxSpan<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for CxxSpanMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_slice(), f)
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for CxxFixedSpan<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_array(), f)
+    }
+}
+
+impl<T: fmt::Debug, const N: usize> fmt::Debug for CxxFixedSpanMut<'_, T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_array(), f)
+    }
+}
+
+impl<T> Default for CxxSpan<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Default for CxxSpanMut<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Deref for CxxSpan<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> Deref for CxxSpanMut<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+
+impl<T> DerefMut for CxxSpanMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, const N: usize> Deref for CxxFixedSpan<'_, T, N> {
+    type Target = [T; N];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_array()
+    }
+}
+
+impl<T, const N: usize> Deref for CxxFixedSpanMut<'_, T, N> {
+    type Target = [T; N];
+
+    fn deref(&self) -> &Self::Target {
+        self.as_array()
+    }
+}
+
+impl<T, const N: usize> DerefMut for CxxFixedSpanMut<'_, T, N> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.as_mut_array()
+    }
+}
+
+impl<'a, T> From<&'a [T]> for CxxSpan<'a, T> {
+    fn from(value: &'a [T]) -> Self {
+        Self::from_slice(value)
+    }
+}
+
+impl<'a, T> From<&'a mut [T]> for CxxSpan<'a, T> {
+    fn from(value: &'a mut [T]) -> Self {
+        Self::from_slice(value)
+    }
+}
+
+impl<'a, T, const N: usize> From<&'a [T; N]> for CxxSpan<'a, T> {
+    fn from(value: &'a [T; N]) -> Self {
+        Self::from_slice(value)
+    }
+}
+
+impl<'a, T> From<&'a mut [T]> for CxxSpanMut<'a, T> {
+    fn from(value: &'a mut [T]) -> Self {
+        Self::from_slice(value)
+    }
+}
+
+impl<'a, T, const N: usize> From<&'a mut [T; N]> for CxxSpanMut<'a, T> {
+    fn from(value: &'a mut [T; N]) -> Self {
+        Self::from_slice(value)
+    }
+}
+
+impl<'a, T> From<CxxSpanMut<'a, T>> for CxxSpan<'a, T> {
+    fn from(value: CxxSpanMut<'a, T>) -> Self {
+        Self::from_slice(value.into_slice())
+    }
+}
+
+impl<'a, T, const N: usize> From<CxxFixedSpan<'a, T, N>> for CxxSpan<'a, T> {
+    fn from(value: CxxFixedSpan<'a, T, N>) -> Self {
+        Self::from_slice(value.as_array())
+    }
+}
+
+impl<'a, T, const N: usize> From<CxxFixedSpanMut<'a, T, N>> for CxxSpanMut<'a, T> {
+    fn from(value: CxxFixedSpanMut<'a, T, N>) -> Self {
+        Self::from_slice(value.into_array())
+    }
+}
+
+impl<'a, T, const N: usize> From<&'a [T; N]> for CxxFixedSpan<'a, T, N> {
+    fn from(value: &'a [T; N]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl<'a, T, const N: usize> From<&'a mut [T; N]> for CxxFixedSpanMut<'a, T, N> {
+    fn from(value: &'a mut [T; N]) -> Self {
+        Self::from_array(value)
+    }
+}
+
+impl<'a, T, const N: usize> TryFrom<&'a [T]> for CxxFixedSpan<'a, T, N> {
+    type Error = <&'a [T; N] as TryFrom<&'a [T]>>::Error;
+
+    fn try_from(value: &'a [T]) -> Result<Self, Self::Error> {
+        value.try_into().map(Self::from_array)
+    }
+}
+
+impl<'a, T, const N: usize> TryFrom<&'a mut [T]> for CxxFixedSpanMut<'a, T, N> {
+    type Error = <&'a mut [T; N] as TryFrom<&'a mut [T]>>::Error;
+
+    fn try_from(value: &'a mut [T]) -> Result<Self, Self::Error> {
+        value.try_into().map(Self::from_array)
+    }
+}
+
+impl<'a, T, const N: usize> TryFrom<CxxSpan<'a, T>> for CxxFixedSpan<'a, T, N> {
+    type Error = <&'a [T; N] as TryFrom<&'a [T]>>::Error;
+
+    fn try_from(value: CxxSpan<'a, T>) -> Result<Self, Self::Error> {
+        value.as_slice().try_into()
+    }
+}
+
+impl<'a, T, A, L> From<&'a CxxVecLayout<T, A, L>> for CxxSpan<'a, T>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from(value: &'a CxxVecLayout<T, A, L>) -> Self {
+        Self::from_slice(value.as_slice())
+    }
+}
+
+impl<'a, T, A, L> From<&'a mut CxxVecLayout<T, A, L>> for CxxSpanMut<'a, T>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from(value: &'a mut CxxVecLayout<T, A, L>) -> Self {
+        Self::from_slice(value.as_mut_slice())
+    }
+}
+
+macro_rules! impl_from_string {
+    ($($string:ident $raw:ident $ch:ty),*) => {$(
+        impl<'a, A, L> From<&'a $string<A, L>> for CxxSpan<'a, $ch>
+        where
+            A: CxxProxy,
+            L: WithCxxProxy<Alloc = A, Value = $raw>,
+        {
+            fn from(value: &'a $string<A, L>) -> Self {
+                Self::from_slice(value.as_bytes())
+            }
+        }
+    )*};
+}
+
+impl_from_string!(
+    CxxNarrowStringLayout RawString u8,
+    CxxWideStringLayout RawWideString u16,
+    CxxUtf8StringLayout RawUtf8String u8,
+    CxxUtf16StringLayout RawUtf16String u16,
+    CxxUtf32StringLayout RawUtf32String u32
+);
+
+unsafe impl<T: Sync> Send for CxxSpan<'_, T> {}
+
+unsafe impl<T: Sync> Sync for CxxSpan<'_, T> {}
+
+unsafe impl<T: Send> Send for CxxSpanMut<'_, T> {}
+
+unsafe impl<T: Sync> Sync for CxxSpanMut<'_, T> {}
+
+unsafe impl<T: Sync, const N: usize> Send for CxxFixedSpan<'_, T, N> {}
+
+unsafe impl<T: Sync, const N: usize> Sync for CxxFixedSpan<'_, T, N> {}
+
+unsafe impl<T: Send, const N: usize> Send for CxxFixedSpanMut<'_, T, N> {}
+
+unsafe impl<T: Sync, const N: usize> Sync for CxxFixedSpanMut<'_, T, N> {}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{offset_of, size_of};
+
+    use super::*;
+    use crate::{alloc::DEFAULT_ALLOC, string::CxxNarrowString, vec::CxxVec};
+
+    #[test]
+    fn dynamic_extent_is_pointer_and_length() {
+        assert_eq!(size_of::<CxxSpan<u8>>(), 2 * size_of::<usize>());
+        assert_eq!(size_of::<CxxSpanMut<u64>>(), 2 * size_of::<usize>());
+        assert_eq!(offset_of!(CxxSpan<u8>, len), size_of::<usize>());
+    }
+
+    #[test]
+    fn static_extent_is_pointer() {
+        assert_eq!(size_of::<CxxFixedSpan<u8, 4>>(), size_of::<usize>());
+        assert_eq!(size_of::<CxxFixedSpanMut<u64, 0>>(), size_of::<usize>());
+    }
+
+    #[test]
+    fn empty_spans_are_null() {
+        let span = CxxSpan::<u32>::new();
+
+        assert!(span.as_ptr().is_null());
+        assert!(span.is_empty());
+        assert_eq!(span.as_slice(), []);
+        assert_eq!(CxxSpanMut::<u32>::default().as_mut_slice(), []);
+    }
+
+    #[test]
+    fn converts_slices() {
+        let mut array = [1, 2, 3];
+
+        let span = CxxSpan::from(&array[..]);
+        assert_eq!(span.as_ptr(), array.as_ptr());
+        assert_eq!(span.len(), 3);
+        assert_eq!(*span, [1, 2, 3]);
+
+        let fixed = CxxFixedSpan::<_, 3>::try_from(span).unwrap();
+        assert_eq!(fixed.as_array(), &[1, 2, 3]);
+        assert!(CxxFixedSpan::<_, 2>::try_from(span).is_err());
+        assert_eq!(CxxSpan::from(fixed).as_slice(), [1, 2, 3]);
+
+        let mut span = CxxSpanMut::from(&mut array);
+        span[0] = 4;
+        assert_eq!(span.as_span().as_slice(), [4, 2, 3]);
+
+        let mut fixed = CxxFixedSpanMut::<_, 3>::try_from(span.into_slice()).unwrap();
+        fixed.as_mut_array()[2] = 6;
+        assert_eq!(CxxSpanMut::from(fixed).as_slice(), [4, 2, 6]);
+        assert_eq!(array, [4, 2, 6]);
+    }
+
+    #[test]
+    fn converts_containers() {
+        let mut vec = CxxVec::new();
+        vec.extend([1u16, 2, 3]);
+
+        assert_eq!(CxxSpan::from(&vec).as_slice(), [1, 2, 3]);
+        CxxSpanMut::from(&mut vec)[1] = 5;
+        assert_eq!(vec.as_slice(), [1, 5, 3]);
+
+        let string = CxxNarrowString::from_bytes_in("span", DEFAULT_ALLOC);
+        assert_eq!(CxxSpan::from(&string).as_slice(), b"span");
+    }
+}
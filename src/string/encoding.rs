@@ -0,0 +1,372 @@
+//! Code page transcoding of narrow and wide strings.
+//!
+//! A `std::string` is only bytes, which MSVC programs usually interpret in
+//! the ANSI code page of the system, like CP932 on Japanese systems. A
+//! `std::wstring` is UTF-16. Code pages follow the WHATWG encodings of
+//! `encoding_rs`, which are close to the Windows code pages they are named
+//! after but not identical:
+//!
+//! - 1252 decodes 0x81, 0x8D, 0x8F, 0x90 and 0x9D to the C1 controls of the same
+//!   value, which the Windows table leaves undefined.
+//! - 932 is the Shift_JIS of WHATWG. It includes the NEC and IBM extensions of
+//!   CP932, but rejects 0xA0 and 0xFD-0xFF, which Windows decodes to private use
+//!   characters, and encodes U+00A5 and U+203E as 0x5C and 0x7E.
+//! - 936 uses the GBK decoder of `encoding_rs`, which is the GB18030 decoder and
+//!   accepts four-byte sequences that Windows rejects.
+
+use core::fmt;
+
+use encoding_rs::{DecoderResult, EncoderResult, Encoding};
+use rust_alloc::{string::String, vec, vec::Vec};
+
+use crate::{
+    alloc::{CxxProxy, WithCxxProxy},
+    string::{
+        narrow::{CxxNarrowStringLayout, RawString},
+        wide::{CxxWideStringLayout, RawWideString},
+        CxxNarrowString, CxxWideString,
+    },
+};
+
+/// Windows code page identifier, as passed to `MultiByteToWideChar`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CodePage(pub u16);
+
+/// Error returned when a string cannot be transcoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncodingError {
+    /// The code page is not supported.
+    UnsupportedCodePage(CodePage),
+    /// The bytes at byte `offset` of a narrow string are not valid in the code page.
+    Malformed { offset: usize },
+    /// The character at `offset` of the text, in bytes or UTF-16 units,
+    /// cannot be represented in the code page.
+    Unmappable { offset: usize, ch: char },
+    /// The UTF-16 unit at `offset` of a wide string is an unpaired surrogate.
+    UnpairedSurrogate { offset: usize },
+}
+
+impl CodePage {
+    pub const IBM866: Self = Self(866);
+    pub const WINDOWS_874: Self = Self(874);
+    pub const SHIFT_JIS: Self = Self(932);
+    pub const GBK: Self = Self(936);
+    pub const EUC_KR: Self = Self(949);
+    pub const BIG5: Self = Self(950);
+    pub const WINDOWS_1250: Self = Self(1250);
+    pub const WINDOWS_1251: Self = Self(1251);
+    pub const WINDOWS_1252: Self = Self(1252);
+    pub const WINDOWS_1253: Self = Self(1253);
+    pub const WINDOWS_1254: Self = Self(1254);
+    pub const WINDOWS_1255: Self = Self(1255);
+    pub const WINDOWS_1256: Self = Self(1256);
+    pub const WINDOWS_1257: Self = Self(1257);
+    pub const WINDOWS_1258: Self = Self(1258);
+    pub const EUC_JP: Self = Self(20932);
+    pub const GB18030: Self = Self(54936);
+    pub const UTF8: Self = Self(65001);
+
+    fn encoding(self) -> Result<&'static Encoding, EncodingError> {
+        use encoding_rs as e;
+
+        Ok(match self.0 {
+            866 => e::IBM866,
+            874 => e::WINDOWS_874,
+            932 => e::SHIFT_JIS,
+            936 => e::GBK,
+            949 => e::EUC_KR,
+            950 => e::BIG5,
+            1250 => e::WINDOWS_1250,
+            1251 => e::WINDOWS_1251,
+            1252 => e::WINDOWS_1252,
+            1253 => e::WINDOWS_1253,
+            1254 => e::WINDOWS_1254,
+            1255 => e::WINDOWS_1255,
+            1256 => e::WINDOWS_1256,
+            1257 => e::WINDOWS_1257,
+            1258 => e::WINDOWS_1258,
+            10000 => e::MACINTOSH,
+            10007 => e::X_MAC_CYRILLIC,
+            20866 => e::KOI8_R,
+            20932 | 51932 => e::EUC_JP,
+            21866 => e::KOI8_U,
+            28592 => e::ISO_8859_2,
+            28593 => e::ISO_8859_3,
+            28594 => e::ISO_8859_4,
+            28595 => e::ISO_8859_5,
+            28596 => e::ISO_8859_6,
+            28597 => e::ISO_8859_7,
+            28598 => e::ISO_8859_8,
+            28603 => e::ISO_8859_13,
+            28605 => e::ISO_8859_15,
+            50220 => e::ISO_2022_JP,
+            54936 => e::GB18030,
+            65001 => e::UTF_8,
+            _ => return Err(EncodingError::UnsupportedCodePage(self)),
+        })
+    }
+}
+
+impl<A, L> CxxNarrowStringLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawString>,
+{
+    /// Encodes `s` in the code page.
+    pub fn encode_in(s: &str, code_page: CodePage, alloc: A) -> Result<Self, EncodingError> {
+        let mut encoder = code_page.encoding()?.new_encoder();
+        let len = encoder
+            .max_buffer_length_from_utf8_without_replacement(s.len())
+            .expect("capacity overflow");
+        let mut bytes = vec![0; len];
+
+        let (result, read, written) =
+            encoder.encode_from_utf8_without_replacement(s, &mut bytes, true);
+
+        match result {
+            EncoderResult::InputEmpty => Ok(Self::from_bytes_in(&bytes[..written], alloc)),
+            EncoderResult::Unmappable(ch) => Err(EncodingError::Unmappable {
+                offset: read - ch.len_utf8(),
+                ch,
+            }),
+            EncoderResult::OutputFull => unreachable!(),
+        }
+    }
+
+    /// Decodes the string from the code page.
+    pub fn decode(&self, code_page: CodePage) -> Result<String, EncodingError> {
+        let bytes = self.as_bytes();
+        let mut decoder = code_page.encoding()?.new_decoder_without_bom_handling();
+        let len = decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len())
+            .expect("capacity overflow");
+        let mut s = String::with_capacity(len);
+
+        let (result, read) = decoder.decode_to_string_without_replacement(bytes, &mut s, true);
+
+        match result {
+            DecoderResult::InputEmpty => Ok(s),
+            DecoderResult::Malformed(bad, extra) => Err(EncodingError::Malformed {
+                offset: read - bad as usize - extra as usize,
+            }),
+            DecoderResult::OutputFull => unreachable!(),
+        }
+    }
+
+    /// Decodes the string from the code page into a wide string.
+    pub fn to_wide_in<A2: CxxProxy>(
+        &self,
+        code_page: CodePage,
+        alloc: A2,
+    ) -> Result<CxxWideString<A2>, EncodingError> {
+        let bytes = self.as_bytes();
+        let mut decoder = code_page.encoding()?.new_decoder_without_bom_handling();
+        let len = decoder
+            .max_utf16_buffer_length(bytes.len())
+            .expect("capacity overflow");
+        let mut text = vec![0; len];
+
+        let (result, read, written) =
+            decoder.decode_to_utf16_without_replacement(bytes, &mut text, true);
+
+        match result {
+            DecoderResult::InputEmpty => Ok(CxxWideString::from_bytes_in(&text[..written], alloc)),
+            DecoderResult::Malformed(bad, extra) => Err(EncodingError::Malformed {
+                offset: read - bad as usize - extra as usize,
+            }),
+            DecoderResult::OutputFull => unreachable!(),
+        }
+    }
+}
+
+impl<A, L> CxxWideStringLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawWideString>,
+{
+    /// Encodes the string in the code page into a narrow string.
+    pub fn to_narrow_in<A2: CxxProxy>(
+        &self,
+        code_page: CodePage,
+        alloc: A2,
+    ) -> Result<CxxNarrowString<A2>, EncodingError> {
+        let text = self.as_bytes();
+
+        // Unpaired surrogates would be silently replaced with U+FFFD.
+        let mut offset = 0;
+
+        for ch in char::decode_utf16(text.iter().copied()) {
+            match ch {
+                Ok(ch) => offset += ch.len_utf16(),
+                Err(_) => return Err(EncodingError::UnpairedSurrogate { offset }),
+            }
+        }
+
+        let mut encoder = code_page.encoding()?.new_encoder();
+        let len = encoder
+            .max_buffer_length_from_utf16_without_replacement(text.len())
+            .expect("capacity overflow");
+        let mut bytes: Vec<u8> = vec![0; len];
+
+        let (result, read, written) =
+            encoder.encode_from_utf16_without_replacement(text, &mut bytes, true);
+
+        match result {
+            EncoderResult::InputEmpty => {
+                Ok(CxxNarrowString::from_bytes_in(&bytes[..written], alloc))
+            }
+            EncoderResult::Unmappable(ch) => Err(EncodingError::Unmappable {
+                offset: read - ch.len_utf16(),
+                ch,
+            }),
+            EncoderResult::OutputFull => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedCodePage(code_page) => {
+                write!(f, "code page {} is not supported", code_page.0)
+            }
+            Self::Malformed { offset } => {
+                write!(f, "invalid bytes at offset {offset} for the code page")
+            }
+            Self::Unmappable { offset, ch } => write!(
+                f,
+                "character {ch:?} at offset {offset} cannot be represented in the code page"
+            ),
+            Self::UnpairedSurrogate { offset } => {
+                write!(f, "unpaired surrogate at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for EncodingError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::DEFAULT_ALLOC;
+
+    const CP932_TEXT: &str = "日本語ｱ①ⅰ";
+    const CP932_BYTES: &[u8] = b"\x93\xFA\x96\x7B\x8C\xEA\xB1\x87\x40\xFA\x40";
+
+    const CP1252_TEXT: &str = "Café – “€5”";
+    const CP1252_BYTES: &[u8] = b"Caf\xE9 \x96 \x93\x805\x94";
+
+    fn narrow(bytes: &[u8]) -> CxxNarrowString {
+        CxxNarrowString::from_bytes_in(bytes, DEFAULT_ALLOC)
+    }
+
+    #[test]
+    fn cp932_round_trip() {
+        let string =
+            CxxNarrowString::encode_in(CP932_TEXT, CodePage::SHIFT_JIS, DEFAULT_ALLOC).unwrap();
+
+        assert_eq!(string.as_bytes(), CP932_BYTES);
+        assert_eq!(string.decode(CodePage::SHIFT_JIS).unwrap(), CP932_TEXT);
+    }
+
+    #[test]
+    fn cp932_errors() {
+        assert_eq!(
+            CxxNarrowString::encode_in("日本€", CodePage::SHIFT_JIS, DEFAULT_ALLOC).unwrap_err(),
+            EncodingError::Unmappable {
+                offset: 6, ch: '€'
+            }
+        );
+        assert_eq!(
+            narrow(b"a\x93\xFA\xA0").decode(CodePage::SHIFT_JIS),
+            Err(EncodingError::Malformed { offset: 3 })
+        );
+        assert_eq!(
+            narrow(b"\x93\xFA\x81 ")
+                .to_wide_in(CodePage::SHIFT_JIS, DEFAULT_ALLOC)
+                .unwrap_err(),
+            EncodingError::Malformed { offset: 2 }
+        );
+    }
+
+    #[test]
+    fn cp1252_round_trip() {
+        let string =
+            CxxNarrowString::encode_in(CP1252_TEXT, CodePage::WINDOWS_1252, DEFAULT_ALLOC).unwrap();
+
+        assert_eq!(string.as_bytes(), CP1252_BYTES);
+        assert_eq!(string.decode(CodePage::WINDOWS_1252).unwrap(), CP1252_TEXT);
+    }
+
+    #[test]
+    fn cp1252_errors() {
+        assert_eq!(
+            CxxNarrowString::encode_in("Straße: 日", CodePage::WINDOWS_1252, DEFAULT_ALLOC)
+                .unwrap_err(),
+            EncodingError::Unmappable {
+                offset: 9,
+                ch: '日'
+            }
+        );
+    }
+
+    #[test]
+    fn cp1252_decodes_undefined_bytes_to_controls() {
+        assert_eq!(
+            narrow(b"\x81\x8D\x8F\x90\x9D")
+                .decode(CodePage::WINDOWS_1252)
+                .unwrap(),
+            "\u{81}\u{8D}\u{8F}\u{90}\u{9D}"
+        );
+    }
+
+    #[test]
+    fn unsupported_code_pages_fail() {
+        assert_eq!(
+            narrow(b"a").decode(CodePage(1)),
+            Err(EncodingError::UnsupportedCodePage(CodePage(1)))
+        );
+    }
+
+    // `wchar_t` is only 16 bits wide on Windows.
+    #[cfg(windows)]
+    #[test]
+    fn wide_round_trip() {
+        for (code_page, text, bytes) in [
+            (CodePage::SHIFT_JIS, CP932_TEXT, CP932_BYTES),
+            (CodePage::WINDOWS_1252, CP1252_TEXT, CP1252_BYTES),
+        ] {
+            let wide = narrow(bytes).to_wide_in(code_page, DEFAULT_ALLOC).unwrap();
+
+            assert_eq!(wide.as_bytes(), text.encode_utf16().collect::<Vec<_>>());
+            assert_eq!(
+                wide.to_narrow_in(code_page, DEFAULT_ALLOC)
+                    .unwrap()
+                    .as_bytes(),
+                bytes
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn wide_errors() {
+        let wide = CxxWideString::from_bytes_in([0x43_u16, 0xD83D, 0xDE00], DEFAULT_ALLOC);
+        assert_eq!(
+            wide.to_narrow_in(CodePage::WINDOWS_1252, DEFAULT_ALLOC)
+                .unwrap_err(),
+            EncodingError::Unmappable {
+                offset: 1,
+                ch: '😀'
+            }
+        );
+
+        let wide = CxxWideString::from_bytes_in([0x43_u16, 0xDE00], DEFAULT_ALLOC);
+        assert_eq!(
+            wide.to_narrow_in(CodePage::WINDOWS_1252, DEFAULT_ALLOC)
+                .unwrap_err(),
+            EncodingError::UnpairedSurrogate { offset: 1 }
+        );
+    }
+}
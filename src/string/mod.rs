@@ -1,17 +1,24 @@
+use core::fmt;
+
 pub mod narrow;
-pub use narrow::CxxNarrowString;
+pub use narrow::{CxxNarrowString, CxxNarrowStringMut, CxxNarrowStringRef};
 
 pub mod wide;
-pub use wide::CxxWideString;
+pub use wide::{CxxWideString, CxxWideStringMut, CxxWideStringRef};
 
 pub mod utf8;
-pub use utf8::CxxUtf8String;
+pub use utf8::{CxxUtf8String, CxxUtf8StringMut, CxxUtf8StringRef};
 
 pub mod utf16;
-pub use utf16::CxxUtf16String;
+pub use utf16::{CxxUtf16String, CxxUtf16StringMut, CxxUtf16StringRef};
 
 pub mod utf32;
-pub use utf32::CxxUtf32String;
+pub use utf32::{CxxUtf32String, CxxUtf32StringMut, CxxUtf32StringRef};
+
+#[cfg(feature = "encoding")]
+pub mod encoding;
+#[cfg(feature = "encoding")]
+pub use encoding::{CodePage, EncodingError};
 
 #[cfg(feature = "msvc2012")]
 pub mod msvc2012 {
@@ -25,3 +32,96 @@ pub mod msvc2012 {
 
     pub use super::utf32::msvc2012::CxxUtf32String;
 }
+
+/// `std::pmr` strings.
+pub mod pmr {
+    use crate::pmr::PmrAlloc;
+
+    pub type CxxNarrowString<'a> = super::CxxNarrowString<PmrAlloc<'a>>;
+
+    pub type CxxWideString<'a> = super::CxxWideString<PmrAlloc<'a>>;
+
+    pub type CxxUtf8String<'a> = super::CxxUtf8String<PmrAlloc<'a>>;
+
+    pub type CxxUtf16String<'a> = super::CxxUtf16String<PmrAlloc<'a>>;
+
+    pub type CxxUtf32String<'a> = super::CxxUtf32String<PmrAlloc<'a>>;
+}
+
+/// Error returned when a raw `std::basic_string` value breaks the container invariants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawStringError {
+    /// The length exceeds the capacity.
+    SizeExceedsCapacity { size: usize, capacity: usize },
+    /// The capacity is smaller than that of the inline buffer.
+    CapacityBelowSmall { capacity: usize, small: usize },
+    /// The capacity exceeds the largest possible string length.
+    CapacityTooLarge { capacity: usize, max: usize },
+    /// The string is in heap mode, but its pointer is null.
+    NullPointer,
+    /// The string is in heap mode, but its pointer is not aligned to the character alignment.
+    Misaligned { align: usize },
+    /// The character past the end of the string is not a NUL terminator.
+    MissingTerminator,
+}
+
+impl fmt::Display for RawStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SizeExceedsCapacity { size, capacity } => {
+                write!(f, "string length {size} exceeds its capacity {capacity}")
+            }
+            Self::CapacityBelowSmall { capacity, small } => {
+                write!(
+                    f,
+                    "string capacity {capacity} is below the inline capacity {small}"
+                )
+            }
+            Self::CapacityTooLarge { capacity, max } => {
+                write!(f, "string capacity {capacity} exceeds the maximum {max}")
+            }
+            Self::NullPointer => f.write_str("string is in heap mode with a null pointer"),
+            Self::Misaligned { align } => {
+                write!(f, "string storage is not aligned to {align} bytes")
+            }
+            Self::MissingTerminator => f.write_str("string is not NUL terminated"),
+        }
+    }
+}
+
+impl core::error::Error for RawStringError {}
+
+/// Largest length of a string of `char_size` byte characters, `max_size()`.
+pub(crate) const fn max_size(char_size: usize) -> usize {
+    let alloc_max = usize::MAX / char_size - 1;
+
+    if alloc_max < isize::MAX as usize {
+        alloc_max
+    } else {
+        isize::MAX as usize
+    }
+}
+
+/// Checks the length and capacity of a string with an inline buffer
+/// of `small` characters and a maximum length of `max`, returning whether
+/// it is in heap mode.
+pub(crate) fn validate_capacity(
+    size: usize,
+    capacity: usize,
+    small: usize,
+    max: usize,
+) -> Result<bool, RawStringError> {
+    if capacity < small {
+        return Err(RawStringError::CapacityBelowSmall { capacity, small });
+    }
+
+    if capacity > max {
+        return Err(RawStringError::CapacityTooLarge { capacity, max });
+    }
+
+    if size > capacity {
+        return Err(RawStringError::SizeExceedsCapacity { size, capacity });
+    }
+
+    Ok(capacity > small)
+}
@@ -1,15 +1,26 @@
-use std::{alloc::System as SysAlloc, borrow::Borrow, fmt, slice};
+use core::{
+    borrow::Borrow,
+    fmt,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
 
 pub use cstl_sys::CSTL_StringVal as RawString;
 use cstl_sys::{
-    CSTL_string_append_char, CSTL_string_append_n, CSTL_string_assign_n,
-    CSTL_string_c_str, CSTL_string_clear, CSTL_string_destroy, CSTL_string_reserve,
-    CSTL_string_shrink_to_fit,
+    CSTL_string_append_char, CSTL_string_append_n, CSTL_string_assign_n, CSTL_string_c_str,
+    CSTL_string_clear, CSTL_string_copy_assign, CSTL_string_destroy, CSTL_string_move_assign,
+    CSTL_string_reserve, CSTL_string_shrink_to_fit, CSTL_string_swap,
 };
 
-use crate::alloc::{CxxProxy, WithCxxProxy};
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc, UsesAllocator, WithCxxProxy, DEFAULT_ALLOC},
+    string::{max_size, validate_capacity, RawStringError},
+};
 
-pub type CxxNarrowString<A = SysAlloc> = CxxNarrowStringLayout<A, Layout<A>>;
+pub type CxxNarrowString<A = DefaultAlloc> = CxxNarrowStringLayout<A, Layout<A>>;
 
 #[repr(C)]
 pub struct Layout<A: CxxProxy> {
@@ -35,10 +46,10 @@ impl<A: CxxProxy> Layout<A> {
     }
 }
 
-impl CxxNarrowString<SysAlloc> {
+impl CxxNarrowString<DefaultAlloc> {
     pub const fn new() -> Self {
         Self {
-            inner: Layout::new_in(SysAlloc),
+            inner: Layout::new_in(DEFAULT_ALLOC),
         }
     }
 }
@@ -72,6 +83,46 @@ where
         new
     }
 
+    /// Creates a string from a raw `std::basic_string` value and its allocator,
+    /// taking ownership of the backing allocation, if any.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must describe a valid string whose backing allocation
+    /// can be freed by `alloc`.
+    pub unsafe fn from_raw_parts_in(raw: RawString, alloc: A) -> Self {
+        let mut new = Self::from_alloc(alloc);
+        *new.inner.value_as_mut() = raw;
+        new
+    }
+
+    /// Checks `raw` with [`validate`] before creating a string from it
+    /// with [`CxxNarrowStringLayout::from_raw_parts_in`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxNarrowStringLayout::from_raw_parts_in`], except that
+    /// `raw` may break the container invariants. If `raw` is in heap mode
+    /// and its length does not exceed its capacity, its pointer must be
+    /// valid for reads of the length plus one characters.
+    pub unsafe fn try_from_raw_parts_in(raw: RawString, alloc: A) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&raw)?;
+            Ok(Self::from_raw_parts_in(raw, alloc))
+        }
+    }
+
+    /// Decomposes the string into a raw `std::basic_string` value and its allocator,
+    /// handing ownership of the backing allocation, if any, to the caller.
+    pub fn into_raw_parts(self) -> (RawString, A) {
+        let string = ManuallyDrop::new(self);
+
+        unsafe {
+            let alloc = ptr::read(string.inner.alloc_as_ref());
+            (*string.inner.value_as_ref(), alloc)
+        }
+    }
+
     pub fn as_ptr(&self) -> *const u8 {
         unsafe { CSTL_string_c_str(self.inner.value_as_ref()) as _ }
     }
@@ -133,11 +184,67 @@ where
         });
     }
 
+    /// Replaces the contents with those of `other`, like move assignment.
+    ///
+    /// Takes the storage of `other` if the allocator propagates or is equal
+    /// to the allocator of `other`, and copies the contents otherwise.
+    pub fn move_assign(&mut self, other: Self) {
+        if A::PROPAGATE_ON_MOVE_ASSIGNMENT {
+            *self = other;
+            return;
+        }
+
+        let mut other = other;
+        let take = self.alloc_eq(&other);
+
+        other.inner.with_proxy_mut(|other_val, other_alloc| {
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                CSTL_string_move_assign(val, alloc, other_val, other_alloc, take);
+            })
+        });
+    }
+
+    /// Swaps the contents with those of `other`, and the allocators if they propagate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocators do not propagate and are not equal,
+    /// which is undefined behavior in C++.
+    pub fn swap_with(&mut self, other: &mut Self) {
+        if A::PROPAGATE_ON_SWAP {
+            mem::swap(&mut self.inner, &mut other.inner);
+            return;
+        }
+
+        assert!(
+            self.alloc_eq(other),
+            "strings with unequal allocators cannot be swapped"
+        );
+
+        unsafe {
+            CSTL_string_swap(self.inner.value_as_mut(), other.inner.value_as_mut());
+        }
+    }
+
     fn from_alloc(alloc: A) -> Self {
         Self {
             inner: L::new_in(alloc),
         }
     }
+
+    fn alloc_eq(&self, other: &Self) -> bool {
+        A::IS_ALWAYS_EQUAL
+            || self
+                .inner
+                .alloc_as_ref()
+                .is_equal(other.inner.alloc_as_ref())
+    }
+
+    /// Replaces the allocator, keeping the storage.
+    fn replace_alloc(&mut self, alloc: A) {
+        let old = mem::replace(&mut self.inner, L::new_in(alloc));
+        *self.inner.value_as_mut() = *old.value_as_ref();
+    }
 }
 
 impl<A, L> fmt::Debug for CxxNarrowStringLayout<A, L>
@@ -204,6 +311,38 @@ where
     fn clone(&self) -> Self {
         Self::from_bytes_in(self, self.inner.alloc_as_ref().clone())
     }
+
+    /// Copies the contents of `source`, and the allocator if it propagates.
+    ///
+    /// Storage is freed first if the allocators are not equal.
+    fn clone_from(&mut self, source: &Self) {
+        let propagate = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+        let realloc = propagate && !self.alloc_eq(source);
+
+        source.inner.with_proxy(|other_val, other_alloc| {
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                CSTL_string_copy_assign(val, alloc, other_val, other_alloc, realloc);
+            })
+        });
+
+        if propagate {
+            self.replace_alloc(source.inner.alloc_as_ref().clone());
+        }
+    }
+}
+
+impl<A, L> UsesAllocator<A> for CxxNarrowStringLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawString>,
+{
+    fn new_with(alloc: A) -> Self {
+        Self::from_alloc(alloc)
+    }
+
+    fn clone_with(&self, alloc: A) -> Self {
+        Self::from_bytes_in(self, alloc)
+    }
 }
 
 impl<A, L> Extend<u8> for CxxNarrowStringLayout<A, L>
@@ -222,6 +361,188 @@ where
     }
 }
 
+/// Shared view over a foreign string.
+///
+/// The backing allocation is never freed.
+pub struct CxxNarrowStringRef<'a, A: CxxProxy = DefaultAlloc> {
+    string: ManuallyDrop<CxxNarrowString<A>>,
+    _marker: PhantomData<&'a RawString>,
+}
+
+/// Mutable view over a foreign string.
+///
+/// Modifications go through `alloc` and are written back to the foreign
+/// string when the view is dropped. The foreign string keeps ownership
+/// of the backing allocation.
+pub struct CxxNarrowStringMut<'a, A: CxxProxy = DefaultAlloc> {
+    raw: NonNull<RawString>,
+    string: ManuallyDrop<CxxNarrowString<A>>,
+    _marker: PhantomData<&'a mut RawString>,
+}
+
+impl<'a, A: CxxProxy> CxxNarrowStringRef<'a, A> {
+    /// Creates a shared view over the string pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid string that is not mutated for the
+    /// duration of `'a`, and `alloc` must be compatible with the allocator
+    /// of that string.
+    pub unsafe fn from_raw_in(raw: *const RawString, alloc: A) -> Self {
+        unsafe {
+            Self {
+                string: ManuallyDrop::new(CxxNarrowString::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the string pointed to by `raw` with [`validate`] before
+    /// creating a shared view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxNarrowStringRef::from_raw_in`] and [`validate`],
+    /// except that the string may break the container invariants.
+    pub unsafe fn try_from_raw_in(raw: *const RawString, alloc: A) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<'a, A: CxxProxy> CxxNarrowStringMut<'a, A> {
+    /// Creates a mutable view over the string pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid string that is not accessed through
+    /// any other pointer for the duration of `'a`, and `alloc` must be able
+    /// to free and reallocate the backing allocation of that string.
+    ///
+    /// Leaking the view leaves the foreign string in the state it was in
+    /// when the view was created, which may refer to freed memory.
+    pub unsafe fn from_raw_in(raw: *mut RawString, alloc: A) -> Self {
+        unsafe {
+            let raw = NonNull::new(raw).expect("`raw` is null");
+
+            Self {
+                raw,
+                string: ManuallyDrop::new(CxxNarrowString::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the string pointed to by `raw` with [`validate`] before
+    /// creating a mutable view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxNarrowStringMut::from_raw_in`] and [`validate`],
+    /// except that the string may break the container invariants.
+    pub unsafe fn try_from_raw_in(raw: *mut RawString, alloc: A) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<A: CxxProxy> Deref for CxxNarrowStringRef<'_, A> {
+    type Target = CxxNarrowString<A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+impl<A: CxxProxy> Deref for CxxNarrowStringMut<'_, A> {
+    type Target = CxxNarrowString<A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+impl<A: CxxProxy> DerefMut for CxxNarrowStringMut<'_, A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.string
+    }
+}
+
+impl<A: CxxProxy> fmt::Debug for CxxNarrowStringRef<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<A: CxxProxy> fmt::Debug for CxxNarrowStringMut<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<A: CxxProxy> Drop for CxxNarrowStringRef<'_, A> {
+    fn drop(&mut self) {
+        let (_, alloc) = unsafe { ManuallyDrop::take(&mut self.string).into_raw_parts() };
+        drop(alloc);
+    }
+}
+
+impl<A: CxxProxy> Drop for CxxNarrowStringMut<'_, A> {
+    fn drop(&mut self) {
+        let (val, alloc) = unsafe { ManuallyDrop::take(&mut self.string).into_raw_parts() };
+
+        unsafe {
+            self.raw.write(val);
+        }
+
+        drop(alloc);
+    }
+}
+
+/// Checks that `raw` upholds the `std::basic_string` invariants.
+///
+/// In inline mode the capacity is equal to that of the inline buffer,
+/// in heap mode it is larger and the string points to a separate allocation.
+///
+/// # Safety
+///
+/// If `raw` is in heap mode and its length does not exceed its capacity,
+/// its pointer must be valid for reads of the length plus one characters.
+pub unsafe fn validate(raw: &RawString) -> Result<(), RawStringError> {
+    const SMALL: usize = new_val().res;
+    const MAX: usize = max_size(mem::size_of::<u8>());
+
+    let size = raw.size;
+
+    let ptr = if validate_capacity(size, raw.res, SMALL, MAX)? {
+        let ptr = unsafe { raw.bx.ptr as *const u8 };
+
+        if ptr.is_null() {
+            return Err(RawStringError::NullPointer);
+        }
+
+        if !ptr.is_aligned() {
+            return Err(RawStringError::Misaligned {
+                align: mem::align_of::<u8>(),
+            });
+        }
+
+        ptr
+    } else {
+        unsafe { raw.bx.buf.as_ptr().cast::<u8>() }
+    };
+
+    if unsafe { ptr.add(size).read() } != 0 {
+        return Err(RawStringError::MissingTerminator);
+    }
+
+    Ok(())
+}
+
 const fn new_val() -> RawString {
     RawString {
         bx: cstl_sys::CSTL_StringUnion { buf: [0; 16] },
@@ -260,9 +581,9 @@ pub mod msvc2012 {
 
     use crate::alloc::{CxxProxy, WithCxxProxy};
 
-    use super::{new_val, CxxNarrowStringLayout, SysAlloc};
+    use super::{new_val, CxxNarrowStringLayout, DefaultAlloc, DEFAULT_ALLOC};
 
-    pub type CxxNarrowString<A = SysAlloc> = CxxNarrowStringLayout<A, Layout<A>>;
+    pub type CxxNarrowString<A = DefaultAlloc> = CxxNarrowStringLayout<A, Layout<A>>;
 
     #[repr(C)]
     pub struct Layout<A: CxxProxy> {
@@ -279,10 +600,10 @@ pub mod msvc2012 {
         }
     }
 
-    impl CxxNarrowString<SysAlloc> {
+    impl CxxNarrowString<DefaultAlloc> {
         pub const fn new() -> Self {
             Self {
-                inner: Layout::new_in(SysAlloc),
+                inner: Layout::new_in(DEFAULT_ALLOC),
             }
         }
     }
@@ -323,3 +644,112 @@ pub mod msvc2012 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw_parts_round_trip() {
+        let string = CxxNarrowString::from_bytes_in("a string on the heap", DEFAULT_ALLOC);
+        let ptr = string.as_ptr();
+
+        let (raw, alloc) = string.into_raw_parts();
+        let string = unsafe { CxxNarrowString::from_raw_parts_in(raw, alloc) };
+
+        assert_eq!(string.as_ptr(), ptr);
+        assert_eq!(string.as_bytes(), b"a string on the heap");
+    }
+
+    #[test]
+    fn shared_view_leaves_foreign_string() {
+        let (raw, alloc) = CxxNarrowString::from_bytes_in("inline", DEFAULT_ALLOC).into_raw_parts();
+
+        {
+            let view = unsafe { CxxNarrowStringRef::from_raw_in(&raw, DEFAULT_ALLOC) };
+            assert_eq!(view.as_bytes(), b"inline");
+        }
+
+        let string = unsafe { CxxNarrowString::from_raw_parts_in(raw, alloc) };
+        assert_eq!(string.as_bytes(), b"inline");
+    }
+
+    #[test]
+    fn mutable_view_writes_back() {
+        let (mut raw, alloc) =
+            CxxNarrowString::from_bytes_in("inline", DEFAULT_ALLOC).into_raw_parts();
+
+        {
+            let mut view = unsafe { CxxNarrowStringMut::from_raw_in(&mut raw, DEFAULT_ALLOC) };
+            view.push(", then on the heap");
+        }
+
+        assert!(raw.res > new_val().res);
+
+        let string = unsafe { CxxNarrowString::from_raw_parts_in(raw, alloc) };
+        assert_eq!(string.as_bytes(), b"inline, then on the heap");
+    }
+
+    fn raw_inline(bytes: &[u8], res: usize) -> RawString {
+        let mut buf = [0; 16];
+
+        for (dst, &src) in buf.iter_mut().zip(bytes) {
+            *dst = src as _;
+        }
+
+        RawString {
+            bx: cstl_sys::CSTL_StringUnion { buf },
+            size: bytes.len(),
+            res,
+        }
+    }
+
+    #[test]
+    fn validate_accepts_valid_strings() {
+        unsafe {
+            assert_eq!(validate(&new_val()), Ok(()));
+            assert_eq!(validate(&raw_inline(b"inline", 15)), Ok(()));
+
+            let heap = CxxNarrowString::from_bytes_in("a string on the heap", DEFAULT_ALLOC);
+            assert_eq!(validate(heap.inner.value_as_ref()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_broken_strings() {
+        let mut missing_nul = raw_inline(b"0123456789abcdef", 15);
+        missing_nul.size = 15;
+
+        let mut null = raw_inline(b"", 31);
+        null.bx.ptr = ptr::null_mut();
+
+        unsafe {
+            assert_eq!(
+                validate(&raw_inline(b"inline", 4)),
+                Err(RawStringError::CapacityBelowSmall {
+                    capacity: 4,
+                    small: 15
+                })
+            );
+            assert_eq!(
+                validate(&RawString {
+                    size: 40,
+                    res: 31,
+                    ..null
+                }),
+                Err(RawStringError::SizeExceedsCapacity {
+                    size: 40,
+                    capacity: 31
+                })
+            );
+            assert_eq!(validate(&null), Err(RawStringError::NullPointer));
+            assert_eq!(
+                validate(&missing_nul),
+                Err(RawStringError::MissingTerminator)
+            );
+
+            let string = CxxNarrowString::try_from_raw_parts_in(null, DEFAULT_ALLOC);
+            assert_eq!(string.err(), Some(RawStringError::NullPointer));
+        }
+    }
+}
@@ -1,15 +1,27 @@
-use std::{alloc::System as SysAlloc, borrow::Borrow, fmt, slice};
+use core::{
+    borrow::Borrow,
+    fmt,
+    marker::PhantomData,
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
 
 pub use cstl_sys::CSTL_UTF16StringVal as RawUtf16String;
 use cstl_sys::{
     CSTL_u16string_append_char, CSTL_u16string_append_n, CSTL_u16string_assign_n,
-    CSTL_u16string_c_str, CSTL_u16string_clear, CSTL_u16string_destroy, CSTL_u16string_reserve,
-    CSTL_u16string_shrink_to_fit,
+    CSTL_u16string_c_str, CSTL_u16string_clear, CSTL_u16string_copy_assign, CSTL_u16string_destroy,
+    CSTL_u16string_move_assign, CSTL_u16string_reserve, CSTL_u16string_shrink_to_fit,
+    CSTL_u16string_swap,
 };
 
-use crate::alloc::{CxxProxy, WithCxxProxy};
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc, UsesAllocator, WithCxxProxy, DEFAULT_ALLOC},
+    string::{max_size, validate_capacity, RawStringError},
+};
 
-pub type CxxUtf16String<A = SysAlloc> = CxxUtf16StringLayout<A, Layout<A>>;
+pub type CxxUtf16String<A = DefaultAlloc> = CxxUtf16StringLayout<A, Layout<A>>;
 
 #[repr(C)]
 pub struct Layout<A: CxxProxy> {
@@ -35,10 +47,10 @@ impl<A: CxxProxy> Layout<A> {
     }
 }
 
-impl CxxUtf16String<SysAlloc> {
+impl CxxUtf16String<DefaultAlloc> {
     pub const fn new() -> Self {
         Self {
-            inner: Layout::new_in(SysAlloc),
+            inner: Layout::new_in(DEFAULT_ALLOC),
         }
     }
 }
@@ -72,6 +84,49 @@ where
         new
     }
 
+    /// Creates a string from a raw `std::basic_string` value and its allocator,
+    /// taking ownership of the backing allocation, if any.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must describe a valid string whose backing allocation
+    /// can be freed by `alloc`.
+    pub unsafe fn from_raw_parts_in(raw: RawUtf16String, alloc: A) -> Self {
+        let mut new = Self::from_alloc(alloc);
+        *new.inner.value_as_mut() = raw;
+        new
+    }
+
+    /// Checks `raw` with [`validate`] before creating a string from it
+    /// with [`CxxUtf16StringLayout::from_raw_parts_in`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxUtf16StringLayout::from_raw_parts_in`], except that
+    /// `raw` may break the container invariants. If `raw` is in heap mode
+    /// and its length does not exceed its capacity, its pointer must be
+    /// valid for reads of the length plus one characters.
+    pub unsafe fn try_from_raw_parts_in(
+        raw: RawUtf16String,
+        alloc: A,
+    ) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&raw)?;
+            Ok(Self::from_raw_parts_in(raw, alloc))
+        }
+    }
+
+    /// Decomposes the string into a raw `std::basic_string` value and its allocator,
+    /// handing ownership of the backing allocation, if any, to the caller.
+    pub fn into_raw_parts(self) -> (RawUtf16String, A) {
+        let string = ManuallyDrop::new(self);
+
+        unsafe {
+            let alloc = ptr::read(string.inner.alloc_as_ref());
+            (*string.inner.value_as_ref(), alloc)
+        }
+    }
+
     pub fn as_ptr(&self) -> *const u16 {
         unsafe { CSTL_u16string_c_str(self.inner.value_as_ref()) as _ }
     }
@@ -133,11 +188,67 @@ where
         });
     }
 
+    /// Replaces the contents with those of `other`, like move assignment.
+    ///
+    /// Takes the storage of `other` if the allocator propagates or is equal
+    /// to the allocator of `other`, and copies the contents otherwise.
+    pub fn move_assign(&mut self, other: Self) {
+        if A::PROPAGATE_ON_MOVE_ASSIGNMENT {
+            *self = other;
+            return;
+        }
+
+        let mut other = other;
+        let take = self.alloc_eq(&other);
+
+        other.inner.with_proxy_mut(|other_val, other_alloc| {
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                CSTL_u16string_move_assign(val, alloc, other_val, other_alloc, take);
+            })
+        });
+    }
+
+    /// Swaps the contents with those of `other`, and the allocators if they propagate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocators do not propagate and are not equal,
+    /// which is undefined behavior in C++.
+    pub fn swap_with(&mut self, other: &mut Self) {
+        if A::PROPAGATE_ON_SWAP {
+            mem::swap(&mut self.inner, &mut other.inner);
+            return;
+        }
+
+        assert!(
+            self.alloc_eq(other),
+            "strings with unequal allocators cannot be swapped"
+        );
+
+        unsafe {
+            CSTL_u16string_swap(self.inner.value_as_mut(), other.inner.value_as_mut());
+        }
+    }
+
     fn from_alloc(alloc: A) -> Self {
         Self {
             inner: L::new_in(alloc),
         }
     }
+
+    fn alloc_eq(&self, other: &Self) -> bool {
+        A::IS_ALWAYS_EQUAL
+            || self
+                .inner
+                .alloc_as_ref()
+                .is_equal(other.inner.alloc_as_ref())
+    }
+
+    /// Replaces the allocator, keeping the storage.
+    fn replace_alloc(&mut self, alloc: A) {
+        let old = mem::replace(&mut self.inner, L::new_in(alloc));
+        *self.inner.value_as_mut() = *old.value_as_ref();
+    }
 }
 
 impl<A, L> fmt::Debug for CxxUtf16StringLayout<A, L>
@@ -204,6 +315,38 @@ where
     fn clone(&self) -> Self {
         Self::from_bytes_in(self, self.inner.alloc_as_ref().clone())
     }
+
+    /// Copies the contents of `source`, and the allocator if it propagates.
+    ///
+    /// Storage is freed first if the allocators are not equal.
+    fn clone_from(&mut self, source: &Self) {
+        let propagate = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+        let realloc = propagate && !self.alloc_eq(source);
+
+        source.inner.with_proxy(|other_val, other_alloc| {
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                CSTL_u16string_copy_assign(val, alloc, other_val, other_alloc, realloc);
+            })
+        });
+
+        if propagate {
+            self.replace_alloc(source.inner.alloc_as_ref().clone());
+        }
+    }
+}
+
+impl<A, L> UsesAllocator<A> for CxxUtf16StringLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawUtf16String>,
+{
+    fn new_with(alloc: A) -> Self {
+        Self::from_alloc(alloc)
+    }
+
+    fn clone_with(&self, alloc: A) -> Self {
+        Self::from_bytes_in(self, alloc)
+    }
 }
 
 impl<A, L> Extend<u16> for CxxUtf16StringLayout<A, L>
@@ -222,6 +365,194 @@ where
     }
 }
 
+/// Shared view over a foreign string.
+///
+/// The backing allocation is never freed.
+pub struct CxxUtf16StringRef<'a, A: CxxProxy = DefaultAlloc> {
+    string: ManuallyDrop<CxxUtf16String<A>>,
+    _marker: PhantomData<&'a RawUtf16String>,
+}
+
+/// Mutable view over a foreign string.
+///
+/// Modifications go through `alloc` and are written back to the foreign
+/// string when the view is dropped. The foreign string keeps ownership
+/// of the backing allocation.
+pub struct CxxUtf16StringMut<'a, A: CxxProxy = DefaultAlloc> {
+    raw: NonNull<RawUtf16String>,
+    string: ManuallyDrop<CxxUtf16String<A>>,
+    _marker: PhantomData<&'a mut RawUtf16String>,
+}
+
+impl<'a, A: CxxProxy> CxxUtf16StringRef<'a, A> {
+    /// Creates a shared view over the string pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid string that is not mutated for the
+    /// duration of `'a`, and `alloc` must be compatible with the allocator
+    /// of that string.
+    pub unsafe fn from_raw_in(raw: *const RawUtf16String, alloc: A) -> Self {
+        unsafe {
+            Self {
+                string: ManuallyDrop::new(CxxUtf16String::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the string pointed to by `raw` with [`validate`] before
+    /// creating a shared view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxUtf16StringRef::from_raw_in`] and [`validate`],
+    /// except that the string may break the container invariants.
+    pub unsafe fn try_from_raw_in(
+        raw: *const RawUtf16String,
+        alloc: A,
+    ) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<'a, A: CxxProxy> CxxUtf16StringMut<'a, A> {
+    /// Creates a mutable view over the string pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid string that is not accessed through
+    /// any other pointer for the duration of `'a`, and `alloc` must be able
+    /// to free and reallocate the backing allocation of that string.
+    ///
+    /// Leaking the view leaves the foreign string in the state it was in
+    /// when the view was created, which may refer to freed memory.
+    pub unsafe fn from_raw_in(raw: *mut RawUtf16String, alloc: A) -> Self {
+        unsafe {
+            let raw = NonNull::new(raw).expect("`raw` is null");
+
+            Self {
+                raw,
+                string: ManuallyDrop::new(CxxUtf16String::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the string pointed to by `raw` with [`validate`] before
+    /// creating a mutable view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxUtf16StringMut::from_raw_in`] and [`validate`],
+    /// except that the string may break the container invariants.
+    pub unsafe fn try_from_raw_in(
+        raw: *mut RawUtf16String,
+        alloc: A,
+    ) -> Result<Self, RawStringError> {
+        unsafe {
+            validate(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<A: CxxProxy> Deref for CxxUtf16StringRef<'_, A> {
+    type Target = CxxUtf16String<A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+impl<A: CxxProxy> Deref for CxxUtf16StringMut<'_, A> {
+    type Target = CxxUtf16String<A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.string
+    }
+}
+
+impl<A: CxxProxy> DerefMut for CxxUtf16StringMut<'_, A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.string
+    }
+}
+
+impl<A: CxxProxy> fmt::Debug for CxxUtf16StringRef<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<A: CxxProxy> fmt::Debug for CxxUtf16StringMut<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<A: CxxProxy> Drop for CxxUtf16StringRef<'_, A> {
+    fn drop(&mut self) {
+        let (_, alloc) = unsafe { ManuallyDrop::take(&mut self.string).into_raw_parts() };
+        drop(alloc);
+    }
+}
+
+impl<A: CxxProxy> Drop for CxxUtf16StringMut<'_, A> {
+    fn drop(&mut self) {
+        let (val, alloc) = unsafe { ManuallyDrop::take(&mut self.string).into_raw_parts() };
+
+        unsafe {
+            self.raw.write(val);
+        }
+
+        drop(alloc);
+    }
+}
+
+/// Checks that `raw` upholds the `std::basic_string` invariants.
+///
+/// In inline mode the capacity is equal to that of the inline buffer,
+/// in heap mode it is larger and the string points to a separate allocation.
+///
+/// # Safety
+///
+/// If `raw` is in heap mode and its length does not exceed its capacity,
+/// its pointer must be valid for reads of the length plus one characters.
+pub unsafe fn validate(raw: &RawUtf16String) -> Result<(), RawStringError> {
+    const SMALL: usize = new_val().res;
+    const MAX: usize = max_size(mem::size_of::<u16>());
+
+    let size = raw.size;
+
+    let ptr = if validate_capacity(size, raw.res, SMALL, MAX)? {
+        let ptr = unsafe { raw.bx.ptr as *const u16 };
+
+        if ptr.is_null() {
+            return Err(RawStringError::NullPointer);
+        }
+
+        if !ptr.is_aligned() {
+            return Err(RawStringError::Misaligned {
+                align: mem::align_of::<u16>(),
+            });
+        }
+
+        ptr
+    } else {
+        unsafe { raw.bx.buf.as_ptr().cast::<u16>() }
+    };
+
+    if unsafe { ptr.add(size).read() } != 0 {
+        return Err(RawStringError::MissingTerminator);
+    }
+
+    Ok(())
+}
+
 const fn new_val() -> RawUtf16String {
     RawUtf16String {
         bx: cstl_sys::CSTL_UTF16StringUnion { buf: [0; 8] },
@@ -260,9 +591,9 @@ pub mod msvc2012 {
 
     use crate::alloc::{CxxProxy, WithCxxProxy};
 
-    use super::{new_val, CxxUtf16StringLayout, SysAlloc};
+    use super::{new_val, CxxUtf16StringLayout, DefaultAlloc, DEFAULT_ALLOC};
 
-    pub type CxxUtf16String<A = SysAlloc> = CxxUtf16StringLayout<A, Layout<A>>;
+    pub type CxxUtf16String<A = DefaultAlloc> = CxxUtf16StringLayout<A, Layout<A>>;
 
     #[repr(C)]
     pub struct Layout<A: CxxProxy> {
@@ -279,10 +610,10 @@ pub mod msvc2012 {
         }
     }
 
-    impl CxxUtf16String<SysAlloc> {
+    impl CxxUtf16String<DefaultAlloc> {
         pub const fn new() -> Self {
             Self {
-                inner: Layout::new_in(SysAlloc),
+                inner: Layout::new_in(DEFAULT_ALLOC),
             }
         }
     }
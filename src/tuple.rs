@@ -0,0 +1,283 @@
+//! `std::tuple` with the MSVC layout.
+
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Trait for Rust tuples that have a `std::tuple` counterpart.
+///
+/// Implemented for tuples of up to 12 elements.
+pub trait TupleElements: Sized {
+    /// Layout of the `std::tuple`, nested [`TupleNode`]s.
+    type Layout;
+
+    /// Tuple of references to the elements.
+    type Ref<'a>
+    where
+        Self: 'a;
+
+    /// Tuple of mutable references to the elements.
+    type Mut<'a>
+    where
+        Self: 'a;
+
+    fn into_layout(self) -> Self::Layout;
+
+    fn from_layout(layout: Self::Layout) -> Self;
+
+    fn layout_as_ref(layout: &Self::Layout) -> Self::Ref<'_>;
+
+    fn layout_as_mut(layout: &mut Self::Layout) -> Self::Mut<'_>;
+}
+
+/// `std::tuple<H, ...>`, which derives from the tuple of the remaining elements `T`.
+///
+/// MSVC does not reuse the tail padding of base classes,
+/// so `T` is laid out as a whole before the first element.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct TupleNode<H, T> {
+    rest: T,
+    first: H,
+}
+
+/// `std::tuple<>`, an empty base class.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct TupleEnd;
+
+/// `std::tuple` with the elements of the Rust tuple `T`.
+///
+/// MSVC stores the elements in reverse order,
+/// `CxxTuple<(A, B, C)>` is laid out as `{ C, B, A }`, each padded to its alignment.
+/// `std::tuple<>` at the end is an empty base and takes up no space.
+///
+/// Zero-sized types take up no space, while C++ empty classes take up a byte
+/// as members, so an empty C++ element is declared as a `u8` placeholder.
+#[repr(transparent)]
+pub struct CxxTuple<T: TupleElements> {
+    layout: T::Layout,
+}
+
+impl<T: TupleElements> CxxTuple<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            layout: value.into_layout(),
+        }
+    }
+
+    pub fn as_ref(&self) -> T::Ref<'_> {
+        T::layout_as_ref(&self.layout)
+    }
+
+    pub fn as_mut(&mut self) -> T::Mut<'_> {
+        T::layout_as_mut(&mut self.layout)
+    }
+
+    pub fn into_tuple(self) -> T {
+        T::from_layout(self.layout)
+    }
+}
+
+impl<T: TupleElements> Clone for CxxTuple<T>
+where
+    T::Layout: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            layout: self.layout.clone(),
+        }
+    }
+}
+
+impl<T: TupleElements> Copy for CxxTuple<T> where T::Layout: Copy {}
+
+impl<T: TupleElements> fmt::Debug for CxxTuple<T>
+where
+    for<'a> T::Ref<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CxxTuple").field(&self.as_ref()).finish()
+    }
+}
+
+impl<T: TupleElements> Default for CxxTuple<T>
+where
+    T::Layout: Default,
+{
+    fn default() -> Self {
+        Self {
+            layout: T::Layout::default(),
+        }
+    }
+}
+
+impl<T: TupleElements> PartialEq for CxxTuple<T>
+where
+    for<'a> T::Ref<'a>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<T: TupleElements> Eq for CxxTuple<T> where for<'a> T::Ref<'a>: Eq {}
+
+impl<T: TupleElements> PartialOrd for CxxTuple<T>
+where
+    for<'a> T::Ref<'a>: PartialOrd,
+{
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.as_ref().partial_cmp(&other.as_ref())
+    }
+}
+
+impl<T: TupleElements> Ord for CxxTuple<T>
+where
+    for<'a> T::Ref<'a>: Ord,
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_ref().cmp(&other.as_ref())
+    }
+}
+
+impl<T: TupleElements> Hash for CxxTuple<T>
+where
+    for<'a> T::Ref<'a>: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
+impl<T: TupleElements> From<T> for CxxTuple<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+macro_rules! tuple_layout {
+    (@type) => {
+        TupleEnd
+    };
+    (@type $head:ident $($rest:ident)*) => {
+        TupleNode<$head, tuple_layout!(@type $($rest)*)>
+    };
+    (@value) => {
+        TupleEnd
+    };
+    (@value $head:ident $($rest:ident)*) => {
+        TupleNode {
+            rest: tuple_layout!(@value $($rest)*),
+            first: $head,
+        }
+    };
+}
+
+macro_rules! impl_tuple_elements {
+    ($($t:ident $v:ident),*) => {
+        #[allow(clippy::unused_unit)]
+        impl<$($t),*> TupleElements for ($($t,)*) {
+            type Layout = tuple_layout!(@type $($t)*);
+
+            type Ref<'a> = ($(&'a $t,)*)
+            where
+                Self: 'a;
+
+            type Mut<'a> = ($(&'a mut $t,)*)
+            where
+                Self: 'a;
+
+            fn into_layout(self) -> Self::Layout {
+                let ($($v,)*) = self;
+                tuple_layout!(@value $($v)*)
+            }
+
+            fn from_layout(layout: Self::Layout) -> Self {
+                let tuple_layout!(@value $($v)*) = layout;
+                ($($v,)*)
+            }
+
+            fn layout_as_ref(layout: &Self::Layout) -> Self::Ref<'_> {
+                let tuple_layout!(@value $($v)*) = layout;
+                ($($v,)*)
+            }
+
+            fn layout_as_mut(layout: &mut Self::Layout) -> Self::Mut<'_> {
+                let tuple_layout!(@value $($v)*) = layout;
+                ($($v,)*)
+            }
+        }
+    };
+}
+
+impl_tuple_elements!();
+impl_tuple_elements!(A a);
+impl_tuple_elements!(A a, B b);
+impl_tuple_elements!(A a, B b, C c);
+impl_tuple_elements!(A a, B b, C c, D d);
+impl_tuple_elements!(A a, B b, C c, D d, E e);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g, H h);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g, H h, I i);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k);
+impl_tuple_elements!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+
+#[cfg(test)]
+mod tests {
+    use core::mem::{offset_of, size_of};
+
+    use super::*;
+
+    #[test]
+    fn elements_are_reversed() {
+        type T = CxxTuple<(u8, u32, u16)>;
+
+        assert_eq!(size_of::<T>(), 12);
+        assert_eq!(offset_of!(T, layout.rest.rest.first), 0);
+        assert_eq!(offset_of!(T, layout.rest.first), 4);
+        assert_eq!(offset_of!(T, layout.first), 8);
+
+        type U = CxxTuple<(u64, u8)>;
+
+        assert_eq!(size_of::<U>(), 16);
+        assert_eq!(offset_of!(U, layout.rest.first), 0);
+        assert_eq!(offset_of!(U, layout.first), 8);
+    }
+
+    #[test]
+    fn tail_padding_is_not_reused() {
+        // The base `std::tuple<u32, u8>` keeps its size of 8 in `std::tuple<u8, u32, u8>`.
+        type T = CxxTuple<(u8, u32, u8)>;
+
+        assert_eq!(size_of::<T>(), 12);
+        assert_eq!(offset_of!(T, layout.rest.rest.first), 0);
+        assert_eq!(offset_of!(T, layout.rest.first), 4);
+        assert_eq!(offset_of!(T, layout.first), 8);
+    }
+
+    #[test]
+    fn empty_tuple_takes_no_space() {
+        assert_eq!(size_of::<CxxTuple<()>>(), 0);
+        assert_eq!(size_of::<CxxTuple<(u16,)>>(), 2);
+        assert_eq!(offset_of!(CxxTuple<(u16,)>, layout.first), 0);
+
+        type T = CxxTuple<((), u32)>;
+
+        assert_eq!(size_of::<T>(), 4);
+        assert_eq!(offset_of!(T, layout.first), 4);
+    }
+
+    #[test]
+    fn converts_from_tuple() {
+        let mut tuple = CxxTuple::new((1u8, 2u32, "three"));
+
+        *tuple.as_mut().1 += 1;
+        assert_eq!(tuple.as_ref(), (&1, &3, &"three"));
+        assert_eq!(tuple.into_tuple(), (1, 3, "three"));
+    }
+}
@@ -0,0 +1,617 @@
+//! `std::variant` with the MSVC layout.
+//!
+//! The alternatives of a variant are declared as a Rust enum with the
+//! [`cxx_variant!`](crate::cxx_variant) macro, which implements [`Alternatives`] for it.
+//! [`CxxVariant`] then stores a value of that enum the way MSVC does:
+//!
+//! ```
+//! use cxx_stl::{cxx_variant, string::CxxNarrowString, variant::CxxVariant, vec::CxxVec};
+//!
+//! cxx_variant! {
+//!     /// Alternatives of `std::variant<int, std::string, std::vector<uint8_t>>`.
+//!     #[derive(Debug)]
+//!     pub enum Value {
+//!         Int(i32),
+//!         Str(CxxNarrowString),
+//!         Bytes(CxxVec<u8>),
+//!     }
+//! }
+//!
+//! let mut var = CxxVariant::new(Value::Int(42));
+//!
+//! let mut bytes = CxxVec::new();
+//! bytes.extend([1, 2, 3]);
+//!
+//! var.set(Value::Bytes(bytes));
+//!
+//! match var.as_ref() {
+//!     Some(Value::Int(int)) => println!("int {int}"),
+//!     Some(Value::Str(s)) => println!("string {s:?}"),
+//!     Some(Value::Bytes(bytes)) => println!("{} bytes", bytes.len()),
+//!     None => println!("valueless"),
+//! }
+//! ```
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ptr::NonNull,
+};
+
+/// Trait for the alternatives of a [`CxxVariant`].
+///
+/// Implemented by [`cxx_variant!`](crate::cxx_variant), which also makes
+/// [`Alternatives::Ref`] and [`Alternatives::Mut`] the same enum over references.
+///
+/// # Safety
+///
+/// [`Alternatives::Storage`] must have the size and alignment of the largest
+/// alternative, and the other items must agree on the index of every alternative.
+pub unsafe trait Alternatives: Sized {
+    /// Union of all alternatives.
+    type Storage;
+
+    /// Type of the index, which must be [`Index`] of the [`index_size`] of [`Alternatives::COUNT`].
+    type Index: VariantIndex;
+
+    /// Shared view of the active alternative.
+    type Ref<'a>
+    where
+        Self: 'a;
+
+    /// Mutable view of the active alternative.
+    type Mut<'a>
+    where
+        Self: 'a;
+
+    /// Number of alternatives.
+    const COUNT: usize;
+
+    /// Index of the alternative of `self`.
+    fn index(&self) -> usize;
+
+    /// Moves the alternative of `self` into `storage`.
+    ///
+    /// # Safety
+    ///
+    /// `storage` must be valid for writes.
+    unsafe fn write(self, storage: NonNull<Self::Storage>);
+
+    /// Moves the alternative at `index` out of `storage`.
+    ///
+    /// # Safety
+    ///
+    /// `storage` must hold an initialized alternative at `index`.
+    unsafe fn read(storage: NonNull<Self::Storage>, index: usize) -> Self;
+
+    /// Borrows the alternative at `index` in `storage`.
+    ///
+    /// # Safety
+    ///
+    /// `storage` must hold an initialized alternative at `index`
+    /// which is not mutated for the duration of `'a`.
+    unsafe fn get<'a>(storage: NonNull<Self::Storage>, index: usize) -> Self::Ref<'a>;
+
+    /// Mutably borrows the alternative at `index` in `storage`.
+    ///
+    /// # Safety
+    ///
+    /// `storage` must hold an initialized alternative at `index`
+    /// which is not accessed through any other pointer for the duration of `'a`.
+    unsafe fn get_mut<'a>(storage: NonNull<Self::Storage>, index: usize) -> Self::Mut<'a>;
+}
+
+/// End of the storage of `std::variant`, an empty class taking up one byte.
+///
+/// MSVC nests the alternatives in unions which end in this class, so the
+/// storage is never smaller than one byte, even if all alternatives are empty.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct VariantEnd(u8);
+
+/// Trait for the signed index types of `std::variant`, [`i8`], [`i16`] and [`i32`].
+pub trait VariantIndex: Copy {
+    /// Index of a variant which is valueless by exception, `-1`.
+    const VALUELESS: Self;
+
+    /// Converts an alternative index.
+    ///
+    /// # Panics
+    ///
+    /// If `index` does not fit in the index type.
+    fn from_index(index: usize) -> Self;
+
+    /// Converts back to an alternative index, or [`None`] if valueless.
+    fn to_index(self) -> Option<usize>;
+}
+
+macro_rules! impl_variant_index {
+    ($($t:ty),*) => {$(
+        impl VariantIndex for $t {
+            const VALUELESS: Self = -1;
+
+            fn from_index(index: usize) -> Self {
+                Self::try_from(index).expect("bad variant index")
+            }
+
+            fn to_index(self) -> Option<usize> {
+                usize::try_from(self).ok()
+            }
+        }
+    )*};
+}
+
+impl_variant_index!(i8, i16, i32);
+
+/// Size of the index of a `std::variant` with `count` alternatives, `_Variant_index_t`.
+///
+/// MSVC uses the smallest signed type that can hold every index and `-1`,
+/// with the largest values left unused.
+pub const fn index_size(count: usize) -> usize {
+    if count < i8::MAX as usize {
+        1
+    } else if count < i16::MAX as usize {
+        2
+    } else {
+        4
+    }
+}
+
+/// Selects the index type of `SIZE` bytes.
+pub struct IndexSize<const SIZE: usize>;
+
+/// Trait mapping an [`IndexSize`] to its index type.
+pub trait SelectIndex {
+    type Index: VariantIndex;
+}
+
+impl SelectIndex for IndexSize<1> {
+    type Index = i8;
+}
+
+impl SelectIndex for IndexSize<2> {
+    type Index = i16;
+}
+
+impl SelectIndex for IndexSize<4> {
+    type Index = i32;
+}
+
+/// Index type of `SIZE` bytes, `Index<{ index_size(COUNT) }>` being that
+/// of a variant with `COUNT` alternatives.
+pub type Index<const SIZE: usize> = <IndexSize<SIZE> as SelectIndex>::Index;
+
+/// `std::variant` with the alternatives in `V`.
+///
+/// The storage is followed by the index of the active alternative,
+/// which is `-1` if the variant is valueless by exception. MSVC uses
+/// the smallest signed type that fits the index, see [`index_size`].
+#[repr(C)]
+pub struct CxxVariant<V: Alternatives> {
+    storage: MaybeUninit<V::Storage>,
+    index: V::Index,
+    _marker: PhantomData<V>,
+}
+
+impl<V: Alternatives> CxxVariant<V> {
+    pub fn new(value: V) -> Self {
+        let mut var = Self::valueless();
+        var.set(value);
+        var
+    }
+
+    /// Creates a variant which is valueless by exception.
+    pub const fn valueless() -> Self {
+        const {
+            assert!(
+                mem::size_of::<V::Index>() == index_size(V::COUNT),
+                "bad variant index type for the number of alternatives",
+            )
+        };
+
+        Self {
+            storage: MaybeUninit::uninit(),
+            index: V::Index::VALUELESS,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Index of the active alternative, or [`None`] if the variant is valueless.
+    pub fn index(&self) -> Option<usize> {
+        self.index.to_index()
+    }
+
+    pub fn is_valueless(&self) -> bool {
+        self.index().is_none()
+    }
+
+    pub fn as_ref(&self) -> Option<V::Ref<'_>> {
+        let index = self.index()?;
+        unsafe { Some(V::get(NonNull::from(&self.storage).cast(), index)) }
+    }
+
+    pub fn as_mut(&mut self) -> Option<V::Mut<'_>> {
+        let index = self.index()?;
+        unsafe { Some(V::get_mut(NonNull::from(&mut self.storage).cast(), index)) }
+    }
+
+    /// Calls `f` with a view of the active alternative.
+    ///
+    /// Returns [`None`] without calling `f` if the variant is valueless.
+    pub fn visit<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(V::Ref<'_>) -> R,
+    {
+        self.as_ref().map(f)
+    }
+
+    /// Calls `f` with a mutable view of the active alternative.
+    ///
+    /// Returns [`None`] without calling `f` if the variant is valueless.
+    pub fn visit_mut<R, F>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(V::Mut<'_>) -> R,
+    {
+        self.as_mut().map(f)
+    }
+
+    /// Takes the active alternative out of the variant, leaving it valueless.
+    pub fn take(&mut self) -> Option<V> {
+        let index = self.index()?;
+        self.index = V::Index::VALUELESS;
+        unsafe { Some(V::read(NonNull::from(&mut self.storage).cast(), index)) }
+    }
+
+    /// Replaces the active alternative with `value`, returning the old one if any.
+    pub fn replace(&mut self, value: V) -> Option<V> {
+        let old = self.take();
+        self.set(value);
+        old
+    }
+
+    /// Replaces the active alternative with `value`, dropping the old one if any.
+    pub fn set(&mut self, value: V) {
+        drop(self.take());
+
+        let index = value.index();
+        assert!(index < V::COUNT, "bad variant index");
+
+        unsafe {
+            value.write(NonNull::from(&mut self.storage).cast());
+        }
+
+        self.index = V::Index::from_index(index);
+    }
+
+    /// Consumes the variant, returning the active alternative if any.
+    pub fn into_inner(mut self) -> Option<V> {
+        self.take()
+    }
+}
+
+/// Trait for views of an alternative that can be cloned into an owned `V`.
+///
+/// Implemented by [`cxx_variant!`](crate::cxx_variant) for [`Alternatives::Ref`]
+/// if every alternative implements [`Clone`].
+pub trait Cloned<V> {
+    fn cloned(self) -> V;
+}
+
+impl<V> Clone for CxxVariant<V>
+where
+    V: Alternatives,
+    for<'a> V::Ref<'a>: Cloned<V>,
+{
+    fn clone(&self) -> Self {
+        match self.as_ref() {
+            Some(value) => Self::new(value.cloned()),
+            None => Self::valueless(),
+        }
+    }
+}
+
+impl<V> fmt::Debug for CxxVariant<V>
+where
+    V: Alternatives,
+    for<'a> V::Ref<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CxxVariant").field(&self.as_ref()).finish()
+    }
+}
+
+impl<V: Alternatives + Default> Default for CxxVariant<V> {
+    fn default() -> Self {
+        Self::new(V::default())
+    }
+}
+
+impl<V: Alternatives> Drop for CxxVariant<V> {
+    fn drop(&mut self) {
+        drop(self.take());
+    }
+}
+
+impl<V> PartialEq for CxxVariant<V>
+where
+    V: Alternatives,
+    for<'a> V::Ref<'a>: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl<V> Eq for CxxVariant<V>
+where
+    V: Alternatives,
+    for<'a> V::Ref<'a>: Eq,
+{
+}
+
+impl<V: Alternatives> From<V> for CxxVariant<V> {
+    fn from(value: V) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Declares the alternatives of a [`CxxVariant`](crate::variant::CxxVariant) as an enum.
+///
+/// Every variant of the enum holds exactly one alternative, in the order of the
+/// C++ template arguments. The names of the variants double as type parameters
+/// of the enum, which default to the alternatives, so that
+/// [`Alternatives::Ref`](crate::variant::Alternatives::Ref) and
+/// [`Alternatives::Mut`](crate::variant::Alternatives::Mut) can be the same enum
+/// over references. For this reason, variant names must not coincide with
+/// the names of types used in the alternatives.
+///
+/// Like `std::variant`, the enum implements [`Default`] with the default value
+/// of the first alternative. Its [`Alternatives::Ref`](crate::variant::Alternatives::Ref)
+/// view implements [`Cloned`](crate::variant::Cloned) if the alternatives are [`Clone`].
+///
+/// See the [module documentation](crate::variant) for an example.
+#[macro_export]
+macro_rules! cxx_variant {
+    (@default $name:ident $first:ident $($rest:ident)*) => {
+        impl<$first: ::core::default::Default, $($rest),*> ::core::default::Default
+            for $name<$first, $($rest),*>
+        {
+            fn default() -> Self {
+                Self::$first(::core::default::Default::default())
+            }
+        }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$alt_attr:meta])* $alt:ident($ty:ty)),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name<$($alt = $ty),+> {
+            $($(#[$alt_attr])* $alt($alt)),+
+        }
+
+        $crate::cxx_variant!(@default $name $($alt)+);
+
+        impl<'a, $($alt: ::core::clone::Clone),+> $crate::variant::Cloned<$name<$($alt),+>>
+            for $name<$(&'a $alt),+>
+        {
+            fn cloned(self) -> $name<$($alt),+> {
+                match self {
+                    $(Self::$alt(value) => $name::$alt(::core::clone::Clone::clone(value))),+
+                }
+            }
+        }
+
+        const _: () = {
+            #[allow(dead_code)]
+            enum __CxxVariantIndex {
+                $($alt),+
+            }
+
+            const __COUNT: usize = [$(__CxxVariantIndex::$alt),+].len();
+
+            /// Flat union with the same layout as the nested MSVC storage.
+            #[allow(dead_code, non_snake_case)]
+            #[repr(C)]
+            pub union __CxxVariantStorage {
+                $($alt: ::core::mem::ManuallyDrop<$ty>,)+
+                __end: $crate::variant::VariantEnd,
+            }
+
+            unsafe impl $crate::variant::Alternatives for $name {
+                type Storage = __CxxVariantStorage;
+
+                type Index = $crate::variant::Index<{ $crate::variant::index_size(__COUNT) }>;
+
+                type Ref<'a> = $name<$(&'a $ty),+>;
+
+                type Mut<'a> = $name<$(&'a mut $ty),+>;
+
+                const COUNT: usize = __COUNT;
+
+                fn index(&self) -> usize {
+                    match self {
+                        $(Self::$alt(_) => __CxxVariantIndex::$alt as usize),+
+                    }
+                }
+
+                unsafe fn write(self, storage: ::core::ptr::NonNull<Self::Storage>) {
+                    match self {
+                        $(Self::$alt(value) => unsafe { storage.cast::<$ty>().write(value) }),+
+                    }
+                }
+
+                unsafe fn read(storage: ::core::ptr::NonNull<Self::Storage>, index: usize) -> Self {
+                    $(
+                        if index == __CxxVariantIndex::$alt as usize {
+                            return Self::$alt(unsafe { storage.cast::<$ty>().read() });
+                        }
+                    )+
+                    unreachable!("bad variant index {index}")
+                }
+
+                unsafe fn get<'a>(
+                    storage: ::core::ptr::NonNull<Self::Storage>,
+                    index: usize,
+                ) -> Self::Ref<'a> {
+                    $(
+                        if index == __CxxVariantIndex::$alt as usize {
+                            return $name::$alt(unsafe { storage.cast::<$ty>().as_ref() });
+                        }
+                    )+
+                    unreachable!("bad variant index {index}")
+                }
+
+                unsafe fn get_mut<'a>(
+                    storage: ::core::ptr::NonNull<Self::Storage>,
+                    index: usize,
+                ) -> Self::Mut<'a> {
+                    $(
+                        if index == __CxxVariantIndex::$alt as usize {
+                            return $name::$alt(unsafe { storage.cast::<$ty>().as_mut() });
+                        }
+                    )+
+                    unreachable!("bad variant index {index}")
+                }
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use rust_alloc::format;
+
+    use super::*;
+    use crate::vec::CxxVec;
+
+    crate::cxx_variant! {
+        #[derive(Debug, PartialEq)]
+        enum Small {
+            Int(i32),
+            Byte(u8),
+        }
+    }
+
+    crate::cxx_variant! {
+        enum Empty {
+            First(()),
+            Second(()),
+        }
+    }
+
+    crate::cxx_variant! {
+        #[derive(Debug, PartialEq)]
+        enum Owned {
+            Int(i32),
+            Bytes(CxxVec<u8>),
+        }
+    }
+
+    crate::cxx_variant! {
+        enum Wide {
+            A0(u8), A1(u8), A2(u8), A3(u8), A4(u8), A5(u8), A6(u8), A7(u8), A8(u8), A9(u8), A10(u8),
+            A11(u8), A12(u8), A13(u8), A14(u8), A15(u8), A16(u8), A17(u8), A18(u8), A19(u8),
+            A20(u8), A21(u8), A22(u8), A23(u8), A24(u8), A25(u8), A26(u8), A27(u8), A28(u8),
+            A29(u8), A30(u8), A31(u8), A32(u8), A33(u8), A34(u8), A35(u8), A36(u8), A37(u8),
+            A38(u8), A39(u8), A40(u8), A41(u8), A42(u8), A43(u8), A44(u8), A45(u8), A46(u8),
+            A47(u8), A48(u8), A49(u8), A50(u8), A51(u8), A52(u8), A53(u8), A54(u8), A55(u8),
+            A56(u8), A57(u8), A58(u8), A59(u8), A60(u8), A61(u8), A62(u8), A63(u8), A64(u8),
+            A65(u8), A66(u8), A67(u8), A68(u8), A69(u8), A70(u8), A71(u8), A72(u8), A73(u8),
+            A74(u8), A75(u8), A76(u8), A77(u8), A78(u8), A79(u8), A80(u8), A81(u8), A82(u8),
+            A83(u8), A84(u8), A85(u8), A86(u8), A87(u8), A88(u8), A89(u8), A90(u8), A91(u8),
+            A92(u8), A93(u8), A94(u8), A95(u8), A96(u8), A97(u8), A98(u8), A99(u8), A100(u8),
+            A101(u8), A102(u8), A103(u8), A104(u8), A105(u8), A106(u8), A107(u8), A108(u8),
+            A109(u8), A110(u8), A111(u8), A112(u8), A113(u8), A114(u8), A115(u8), A116(u8),
+            A117(u8), A118(u8), A119(u8), A120(u8), A121(u8), A122(u8), A123(u8), A124(u8),
+            A125(u8), A126(u8)
+        }
+    }
+
+    #[test]
+    fn index_after_storage() {
+        let var = CxxVariant::new(Small::Byte(7));
+
+        assert_eq!(mem::size_of::<CxxVariant<Small>>(), 8);
+        assert_eq!(mem::size_of::<CxxVariant<Empty>>(), 2);
+        assert_eq!(var.index(), Some(1));
+        assert_eq!(
+            unsafe {
+                (&var as *const CxxVariant<Small>)
+                    .cast::<i8>()
+                    .add(4)
+                    .read()
+            },
+            1
+        );
+    }
+
+    #[test]
+    fn index_size_follows_msvc() {
+        assert_eq!(index_size(1), 1);
+        assert_eq!(index_size(126), 1);
+        assert_eq!(index_size(127), 2);
+        assert_eq!(index_size(32766), 2);
+        assert_eq!(index_size(32767), 4);
+    }
+
+    #[test]
+    fn wide_index() {
+        let mut var = CxxVariant::new(Wide::A0(1));
+        var.set(Wide::A126(2));
+
+        assert_eq!(Wide::COUNT, 127);
+        assert_eq!(mem::size_of::<CxxVariant<Wide>>(), 4);
+        assert_eq!(var.index(), Some(126));
+        assert_eq!(
+            unsafe {
+                (&var as *const CxxVariant<Wide>)
+                    .cast::<i16>()
+                    .add(1)
+                    .read()
+            },
+            126
+        );
+
+        var.take();
+        assert_eq!(
+            unsafe {
+                (&var as *const CxxVariant<Wide>)
+                    .cast::<i16>()
+                    .add(1)
+                    .read()
+            },
+            -1
+        );
+    }
+
+    #[test]
+    fn clone_compare_and_format_through_views() {
+        let var = CxxVariant::new(Owned::Bytes(CxxVec::from_slice_in(
+            &[1, 2, 3],
+            crate::alloc::DEFAULT_ALLOC,
+        )));
+        let clone = var.clone();
+
+        match (var.as_ref(), clone.as_ref()) {
+            (Some(Owned::Bytes(a)), Some(Owned::Bytes(b))) => assert_ne!(a.as_ptr(), b.as_ptr()),
+            _ => panic!("bad clone"),
+        }
+
+        assert_eq!(var, clone);
+        assert_ne!(var, CxxVariant::new(Owned::Int(1)));
+        assert_eq!(
+            format!("{:?}", CxxVariant::new(Owned::Int(1))),
+            "CxxVariant(Some(Int(1)))"
+        );
+        assert_eq!(
+            format!("{:?}", CxxVariant::<Owned>::valueless()),
+            "CxxVariant(None)"
+        );
+    }
+}
@@ -1,5 +1,4 @@
-use std::{
-    alloc::System as SysAlloc,
+use core::{
     iter::FusedIterator,
     marker::PhantomData,
     mem::ManuallyDrop,
@@ -10,11 +9,11 @@ use std::{
 use cstl_sys::CSTL_VectorVal as RawVec;
 
 use crate::{
-    alloc::CxxProxy,
+    alloc::{CxxProxy, DefaultAlloc},
     vec::{CxxVec, Layout},
 };
 
-pub struct IntoIter<T, A: CxxProxy = SysAlloc> {
+pub struct IntoIter<T, A: CxxProxy = DefaultAlloc> {
     pub(super) alloc: ManuallyDrop<A>,
     pub(super) val: RawVec,
     pub(super) _marker: PhantomData<T>,
@@ -1,5 +1,6 @@
-use std::{
-    alloc::System as SysAlloc,
+#[cfg(feature = "nightly")]
+use core::alloc::Allocator;
+use core::{
     borrow::{Borrow, BorrowMut},
     fmt,
     hash::{Hash, Hasher},
@@ -10,26 +11,41 @@ use std::{
     slice::{self, SliceIndex},
 };
 
+use rust_alloc::vec::Vec;
+
 pub use cstl_sys::CSTL_VectorVal as RawVec;
 use cstl_sys::{
     CSTL_vector_begin, CSTL_vector_clear, CSTL_vector_copy_assign, CSTL_vector_copy_assign_range,
     CSTL_vector_destroy, CSTL_vector_end, CSTL_vector_erase, CSTL_vector_iterator_add,
     CSTL_vector_iterator_eq, CSTL_vector_move_assign, CSTL_vector_move_assign_range,
     CSTL_vector_move_insert, CSTL_vector_move_push_back, CSTL_vector_pop_back, CSTL_vector_reserve,
-    CSTL_vector_resize, CSTL_vector_shrink_to_fit, CSTL_vector_truncate,
+    CSTL_vector_resize, CSTL_vector_shrink_to_fit, CSTL_vector_swap, CSTL_vector_truncate,
 };
 use into_iter::IntoIter;
+pub use vec_bool::{CxxVecBool, CxxVecBoolLayout};
+pub use view::{CxxVecMut, CxxVecRef};
 
+#[cfg(feature = "nightly")]
+use crate::alloc::AllocatorProxy;
 use crate::{
-    alloc::{CxxProxy, WithCxxProxy},
+    alloc::{CxxProxy, DefaultAlloc, ScopedAlloc, UsesAllocator, WithCxxProxy, DEFAULT_ALLOC},
     semantics::{BaseType, CopyMoveType, CopyOnlyType, DefaultUninit, MoveType},
 };
 
 pub mod into_iter;
 #[cfg(feature = "msvc2012")]
 pub mod msvc2012;
+pub mod vec_bool;
+pub mod view;
+
+/// `std::pmr::vector`.
+pub mod pmr {
+    use crate::pmr::PmrAlloc;
+
+    pub type CxxVec<'a, T> = super::CxxVec<T, PmrAlloc<'a>>;
+}
 
-pub type CxxVec<T, A = SysAlloc> = CxxVecLayout<T, A, Layout<A>>;
+pub type CxxVec<T, A = DefaultAlloc> = CxxVecLayout<T, A, Layout<A>>;
 
 #[repr(C)]
 pub struct Layout<A: CxxProxy> {
@@ -56,10 +72,10 @@ impl<A: CxxProxy> Layout<A> {
     }
 }
 
-impl<T> CxxVec<T, SysAlloc> {
+impl<T> CxxVec<T, DefaultAlloc> {
     pub const fn new() -> Self {
         Self {
-            inner: Layout::new_in(SysAlloc),
+            inner: Layout::new_in(DEFAULT_ALLOC),
             _marker: PhantomData,
         }
     }
@@ -83,6 +99,10 @@ where
     A: CxxProxy,
     L: WithCxxProxy<Alloc = A, Value = RawVec>,
 {
+    /// Moves the elements of `vec` one by one into storage allocated by `alloc`.
+    ///
+    /// The storage of `vec` is never reused, since its allocator may be
+    /// of another type. See [`CxxVecLayout::move_assign`] for a move that reuses it.
     pub fn from_vec_in<L2, A2>(vec: CxxVecLayout<T, A2, L2>, alloc: A) -> Self
     where
         L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
@@ -120,6 +140,46 @@ where
         CxxVecLayout::from_vec_in(self, alloc)
     }
 
+    /// Creates a vector from a raw `std::vector` value and its allocator,
+    /// taking ownership of the elements and the backing allocation.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must describe a valid `std::vector<T>` whose backing allocation
+    /// can be freed by `alloc`.
+    pub unsafe fn from_raw_parts_in(raw: RawVec, alloc: A) -> Self {
+        let mut new = Self::from_alloc(alloc);
+        *new.inner.value_as_mut() = raw;
+        new
+    }
+
+    /// Checks `raw` with [`validate`] before creating a vector from it
+    /// with [`CxxVecLayout::from_raw_parts_in`].
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxVecLayout::from_raw_parts_in`], except that
+    /// `raw` may break the container invariants.
+    pub unsafe fn try_from_raw_parts_in(raw: RawVec, alloc: A) -> Result<Self, RawVecError> {
+        validate::<T>(&raw)?;
+        unsafe { Ok(Self::from_raw_parts_in(raw, alloc)) }
+    }
+
+    /// Decomposes the vector into a raw `std::vector` value and its allocator,
+    /// handing ownership of the elements and the backing allocation to the caller.
+    pub fn into_raw_parts(self) -> (RawVec, A) {
+        let vec = ManuallyDrop::new(self);
+
+        unsafe {
+            let alloc = ptr::read(vec.inner.alloc_as_ref());
+            (*vec.inner.value_as_ref(), alloc)
+        }
+    }
+
+    /// Moves the elements of `vec` to new storage allocated by `alloc`.
+    ///
+    /// With the `nightly` feature, vectors using an `AllocatorProxy`
+    /// can take the storage of a `Vec` instead.
     pub fn from_rust_vec_in(vec: Vec<T>, alloc: A) -> Self {
         let mut new = Self::from_alloc(alloc);
         let mut drained = vec;
@@ -215,7 +275,7 @@ where
     }
 
     pub fn is_empty(&self) -> bool {
-        self.first_ptr() == self.end_ptr()
+        self.first_ptr() == self.last_ptr()
     }
 
     pub fn capacity(&self) -> usize {
@@ -295,7 +355,7 @@ where
         }
 
         unsafe {
-            let removed = self.first_ptr().offset(index as isize).read();
+            let removed = self.first_ptr().add(index).read();
 
             let pos = CSTL_vector_iterator_add(
                 CSTL_vector_begin(self.inner.value_as_ref(), <T as BaseType>::TYPE),
@@ -318,30 +378,6 @@ where
         }
     }
 
-    pub fn resize(&mut self, new_len: usize, value: T)
-    where
-        T: Clone,
-    {
-        if new_len > isize::MAX as usize {
-            panic!("requested length ({new_len}) exceeded `isize::MAX`");
-        }
-
-        if new_len > self.len() {
-            self.inner.with_proxy_mut(|val, alloc| unsafe {
-                CSTL_vector_resize(
-                    val,
-                    <T as BaseType>::TYPE,
-                    &<DefaultUninit<T> as CopyMoveType>::COPY,
-                    new_len,
-                    &value as *const T as _,
-                    alloc,
-                );
-            });
-        } else {
-            self.truncate(new_len);
-        }
-    }
-
     pub fn resize_with<F>(&mut self, new_len: usize, mut f: F)
     where
         F: FnMut() -> T,
@@ -402,6 +438,151 @@ where
             );
         });
     }
+
+    /// Replaces the elements with those of `other`, like move assignment.
+    ///
+    /// Takes the storage of `other` if the allocator propagates or is equal
+    /// to the allocator of `other`, and moves the elements one by one otherwise.
+    pub fn move_assign(&mut self, other: Self) {
+        if A::PROPAGATE_ON_MOVE_ASSIGNMENT {
+            *self = other;
+            return;
+        }
+
+        let mut other = other;
+
+        if self.alloc_eq(&other) {
+            other.inner.with_proxy_mut(|other_val, other_alloc| {
+                self.inner.with_proxy_mut(|val, alloc| unsafe {
+                    CSTL_vector_move_assign(
+                        val,
+                        <T as BaseType>::TYPE,
+                        &<DefaultUninit<T> as MoveType>::MOVE,
+                        other_val,
+                        alloc,
+                        other_alloc,
+                        true,
+                    );
+                })
+            });
+        } else {
+            let Range { start, end } = other.as_mut_ptr_range();
+
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                let moved = CSTL_vector_move_assign_range(
+                    val,
+                    <T as BaseType>::TYPE,
+                    &<DefaultUninit<T> as MoveType>::MOVE,
+                    start as _,
+                    end as _,
+                    alloc,
+                );
+
+                if moved {
+                    other.inner.value_as_mut().last = other.inner.value_as_mut().first;
+                }
+            });
+        }
+    }
+
+    /// Swaps the elements with those of `other`, and the allocators if they propagate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the allocators do not propagate and are not equal,
+    /// which is undefined behavior in C++.
+    pub fn swap_with(&mut self, other: &mut Self) {
+        if A::PROPAGATE_ON_SWAP {
+            mem::swap(&mut self.inner, &mut other.inner);
+            return;
+        }
+
+        assert!(
+            self.alloc_eq(other),
+            "vectors with unequal allocators cannot be swapped"
+        );
+
+        unsafe {
+            CSTL_vector_swap(self.inner.value_as_mut(), other.inner.value_as_mut());
+        }
+    }
+}
+
+impl<T, A, L> CxxVecLayout<T, A, L>
+where
+    T: Clone,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Resizes the vector, copying `value` into new elements.
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        if new_len > isize::MAX as usize {
+            panic!("requested length ({new_len}) exceeded `isize::MAX`");
+        }
+
+        if new_len > self.len() {
+            self.inner.with_proxy_mut(|val, alloc| unsafe {
+                CSTL_vector_resize(
+                    val,
+                    <T as BaseType>::TYPE,
+                    &<DefaultUninit<T> as CopyMoveType>::COPY,
+                    new_len,
+                    &value as *const T as _,
+                    alloc,
+                );
+            });
+        } else {
+            self.truncate(new_len);
+        }
+    }
+}
+
+impl<T, A, L> CxxVecLayout<T, ScopedAlloc<A>, L>
+where
+    T: UsesAllocator<A>,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = ScopedAlloc<A>, Value = RawVec>,
+{
+    /// Resizes the vector, copying `value` into new elements with the inner allocator.
+    pub fn resize(&mut self, new_len: usize, value: T) {
+        let alloc = self.inner_alloc();
+        self.resize_with(new_len, || value.clone_with(alloc.clone()));
+    }
+
+    /// Appends an empty element constructed with the inner allocator, `emplace_back()`.
+    pub fn emplace(&mut self) -> &mut T {
+        self.push(T::new_with(self.inner_alloc()));
+        self.last_mut().unwrap()
+    }
+
+    /// Resizes the vector, constructing new elements empty with the inner allocator, `resize(n)`.
+    pub fn resize_empty(&mut self, new_len: usize) {
+        let alloc = self.inner_alloc();
+        self.resize_with(new_len, || T::new_with(alloc.clone()));
+    }
+
+    /// Appends copies of the elements of `iter` made with the inner allocator.
+    pub fn extend_scoped<'a, I>(&mut self, iter: I)
+    where
+        T: 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let alloc = self.inner_alloc();
+        let iter = iter.into_iter();
+
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|e| self.push(e.clone_with(alloc.clone())));
+    }
+
+    fn clone_scoped_in(&self, alloc: ScopedAlloc<A>) -> Self {
+        let mut new = Self::from_alloc(alloc);
+        new.extend_scoped(self.iter());
+        new
+    }
+
+    fn inner_alloc(&self) -> A {
+        self.inner.alloc_as_ref().0.clone()
+    }
 }
 
 impl<T, A, L> CxxVecLayout<T, A, L>
@@ -416,6 +597,20 @@ where
         }
     }
 
+    fn alloc_eq(&self, other: &Self) -> bool {
+        A::IS_ALWAYS_EQUAL
+            || self
+                .inner
+                .alloc_as_ref()
+                .is_equal(other.inner.alloc_as_ref())
+    }
+
+    /// Replaces the allocator, keeping the storage.
+    fn replace_alloc(&mut self, alloc: A) {
+        let old = mem::replace(&mut self.inner, L::new_in(alloc));
+        *self.inner.value_as_mut() = *old.value_as_ref();
+    }
+
     fn first_ptr(&self) -> *const T {
         self.inner.value_as_ref().first as _
     }
@@ -536,6 +731,62 @@ where
     }
 }
 
+#[cfg(feature = "nightly")]
+impl<T, B, L> CxxVecLayout<T, AllocatorProxy<B>, L>
+where
+    B: Allocator + Clone,
+    L: WithCxxProxy<Alloc = AllocatorProxy<B>, Value = RawVec>,
+{
+    /// Takes the storage of `vec` without moving the elements,
+    /// unless `T` is zero-sized, which `std::vector` stores differently.
+    pub fn from_rust_vec_with_alloc(vec: Vec<T, B>) -> Self {
+        if mem::size_of::<T>() == 0 || vec.capacity() == 0 {
+            let mut new = Self::from_alloc(AllocatorProxy(vec.allocator().clone()));
+            new.extend(vec);
+            return new;
+        }
+
+        let mut vec = ManuallyDrop::new(vec);
+        let alloc = unsafe { ptr::read(vec.allocator()) };
+        let first = vec.as_mut_ptr();
+
+        unsafe {
+            let raw = RawVec {
+                first: first.cast(),
+                last: first.add(vec.len()).cast(),
+                end: first.add(vec.capacity()).cast(),
+            };
+
+            Self::from_raw_parts_in(raw, AllocatorProxy(alloc))
+        }
+    }
+
+    /// Hands the storage to a `Vec` without moving the elements,
+    /// unless `T` is zero-sized, which `std::vector` stores differently.
+    pub fn into_rust_vec_in(self) -> Vec<T, B> {
+        let (raw, AllocatorProxy(alloc)) = self.into_raw_parts();
+        let first = raw.first.cast::<T>();
+
+        if first.is_null() {
+            return Vec::new_in(alloc);
+        }
+
+        unsafe {
+            if mem::size_of::<T>() == 0 {
+                let vec = Self::from_raw_parts_in(raw, AllocatorProxy(alloc.clone()));
+                let mut new = Vec::new_in(alloc);
+                new.extend(vec.into_rust_vec());
+                return new;
+            }
+
+            let len = raw.last.cast::<T>().offset_from(first) as usize;
+            let capacity = raw.end.cast::<T>().offset_from(first) as usize;
+
+            Vec::from_raw_parts_in(first, len, capacity, alloc)
+        }
+    }
+}
+
 impl<T, A, L> Drop for CxxVecLayout<T, A, L>
 where
     A: CxxProxy,
@@ -573,6 +824,91 @@ where
 
         new
     }
+
+    /// Copies the elements of `source`, and the allocator if it propagates.
+    ///
+    /// Storage is freed first if the allocators are not equal.
+    fn clone_from(&mut self, source: &Self) {
+        let propagate = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+
+        if propagate && !self.alloc_eq(source) {
+            *self = Self::from_alloc(source.inner.alloc_as_ref().clone());
+        } else if propagate {
+            self.replace_alloc(source.inner.alloc_as_ref().clone());
+        }
+
+        self.inner.with_proxy_mut(|val, alloc| unsafe {
+            let Range { start, end } = source.as_ptr_range();
+
+            CSTL_vector_copy_assign_range(
+                val,
+                <T as BaseType>::TYPE,
+                &<T as CopyOnlyType>::COPY,
+                start as _,
+                end as _,
+                alloc,
+            );
+        });
+    }
+}
+
+/// Copies the elements with the inner allocator.
+impl<T, A, L> Clone for CxxVecLayout<T, ScopedAlloc<A>, L>
+where
+    T: UsesAllocator<A>,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = ScopedAlloc<A>, Value = RawVec>,
+{
+    fn clone(&self) -> Self {
+        self.clone_scoped_in(ScopedAlloc(self.inner_alloc()))
+    }
+
+    /// Copies the elements of `source`, and the allocator if it propagates.
+    ///
+    /// Storage is freed first if the allocators are not equal.
+    fn clone_from(&mut self, source: &Self) {
+        let propagate = A::PROPAGATE_ON_COPY_ASSIGNMENT;
+
+        if propagate && !self.alloc_eq(source) {
+            *self = Self::from_alloc(ScopedAlloc(source.inner_alloc()));
+        } else if propagate {
+            self.replace_alloc(ScopedAlloc(source.inner_alloc()));
+        }
+
+        self.clear();
+        self.extend_scoped(source.iter());
+    }
+}
+
+impl<T, A, L> UsesAllocator<A> for CxxVecLayout<T, A, L>
+where
+    T: Clone,
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn new_with(alloc: A) -> Self {
+        Self::from_alloc(alloc)
+    }
+
+    fn clone_with(&self, alloc: A) -> Self {
+        Self::from_slice_in(self, alloc)
+    }
+}
+
+/// Passes the allocator on to the elements.
+impl<T, A, L> UsesAllocator<A> for CxxVecLayout<T, ScopedAlloc<A>, L>
+where
+    T: UsesAllocator<A>,
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = ScopedAlloc<A>, Value = RawVec>,
+{
+    fn new_with(alloc: A) -> Self {
+        Self::from_alloc(ScopedAlloc(alloc))
+    }
+
+    fn clone_with(&self, alloc: A) -> Self {
+        self.clone_scoped_in(ScopedAlloc(alloc))
+    }
 }
 
 impl<T, I, A, L> Index<I> for CxxVecLayout<T, A, L>
@@ -643,7 +979,7 @@ where
     L1: WithCxxProxy<Alloc = A1, Value = RawVec>,
     L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
 {
-    fn partial_cmp(&self, other: &CxxVecLayout<T, A2, L2>) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &CxxVecLayout<T, A2, L2>) -> Option<core::cmp::Ordering> {
         PartialOrd::partial_cmp(&**self, &**other)
     }
 }
@@ -662,7 +998,7 @@ where
     A: CxxProxy,
     L: WithCxxProxy<Alloc = A, Value = RawVec>,
 {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         Ord::cmp(&**self, &**other)
     }
 }
@@ -754,6 +1090,83 @@ where
 {
 }
 
+/// Error returned when a raw `std::vector` value breaks the container invariants.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawVecError {
+    /// Some, but not all of the pointers are null.
+    NullMismatch,
+    /// The pointers are not ordered as `first <= last <= end`.
+    Misordered,
+    /// `first` is not aligned to the element alignment.
+    Misaligned { align: usize },
+    /// The length or capacity in bytes is not a multiple of the element size.
+    SizeNotMultiple { size: usize },
+}
+
+impl fmt::Display for RawVecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NullMismatch => f.write_str("vector pointers are partially null"),
+            Self::Misordered => f.write_str("vector pointers are not ordered"),
+            Self::Misaligned { align } => {
+                write!(f, "vector storage is not aligned to {align} bytes")
+            }
+            Self::SizeNotMultiple { size } => {
+                write!(f, "vector storage is not a multiple of {size} bytes")
+            }
+        }
+    }
+}
+
+impl core::error::Error for RawVecError {}
+
+/// Checks that `raw` upholds the `std::vector<T>` invariants.
+///
+/// Does not access the elements, so it can be used on garbage values.
+pub fn validate<T>(raw: &RawVec) -> Result<(), RawVecError> {
+    validate_parts(
+        raw.first as u64,
+        raw.last as u64,
+        raw.end as u64,
+        <T as BaseType>::SIZE,
+        <T as BaseType>::ALIGN,
+        isize::MAX as u64,
+    )
+}
+
+/// Checks the `std::vector` invariants for pointers given as addresses,
+/// `max` being the largest allowed capacity in bytes.
+pub(crate) fn validate_parts(
+    first: u64,
+    last: u64,
+    end: u64,
+    size: usize,
+    align: usize,
+    max: u64,
+) -> Result<(), RawVecError> {
+    if first == 0 || last == 0 || end == 0 {
+        return if first == 0 && last == 0 && end == 0 {
+            Ok(())
+        } else {
+            Err(RawVecError::NullMismatch)
+        };
+    }
+
+    if first > last || last > end || end - first > max {
+        return Err(RawVecError::Misordered);
+    }
+
+    if !first.is_multiple_of(align as u64) {
+        return Err(RawVecError::Misaligned { align });
+    }
+
+    if !(last - first).is_multiple_of(size as u64) || !(end - first).is_multiple_of(size as u64) {
+        return Err(RawVecError::SizeNotMultiple { size });
+    }
+
+    Ok(())
+}
+
 const fn new_val() -> RawVec {
     RawVec {
         first: ptr::null_mut(),
@@ -785,3 +1198,96 @@ impl<A: CxxProxy> WithCxxProxy for Layout<A> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "nightly")]
+    use rust_alloc::alloc::Global;
+
+    use super::*;
+
+    #[cfg(feature = "nightly")]
+    #[test]
+    fn rust_vec_storage_survives_round_trip() {
+        let mut vec = Vec::with_capacity_in(10, Global);
+        vec.extend([1u32, 2, 3]);
+        let ptr = vec.as_ptr();
+
+        let cxx = CxxVec::<u32, AllocatorProxy<Global>>::from_rust_vec_with_alloc(vec);
+        assert_eq!(cxx.as_ptr(), ptr);
+        assert_eq!(cxx.capacity(), 10);
+        assert_eq!(cxx.as_slice(), [1, 2, 3]);
+
+        let vec = cxx.into_rust_vec_in();
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec.capacity(), 10);
+        assert_eq!(vec, [1, 2, 3]);
+    }
+
+    #[test]
+    fn empty_with_spare_capacity() {
+        let mut vec = CxxVec::<u32>::new();
+        vec.reserve(4);
+
+        assert!(vec.is_empty());
+        assert_eq!(vec.pop(), None);
+
+        vec.push(1);
+        assert!(!vec.is_empty());
+        assert_eq!(vec.pop(), Some(1));
+        assert!(vec.is_empty());
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn validate_accepts_valid_vectors() {
+        assert_eq!(validate::<u32>(&new_val()), Ok(()));
+        assert_eq!(
+            validate_parts(0x1000, 0x1008, 0x1010, 4, 4, u64::MAX),
+            Ok(())
+        );
+
+        let vec = CxxVec::from_slice_in(&[1u64, 2, 3], DEFAULT_ALLOC);
+        assert_eq!(validate::<u64>(vec.inner.value_as_ref()), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_broken_vectors() {
+        assert_eq!(
+            validate_parts(0, 0x1000, 0x1000, 4, 4, u64::MAX),
+            Err(RawVecError::NullMismatch)
+        );
+        assert_eq!(
+            validate_parts(0x1008, 0x1000, 0x1010, 4, 4, u64::MAX),
+            Err(RawVecError::Misordered)
+        );
+        assert_eq!(
+            validate_parts(0x1000, 0x1010, 0x1008, 4, 4, u64::MAX),
+            Err(RawVecError::Misordered)
+        );
+        assert_eq!(
+            validate_parts(0x1000, 0x1000, 0x2000, 4, 4, 0x800),
+            Err(RawVecError::Misordered)
+        );
+        assert_eq!(
+            validate_parts(0x1002, 0x1002, 0x1002, 4, 4, u64::MAX),
+            Err(RawVecError::Misaligned { align: 4 })
+        );
+        assert_eq!(
+            validate_parts(0x1000, 0x1006, 0x1010, 4, 4, u64::MAX),
+            Err(RawVecError::SizeNotMultiple { size: 4 })
+        );
+    }
+
+    #[test]
+    fn checked_raw_parts_reject_broken_vectors() {
+        let raw = RawVec {
+            first: 0x1000 as _,
+            last: ptr::null_mut(),
+            end: 0x1000 as _,
+        };
+
+        let vec = unsafe { CxxVec::<u32>::try_from_raw_parts_in(raw, DEFAULT_ALLOC) };
+        assert_eq!(vec.err(), Some(RawVecError::NullMismatch));
+    }
+}
@@ -1,12 +1,12 @@
-use std::marker::PhantomData;
+use core::marker::PhantomData;
 
 use cstl_sys::CSTL_VectorVal as RawVec;
 
 use crate::alloc::{CxxProxy, WithCxxProxy};
 
-use super::{new_val, CxxVecLayout, SysAlloc};
+use super::{new_val, CxxVecLayout, DefaultAlloc, DEFAULT_ALLOC};
 
-pub type CxxVec<T, A = SysAlloc> = CxxVecLayout<T, A, Layout<A>>;
+pub type CxxVec<T, A = DefaultAlloc> = CxxVecLayout<T, A, Layout<A>>;
 
 #[repr(C)]
 pub struct Layout<A: CxxProxy> {
@@ -23,10 +23,10 @@ impl<A: CxxProxy> Layout<A> {
     }
 }
 
-impl<T> CxxVec<T, SysAlloc> {
+impl<T> CxxVec<T, DefaultAlloc> {
     pub const fn new() -> Self {
         Self {
-            inner: Layout::new_in(SysAlloc),
+            inner: Layout::new_in(DEFAULT_ALLOC),
             _marker: PhantomData,
         }
     }
@@ -0,0 +1,494 @@
+//! `std::vector<bool>`, a bit-packed specialization of `std::vector`.
+
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    ops::{BitAndAssign, BitOrAssign, BitXorAssign, Not, Range},
+};
+
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc, WithCxxProxy},
+    vec::{CxxVec, CxxVecLayout, Layout, RawVec},
+};
+
+/// Number of bits in a word of `std::vector<bool>`.
+const WORD_BITS: usize = u32::BITS as usize;
+
+pub type CxxVecBool<A = DefaultAlloc> = CxxVecBoolLayout<A, Layout<A>>;
+
+/// `std::vector<bool>`.
+///
+/// The bits are packed into a `std::vector<unsigned int>`, followed by the
+/// number of bits. The bits past the end in the last word are always zero.
+#[repr(C)]
+pub struct CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    words: CxxVecLayout<u32, A, L>,
+    len: usize,
+}
+
+/// Iterator over the bits of a [`CxxVecBoolLayout`].
+#[derive(Clone)]
+pub struct Iter<'a> {
+    words: &'a [u32],
+    range: Range<usize>,
+}
+
+impl CxxVecBool<DefaultAlloc> {
+    pub const fn new() -> Self {
+        Self {
+            words: CxxVec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<A: CxxProxy> CxxVecBool<A> {
+    pub const fn new_in(alloc: A) -> Self {
+        Self {
+            words: CxxVec::new_in(alloc),
+            len: 0,
+        }
+    }
+
+    pub const fn allocator(&self) -> &A {
+        self.words.allocator()
+    }
+}
+
+impl<A, L> CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    /// Creates a vector of `len` copies of `value`.
+    pub fn from_elem_in(value: bool, len: usize, alloc: A) -> Self {
+        let mut new = Self::from_alloc(alloc);
+        new.resize(len, value);
+        new
+    }
+
+    /// Words holding the bits, least significant bit first.
+    pub fn as_words(&self) -> &[u32] {
+        self.words.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.words.capacity().saturating_mul(WORD_BITS)
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| get_bit(self.as_words(), index))
+    }
+
+    /// Sets the bit at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        let len = self.len;
+        assert!(index < len, "index {index} out of range for length {len}");
+
+        set_bit(self.words.as_mut_slice(), index, value);
+    }
+
+    pub fn first(&self) -> Option<bool> {
+        self.get(0)
+    }
+
+    pub fn last(&self) -> Option<bool> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+
+        self.len += 1;
+        self.set(self.len - 1, value);
+    }
+
+    pub fn pop(&mut self) -> Option<bool> {
+        let value = self.last()?;
+        self.truncate(self.len - 1);
+        Some(value)
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+        self.len = 0;
+    }
+
+    pub fn resize(&mut self, new_len: usize, value: bool) {
+        if new_len > self.len {
+            let fill = if value { u32::MAX } else { 0 };
+
+            if value && !self.len.is_multiple_of(WORD_BITS) {
+                let last = self.words.len() - 1;
+                self.words[last] |= fill << (self.len % WORD_BITS);
+            }
+
+            self.words.resize_with(words_for(new_len), || fill);
+            self.len = new_len;
+            self.trim();
+        } else {
+            self.truncate(new_len);
+        }
+    }
+
+    pub fn truncate(&mut self, new_len: usize) {
+        if new_len < self.len {
+            self.words.truncate(words_for(new_len));
+            self.len = new_len;
+            self.trim();
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more bits.
+    pub fn reserve(&mut self, additional: usize) {
+        let len = self.len.checked_add(additional).expect("capacity overflow");
+        let words = words_for(len).saturating_sub(self.words.len());
+        self.words.reserve(words);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.words.shrink_to_fit();
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: self.as_words(),
+            range: 0..self.len,
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.as_words()
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    /// Flips every bit.
+    pub fn flip(&mut self) {
+        self.words.iter_mut().for_each(|word| *word = !*word);
+        self.trim();
+    }
+
+    fn from_alloc(alloc: A) -> Self {
+        Self {
+            words: CxxVecLayout::from_alloc(alloc),
+            len: 0,
+        }
+    }
+
+    /// Clears the bits past the end in the last word.
+    fn trim(&mut self) {
+        let used = self.len % WORD_BITS;
+
+        if used != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+
+    fn zip_words<A2, L2, F>(&mut self, other: &CxxVecBoolLayout<A2, L2>, f: F)
+    where
+        A2: CxxProxy,
+        L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
+        F: Fn(&mut u32, u32),
+    {
+        assert_eq!(self.len, other.len, "vectors of bools differ in length");
+
+        self.words
+            .iter_mut()
+            .zip(other.as_words())
+            .for_each(|(word, &other)| f(word, other));
+    }
+}
+
+fn words_for(len: usize) -> usize {
+    len.div_ceil(WORD_BITS)
+}
+
+fn get_bit(words: &[u32], index: usize) -> bool {
+    words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+}
+
+fn set_bit(words: &mut [u32], index: usize, value: bool) {
+    let word = &mut words[index / WORD_BITS];
+    let mask = 1 << (index % WORD_BITS);
+
+    if value {
+        *word |= mask;
+    } else {
+        *word &= !mask;
+    }
+}
+
+impl<A, L> fmt::Debug for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<A, L> Default for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn default() -> Self {
+        Self::from_alloc(A::default())
+    }
+}
+
+impl<A, L> Clone for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy + Clone,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn clone(&self) -> Self {
+        Self {
+            words: self.words.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<A, L> Extend<bool> for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        iter.for_each(|value| self.push(value));
+    }
+}
+
+impl<'a, A, L> Extend<&'a bool> for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn extend<I: IntoIterator<Item = &'a bool>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied())
+    }
+}
+
+impl<A, L> FromIterator<bool> for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy + Default,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut new = Self::default();
+        new.extend(iter);
+        new
+    }
+}
+
+impl<A1, A2, L1, L2> PartialEq<CxxVecBoolLayout<A2, L2>> for CxxVecBoolLayout<A1, L1>
+where
+    A1: CxxProxy,
+    A2: CxxProxy,
+    L1: WithCxxProxy<Alloc = A1, Value = RawVec>,
+    L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
+{
+    fn eq(&self, other: &CxxVecBoolLayout<A2, L2>) -> bool {
+        self.len == other.len && self.as_words() == other.as_words()
+    }
+}
+
+impl<A, L> Eq for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+}
+
+impl<A, L> Hash for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        self.as_words().hash(state);
+    }
+}
+
+impl<A, L> Not for CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    type Output = Self;
+
+    fn not(mut self) -> Self::Output {
+        self.flip();
+        self
+    }
+}
+
+macro_rules! impl_bit_assign {
+    ($($trait:ident $fn:ident $op:tt),*) => {$(
+        /// # Panics
+        ///
+        /// If the vectors differ in length.
+        impl<A1, A2, L1, L2> $trait<&CxxVecBoolLayout<A2, L2>> for CxxVecBoolLayout<A1, L1>
+        where
+            A1: CxxProxy,
+            A2: CxxProxy,
+            L1: WithCxxProxy<Alloc = A1, Value = RawVec>,
+            L2: WithCxxProxy<Alloc = A2, Value = RawVec>,
+        {
+            fn $fn(&mut self, rhs: &CxxVecBoolLayout<A2, L2>) {
+                self.zip_words(rhs, |word, other| *word $op other);
+            }
+        }
+    )*};
+}
+
+impl_bit_assign!(
+    BitAndAssign bitand_assign &=,
+    BitOrAssign bitor_assign |=,
+    BitXorAssign bitxor_assign ^=
+);
+
+impl<'a, A, L> IntoIterator for &'a CxxVecBoolLayout<A, L>
+where
+    A: CxxProxy,
+    L: WithCxxProxy<Alloc = A, Value = RawVec>,
+{
+    type Item = bool;
+
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Iterator for Iter<'_> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range.next().map(|index| get_bit(self.words, index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.range.nth(n).map(|index| get_bit(self.words, index))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range
+            .next_back()
+            .map(|index| get_bit(self.words, index))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+impl fmt::Debug for Iter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::*;
+
+    #[test]
+    fn words_are_followed_by_length() {
+        assert_eq!(size_of::<CxxVecBool>(), 4 * size_of::<usize>());
+    }
+
+    #[test]
+    fn pushes_across_word_boundary() {
+        let mut bits = CxxVecBool::new();
+
+        for index in 0..33 {
+            bits.push(index % 3 == 0);
+        }
+
+        assert_eq!(bits.len(), 33);
+        assert_eq!(bits.as_words().len(), 2);
+        assert_eq!(bits.get(31), Some(false));
+        assert_eq!(bits.get(32), Some(false));
+        assert_eq!(bits.get(33), None);
+        assert_eq!(bits.count_ones(), 11);
+
+        bits.set(32, true);
+        assert_eq!(bits.as_words()[1], 1);
+        assert_eq!(bits.last(), Some(true));
+
+        assert_eq!(bits.pop(), Some(true));
+        assert_eq!(bits.as_words().len(), 1);
+        assert_eq!(bits.len(), 32);
+    }
+
+    #[test]
+    fn resizes_across_word_boundary() {
+        let mut bits = CxxVecBool::from_elem_in(false, 30, DefaultAlloc::default());
+
+        bits.resize(40, true);
+        assert_eq!(bits.as_words(), [0b11 << 30, 0xFF]);
+        assert_eq!(bits.count_ones(), 10);
+
+        bits.truncate(31);
+        assert_eq!(bits.as_words(), [1 << 30]);
+
+        bits.resize(64, false);
+        assert_eq!(bits.as_words(), [1 << 30, 0]);
+        assert_eq!(bits.iter().filter(|&bit| bit).count(), 1);
+    }
+
+    #[test]
+    fn flip_keeps_unused_bits_clear() {
+        let mut bits: CxxVecBool = [true, false, true].into_iter().collect();
+
+        bits.flip();
+        assert_eq!(bits.as_words(), [0b010]);
+        assert_eq!(bits.count_zeros(), 2);
+        assert_eq!(
+            !bits,
+            [true, false, true].into_iter().collect::<CxxVecBool>()
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Borrowed views over `std::vector` values owned by C++.
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use cstl_sys::CSTL_VectorVal as RawVec;
+
+use crate::{
+    alloc::{CxxProxy, DefaultAlloc},
+    vec::{validate, CxxVec, RawVecError},
+};
+
+/// Shared view over a foreign `std::vector<T>`.
+///
+/// The elements are never dropped and the backing allocation is never freed.
+pub struct CxxVecRef<'a, T, A: CxxProxy = DefaultAlloc> {
+    vec: ManuallyDrop<CxxVec<T, A>>,
+    _marker: PhantomData<&'a RawVec>,
+}
+
+/// Mutable view over a foreign `std::vector<T>`.
+///
+/// Modifications go through `alloc` and are written back to the foreign
+/// vector when the view is dropped. The foreign vector keeps ownership
+/// of the elements and the backing allocation.
+pub struct CxxVecMut<'a, T, A: CxxProxy = DefaultAlloc> {
+    raw: NonNull<RawVec>,
+    vec: ManuallyDrop<CxxVec<T, A>>,
+    _marker: PhantomData<&'a mut RawVec>,
+}
+
+impl<'a, T, A: CxxProxy> CxxVecRef<'a, T, A> {
+    /// Creates a shared view over the vector pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid `std::vector<T>` that is not mutated
+    /// for the duration of `'a`, and `alloc` must be compatible with the
+    /// allocator of that vector.
+    pub unsafe fn from_raw_in(raw: *const RawVec, alloc: A) -> Self {
+        unsafe {
+            Self {
+                vec: ManuallyDrop::new(CxxVec::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the vector pointed to by `raw` with [`validate`] before
+    /// creating a shared view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxVecRef::from_raw_in`], except that the vector
+    /// may break the container invariants.
+    pub unsafe fn try_from_raw_in(raw: *const RawVec, alloc: A) -> Result<Self, RawVecError> {
+        unsafe {
+            validate::<T>(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<'a, T, A: CxxProxy> CxxVecMut<'a, T, A> {
+    /// Creates a mutable view over the vector pointed to by `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a valid `std::vector<T>` that is not accessed
+    /// through any other pointer for the duration of `'a`, and `alloc` must
+    /// be able to free and reallocate the backing allocation of that vector.
+    ///
+    /// Leaking the view leaves the foreign vector in the state it was in
+    /// when the view was created, which may refer to freed memory.
+    pub unsafe fn from_raw_in(raw: *mut RawVec, alloc: A) -> Self {
+        unsafe {
+            let raw = NonNull::new(raw).expect("`raw` is null");
+
+            Self {
+                raw,
+                vec: ManuallyDrop::new(CxxVec::from_raw_parts_in(raw.read(), alloc)),
+                _marker: PhantomData,
+            }
+        }
+    }
+
+    /// Checks the vector pointed to by `raw` with [`validate`] before
+    /// creating a mutable view over it.
+    ///
+    /// # Safety
+    ///
+    /// The same as for [`CxxVecMut::from_raw_in`], except that the vector
+    /// may break the container invariants.
+    pub unsafe fn try_from_raw_in(raw: *mut RawVec, alloc: A) -> Result<Self, RawVecError> {
+        unsafe {
+            validate::<T>(&*raw)?;
+            Ok(Self::from_raw_in(raw, alloc))
+        }
+    }
+}
+
+impl<T, A: CxxProxy> Deref for CxxVecRef<'_, T, A> {
+    type Target = CxxVec<T, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.vec
+    }
+}
+
+impl<T, A: CxxProxy> Deref for CxxVecMut<'_, T, A> {
+    type Target = CxxVec<T, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.vec
+    }
+}
+
+impl<T, A: CxxProxy> DerefMut for CxxVecMut<'_, T, A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.vec
+    }
+}
+
+impl<T: fmt::Debug, A: CxxProxy> fmt::Debug for CxxVecRef<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: fmt::Debug, A: CxxProxy> fmt::Debug for CxxVecMut<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T, A: CxxProxy> Drop for CxxVecRef<'_, T, A> {
+    fn drop(&mut self) {
+        let (_, alloc) = unsafe { ManuallyDrop::take(&mut self.vec).into_raw_parts() };
+        drop(alloc);
+    }
+}
+
+impl<T, A: CxxProxy> Drop for CxxVecMut<'_, T, A> {
+    fn drop(&mut self) {
+        let (val, alloc) = unsafe { ManuallyDrop::take(&mut self.vec).into_raw_parts() };
+
+        unsafe {
+            self.raw.write(val);
+        }
+
+        drop(alloc);
+    }
+}
+
+unsafe impl<T: Sync, A: CxxProxy + Send> Send for CxxVecRef<'_, T, A> {}
+
+unsafe impl<T: Sync, A: CxxProxy + Sync> Sync for CxxVecRef<'_, T, A> {}
+
+unsafe impl<T: Send, A: CxxProxy + Send> Send for CxxVecMut<'_, T, A> {}
+
+unsafe impl<T: Sync, A: CxxProxy + Sync> Sync for CxxVecMut<'_, T, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::DEFAULT_ALLOC;
+
+    #[test]
+    fn raw_parts_round_trip() {
+        let vec = CxxVec::from_slice_in(&[1u32, 2, 3], DEFAULT_ALLOC);
+        let ptr = vec.as_ptr();
+
+        let (raw, alloc) = vec.into_raw_parts();
+        assert_eq!(raw.first.cast_const().cast(), ptr);
+
+        let vec = unsafe { CxxVec::<u32>::from_raw_parts_in(raw, alloc) };
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn shared_view_leaves_foreign_vector() {
+        let (raw, alloc) = CxxVec::from_slice_in(&[1u32, 2, 3], DEFAULT_ALLOC).into_raw_parts();
+
+        {
+            let view = unsafe { CxxVecRef::<u32>::from_raw_in(&raw, DEFAULT_ALLOC) };
+            assert_eq!(view.as_slice(), [1, 2, 3]);
+        }
+
+        let vec = unsafe { CxxVec::<u32>::from_raw_parts_in(raw, alloc) };
+        assert_eq!(vec.as_slice(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn mutable_view_writes_back() {
+        let (mut raw, alloc) = CxxVec::from_slice_in(&[1u32], DEFAULT_ALLOC).into_raw_parts();
+
+        {
+            let mut view = unsafe { CxxVecMut::<u32>::from_raw_in(&mut raw, DEFAULT_ALLOC) };
+            view.extend([2, 3, 4, 5]);
+            view[0] = 0;
+        }
+
+        let vec = unsafe { CxxVec::<u32>::from_raw_parts_in(raw, alloc) };
+        assert_eq!(vec.as_slice(), [0, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn checked_views_reject_broken_vectors() {
+        let mut raw = RawVec {
+            first: 8 as _,
+            last: 4 as _,
+            end: 16 as _,
+        };
+
+        let view = unsafe { CxxVecRef::<u32>::try_from_raw_in(&raw, DEFAULT_ALLOC) };
+        assert_eq!(view.err(), Some(RawVecError::Misordered));
+
+        let view = unsafe { CxxVecMut::<u32>::try_from_raw_in(&mut raw, DEFAULT_ALLOC) };
+        assert_eq!(view.err(), Some(RawVecError::Misordered));
+    }
+}